@@ -0,0 +1,67 @@
+pub mod reader;
+pub mod writer;
+
+pub use reader::BinaryReader;
+pub use writer::BinaryWriter;
+
+/// Magic of binary resources (`.scn`, `.res`)
+pub const MAGIC: &[u8; 4] = b"RSRC";
+/// Magic of compressed binary resources
+pub const MAGIC_COMPRESSED: &[u8; 4] = b"RSCC";
+/// Binary format version written by Godot 3.x
+pub const FORMAT_VERSION: u32 = 3;
+/// `SceneState` bundle version stored in `_bundled`
+pub const PACKED_SCENE_VERSION: isize = 2;
+
+pub(crate) const VARIANT_NIL: u32 = 1;
+pub(crate) const VARIANT_BOOL: u32 = 2;
+pub(crate) const VARIANT_INT: u32 = 3;
+pub(crate) const VARIANT_REAL: u32 = 4;
+pub(crate) const VARIANT_STRING: u32 = 5;
+pub(crate) const VARIANT_VECTOR2: u32 = 10;
+pub(crate) const VARIANT_RECT2: u32 = 11;
+pub(crate) const VARIANT_VECTOR3: u32 = 12;
+pub(crate) const VARIANT_PLANE: u32 = 13;
+pub(crate) const VARIANT_QUAT: u32 = 14;
+pub(crate) const VARIANT_AABB: u32 = 15;
+pub(crate) const VARIANT_MATRIX3: u32 = 16;
+pub(crate) const VARIANT_TRANSFORM: u32 = 17;
+pub(crate) const VARIANT_MATRIX32: u32 = 18;
+pub(crate) const VARIANT_COLOR: u32 = 20;
+pub(crate) const VARIANT_NODE_PATH: u32 = 22;
+pub(crate) const VARIANT_RID: u32 = 23;
+pub(crate) const VARIANT_OBJECT: u32 = 24;
+pub(crate) const VARIANT_DICTIONARY: u32 = 26;
+pub(crate) const VARIANT_ARRAY: u32 = 30;
+pub(crate) const VARIANT_RAW_ARRAY: u32 = 31;
+pub(crate) const VARIANT_INT_ARRAY: u32 = 32;
+pub(crate) const VARIANT_REAL_ARRAY: u32 = 33;
+pub(crate) const VARIANT_STRING_ARRAY: u32 = 34;
+pub(crate) const VARIANT_VECTOR3_ARRAY: u32 = 35;
+pub(crate) const VARIANT_COLOR_ARRAY: u32 = 36;
+pub(crate) const VARIANT_VECTOR2_ARRAY: u32 = 37;
+pub(crate) const VARIANT_INT64: u32 = 40;
+pub(crate) const VARIANT_DOUBLE: u32 = 41;
+
+pub(crate) const OBJECT_EMPTY: u32 = 0;
+pub(crate) const OBJECT_EXTERNAL_RESOURCE: u32 = 1;
+pub(crate) const OBJECT_INTERNAL_RESOURCE: u32 = 2;
+pub(crate) const OBJECT_EXTERNAL_RESOURCE_INDEX: u32 = 3;
+
+pub(crate) const NAME_INDEX_BITS: u32 = 18;
+pub(crate) const FLAG_ID_IS_PATH: isize = 1 << 30;
+pub(crate) const FLAG_INSTANCE_IS_PLACEHOLDER: isize = 1 << 30;
+pub(crate) const FLAG_MASK: isize = (1 << 24) - 1;
+pub(crate) const TYPE_INSTANCED: isize = 0x7FFF_FFFF;
+
+/// Variants without a `VarType` counterpart, kept in their text form (i.e. `Color( 1, 1, 1, 1 )`)
+/// as a list of reals: `(variant, constructor, reals per element, fixed size)`
+pub(crate) const REAL_CONSTRUCTORS: [(u32, &str, usize, bool); 7] = [
+    (VARIANT_VECTOR3, "Vector3", 3, true),
+    (VARIANT_PLANE, "Plane", 4, true),
+    (VARIANT_QUAT, "Quat", 4, true),
+    (VARIANT_AABB, "AABB", 6, true),
+    (VARIANT_COLOR, "Color", 4, true),
+    (VARIANT_VECTOR3_ARRAY, "PoolVector3Array", 3, false),
+    (VARIANT_COLOR_ARRAY, "PoolColorArray", 4, false),
+];
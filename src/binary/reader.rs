@@ -0,0 +1,729 @@
+use crate::binary::*;
+use crate::tscn_writer::TscnWriter;
+use crate::types::{Curve, Transform2D, Transform2DTrait, Transform3D, Transform3DTrait, VarType};
+use crate::{
+    Connection, ExtResourceEntry, NodeEntry, PropertyMap, PropertyTrait, Tscn, TEXT_FORMAT,
+};
+
+use indexmap::IndexMap;
+use nalgebra::{Matrix3, Vector2};
+use std::collections::HashMap;
+use std::convert::{TryFrom, TryInto};
+
+struct Resource {
+    rtype: String,
+    path: String,
+    properties: PropertyMap,
+}
+
+/// Decoder of Godot 3 binary resources (`RSRC` format)
+pub struct BinaryReader<'a> {
+    data: &'a [u8],
+    offset: usize,
+    big_endian: bool,
+    real64: bool,
+    strings: Vec<String>,
+}
+
+impl<'a> BinaryReader<'a> {
+    /// Checks file magic
+    pub fn is_binary(data: &[u8]) -> bool {
+        data.starts_with(MAGIC) || data.starts_with(MAGIC_COMPRESSED)
+    }
+
+    /// Decodes binary scene or resource, ext resources are listed but not loaded
+    pub fn read(data: &'a [u8]) -> Result<Tscn, String> {
+        let mut reader = BinaryReader {
+            data,
+            offset: 0,
+            big_endian: false,
+            real64: false,
+            strings: Vec::new(),
+        };
+
+        reader.read_tscn()
+    }
+
+    fn read_tscn(&mut self) -> Result<Tscn, String> {
+        let magic = self.read_bytes(4)?;
+        if magic == MAGIC_COMPRESSED {
+            return Err("Compressed binary resources are not supported".to_string());
+        }
+        if magic != MAGIC {
+            return Err("Not a binary resource".to_string());
+        }
+
+        self.big_endian = self.read_u32()? != 0;
+        self.real64 = self.read_u32()? != 0;
+
+        let _ver_major = self.read_u32()?;
+        let _ver_minor = self.read_u32()?;
+        let ver_format = self.read_u32()?;
+        if ver_format > FORMAT_VERSION {
+            return Err(format!("Unsupported binary format version {}", ver_format));
+        }
+
+        let main_type = self.read_unicode_string()?;
+        let _import_metadata = self.read_u64()?;
+        for _ in 0..14 {
+            self.read_u32()?;
+        }
+
+        let string_count = self.read_u32()?;
+        for _ in 0..string_count {
+            let string = self.read_unicode_string()?;
+            self.strings.push(string);
+        }
+
+        let mut ext_resource_entries = IndexMap::new();
+        let ext_count = self.read_u32()?;
+        for i in 0..ext_count as usize {
+            let rtype = self.read_unicode_string()?;
+            let path = self.read_unicode_string()?;
+
+            ext_resource_entries.insert(
+                i + 1,
+                ExtResourceEntry {
+                    path,
+                    rtype,
+                    uid: None,
+                },
+            );
+        }
+
+        let mut offsets: Vec<(String, u64)> = Vec::new();
+        let int_count = self.read_u32()?;
+        for _ in 0..int_count {
+            let path = self.read_unicode_string()?;
+            let offset = self.read_u64()?;
+            offsets.push((path, offset));
+        }
+
+        let mut resources: Vec<Resource> = Vec::new();
+        for (path, offset) in offsets {
+            self.offset = offset as usize;
+            resources.push(self.read_resource(path)?);
+        }
+
+        let main = resources.pop().ok_or("Missing main resource")?;
+        if main.rtype != main_type {
+            return Err("Main resource type mismatch".to_string());
+        }
+
+        let mut sub_resources = IndexMap::new();
+        for resource in resources {
+            let id = resource
+                .path
+                .rsplit("::")
+                .next()
+                .and_then(|p| p.trim_start_matches("local://").parse::<usize>().ok())
+                .ok_or(format!("Invalid sub resource path `{}`", resource.path))?;
+
+            sub_resources.insert(
+                id,
+                NodeEntry {
+                    properties: resource.properties,
+                    ..NodeEntry::new_type(&resource.rtype)
+                },
+            );
+        }
+
+        let mut tscn = Tscn {
+            rtype: main.rtype.clone(),
+            nodes: IndexMap::new(),
+            resource: HashMap::new(),
+            sub_resources,
+            ext_resources: HashMap::new(),
+            ext_resource_entries,
+            connections: Vec::new(),
+            // Godot 4 binaries have a newer format version and are rejected above
+            format: TEXT_FORMAT,
+            editable_instances: Vec::new(),
+        };
+
+        match (main.rtype.as_str(), main.properties.get("_bundled")) {
+            ("PackedScene", Some(VarType::Dict(bundled))) => {
+                tscn.rtype = "Scene".to_string();
+                Self::read_bundled(bundled, &mut tscn)?;
+            }
+            _ => tscn.resource = main.properties,
+        }
+
+        Ok(tscn)
+    }
+
+    fn read_resource(&mut self, path: String) -> Result<Resource, String> {
+        let rtype = self.read_unicode_string()?;
+        let mut properties = PropertyMap::new();
+
+        let count = self.read_u32()?;
+        for _ in 0..count {
+            let name = self.read_string()?;
+            let mut value = self.read_variant()?;
+
+            if rtype == "Curve" {
+                if let VarType::Arr(data) = &value {
+                    value = VarType::Curve(Curve::from_data(data));
+                }
+            }
+
+            properties.insert_to(name, value);
+        }
+
+        Ok(Resource {
+            rtype,
+            path,
+            properties,
+        })
+    }
+
+    /// Rebuilds node tree and connections from `SceneState` bundle
+    fn read_bundled(bundled: &HashMap<String, VarType>, tscn: &mut Tscn) -> Result<(), String> {
+        let version = Self::bundled_num(bundled, "version").unwrap_or(1);
+        if version > PACKED_SCENE_VERSION {
+            return Err(format!("Unsupported scene version {}", version));
+        }
+
+        let names = match bundled.get("names") {
+            Some(VarType::StrArr(names)) => names.clone(),
+            _ => Vec::new(),
+        };
+        let variants = match bundled.get("variants") {
+            Some(VarType::Arr(variants)) => variants.clone(),
+            _ => Vec::new(),
+        };
+        let node_paths: Vec<String> = match bundled.get("node_paths") {
+            Some(VarType::Arr(paths)) => paths.iter().map(Self::node_path_text).collect(),
+            _ => Vec::new(),
+        };
+
+        let name = |idx: isize| -> Result<String, String> {
+            names
+                .get(idx as usize)
+                .cloned()
+                .ok_or(format!("Invalid name index {}", idx))
+        };
+        let variant = |idx: isize| -> Result<VarType, String> {
+            variants
+                .get(idx as usize)
+                .cloned()
+                .ok_or(format!("Invalid variant index {}", idx))
+        };
+
+        let nodes = match bundled.get("nodes") {
+            Some(VarType::IntArr(nodes)) => nodes.clone(),
+            _ => Vec::new(),
+        };
+        let mut r = nodes.iter().copied();
+        let mut next = || r.next().ok_or("Unexpected end of scene nodes");
+
+        let node_count = Self::bundled_num(bundled, "node_count").unwrap_or(0);
+        for id in 0..node_count as usize {
+            let parent = next()?;
+            let _owner = next()?;
+            let rtype = next()?;
+            let name_index = next()?;
+            let name_index = if version >= 2 {
+                name_index & ((1 << NAME_INDEX_BITS) - 1)
+            } else {
+                name_index
+            };
+            let instance = next()?;
+
+            let mut entry = NodeEntry {
+                name: name(name_index)?,
+                rtype: if rtype == TYPE_INSTANCED {
+                    "".to_string()
+                } else {
+                    name(rtype)?
+                },
+                ..NodeEntry::default()
+            };
+
+            if instance >= 0 && instance & FLAG_INSTANCE_IS_PLACEHOLDER == 0 {
+                if let VarType::ExtResource(ext) = variant(instance & FLAG_MASK)? {
+                    entry.instance = ext;
+                }
+            }
+
+            let prop_count = next()?;
+            for _ in 0..prop_count {
+                let prop_name = name(next()?)?;
+                let value = variant(next()?)?;
+                entry.properties.insert_to(prop_name, value);
+            }
+
+            let group_count = next()?;
+            for _ in 0..group_count {
+                entry.groups.push(name(next()?)?);
+            }
+
+            if parent < 0 {
+                entry.path = format!("/{}", entry.name);
+                if let Some(VarType::ExtResource(ext)) = bundled
+                    .get("base_scene")
+                    .and_then(|idx| isize::try_from(idx).ok())
+                    .and_then(|idx| variants.get(idx as usize))
+                {
+                    entry.instance = *ext;
+                }
+            } else {
+                let parent_id = Self::resolve_node(tscn, parent, &node_paths)?;
+                let parent_entry = tscn
+                    .nodes
+                    .get_mut(&parent_id)
+                    .ok_or(format!("Invalid parent {}", parent))?;
+
+                parent_entry.childrens.push(id);
+                entry.path = format!("{}/{}", parent_entry.path, entry.name);
+                entry.level = parent_entry.level + 1;
+                entry.parent_id = parent_id;
+            }
+
+            tscn.nodes.insert(id, entry);
+        }
+
+        if let Some(VarType::Arr(paths)) = bundled.get("editable_instances") {
+            tscn.editable_instances = paths.iter().map(Self::node_path_text).collect();
+        }
+
+        let conns = match bundled.get("conns") {
+            Some(VarType::IntArr(conns)) => conns.clone(),
+            _ => Vec::new(),
+        };
+        let mut r = conns.iter().copied();
+        let mut next = || r.next().ok_or("Unexpected end of scene connections");
+
+        let conn_count = Self::bundled_num(bundled, "conn_count").unwrap_or(0);
+        for _ in 0..conn_count {
+            let from = Self::resolve_node(tscn, next()?, &node_paths)?;
+            let to = Self::resolve_node(tscn, next()?, &node_paths)?;
+            let missing = || "Invalid connection node".to_string();
+            let signal = name(next()?)?;
+            let method = name(next()?)?;
+            let flags = next()?;
+
+            let bind_count = next()?;
+            for _ in 0..bind_count {
+                next()?;
+            }
+
+            tscn.connections.push(Connection {
+                signal,
+                from: tscn.relative_path(from).ok_or_else(missing)?,
+                to: tscn.relative_path(to).ok_or_else(missing)?,
+                method,
+                flags: flags as usize,
+            });
+        }
+
+        Ok(())
+    }
+
+    fn bundled_num(bundled: &HashMap<String, VarType>, key: &str) -> Option<isize> {
+        bundled.get(key).and_then(|v| isize::try_from(v).ok())
+    }
+
+    /// Finds node by its index or, when flagged, by its path in `node_paths`
+    fn resolve_node(tscn: &Tscn, idx: isize, node_paths: &[String]) -> Result<usize, String> {
+        if idx & FLAG_ID_IS_PATH == 0 {
+            return Ok(idx as usize);
+        }
+
+        let path = node_paths
+            .get((idx & FLAG_MASK) as usize)
+            .ok_or(format!("Invalid node path index {}", idx))?;
+
+        tscn.find_node(path)
+            .ok_or(format!("Unresolved node path `{}`", path))
+    }
+
+    fn node_path_text(value: &VarType) -> String {
+        match value {
+            VarType::None(text) => text
+                .trim_start_matches("NodePath(\"")
+                .trim_end_matches("\")")
+                .to_string(),
+            _ => "".to_string(),
+        }
+    }
+
+    fn read_variant(&mut self) -> Result<VarType, String> {
+        let vtype = self.read_u32()?;
+
+        let value = match vtype {
+            VARIANT_NIL => VarType::None("null".to_string()),
+            VARIANT_BOOL => VarType::Bool(self.read_u32()? != 0),
+            VARIANT_INT => VarType::Num(self.read_u32()? as i32 as isize),
+            VARIANT_INT64 => VarType::Num(self.read_u64()? as i64 as isize),
+            VARIANT_REAL => VarType::Float(self.read_real()?),
+            VARIANT_DOUBLE => VarType::Float(self.read_f64()? as f32),
+            VARIANT_STRING => VarType::Str(self.read_unicode_string()?),
+            VARIANT_VECTOR2 => VarType::Vector(self.read_vector2()?),
+            VARIANT_RECT2 => VarType::Rect2([self.read_vector2()?, self.read_vector2()?]),
+            VARIANT_MATRIX32 => {
+                let mut reals = [0.0; 6];
+                for real in reals.iter_mut() {
+                    *real = self.read_real()?;
+                }
+
+                VarType::Transform2D(Transform2D::from_reals(reals))
+            }
+            VARIANT_MATRIX3 => {
+                let mut reals = [0.0; 9];
+                for real in reals.iter_mut() {
+                    *real = self.read_real()?;
+                }
+
+                VarType::Basis(Matrix3::from_row_slice(&reals))
+            }
+            VARIANT_TRANSFORM => {
+                let mut reals = [0.0; 12];
+                for real in reals.iter_mut() {
+                    *real = self.read_real()?;
+                }
+
+                VarType::Transform3D(Transform3D::from_reals(reals))
+            }
+            VARIANT_NODE_PATH => {
+                let name_count = self.read_u16()?;
+                let subname_count = self.read_u16()?;
+                let absolute = subname_count & 0x8000 != 0;
+
+                let mut names = Vec::new();
+                for _ in 0..name_count {
+                    names.push(self.read_string()?);
+                }
+
+                let mut path = names.join("/");
+                if absolute {
+                    path = format!("/{}", path);
+                }
+
+                for _ in 0..subname_count & 0x7FFF {
+                    path = format!("{}:{}", path, self.read_string()?);
+                }
+
+                VarType::None(format!("NodePath(\"{}\")", path))
+            }
+            VARIANT_OBJECT => match self.read_u32()? {
+                OBJECT_EMPTY => VarType::None("null".to_string()),
+                OBJECT_EXTERNAL_RESOURCE => {
+                    let _rtype = self.read_unicode_string()?;
+                    let path = self.read_unicode_string()?;
+
+                    VarType::None(format!("Resource( \"{}\" )", path))
+                }
+                OBJECT_INTERNAL_RESOURCE => VarType::SubResource(self.read_u32()? as usize),
+                OBJECT_EXTERNAL_RESOURCE_INDEX => {
+                    VarType::ExtResource(self.read_u32()? as usize + 1)
+                }
+                otype => return Err(format!("Unsupported object type {}", otype)),
+            },
+            VARIANT_DICTIONARY => {
+                let len = self.read_u32()? & 0x7FFF_FFFF;
+                let mut dict = HashMap::new();
+
+                for _ in 0..len {
+                    let key = match self.read_variant()? {
+                        VarType::Str(key) => key,
+                        key => TscnWriter::write_value(&key),
+                    };
+                    dict.insert(key, self.read_variant()?);
+                }
+
+                VarType::Dict(dict)
+            }
+            VARIANT_ARRAY => {
+                let len = self.read_u32()? & 0x7FFF_FFFF;
+                let mut arr = Vec::new();
+
+                for _ in 0..len {
+                    arr.push(self.read_variant()?);
+                }
+
+                // Arrays of dictionaries are only written as `[ { ... } ]` blocks
+                let is_arr_map =
+                    !arr.is_empty() && arr.iter().all(|v| matches!(v, VarType::Dict(_)));
+                if is_arr_map {
+                    let arr_map = arr
+                        .into_iter()
+                        .filter_map(|v| match v {
+                            VarType::Dict(map) => Some(map),
+                            _ => None,
+                        })
+                        .collect();
+
+                    VarType::ArrMap(arr_map)
+                } else {
+                    VarType::Arr(arr)
+                }
+            }
+            VARIANT_RAW_ARRAY => {
+                let len = self.read_u32()? as usize;
+                let bytes: Vec<String> = self
+                    .read_bytes(len)?
+                    .iter()
+                    .map(|b| b.to_string())
+                    .collect();
+                self.read_bytes((4 - len % 4) % 4)?;
+
+                VarType::None(format!("PoolByteArray( {} )", bytes.join(", ")))
+            }
+            VARIANT_INT_ARRAY => {
+                let len = self.read_u32()?;
+                let mut arr = Vec::new();
+
+                for _ in 0..len {
+                    arr.push(self.read_u32()? as i32 as isize);
+                }
+
+                VarType::IntArr(arr)
+            }
+            VARIANT_REAL_ARRAY => {
+                let len = self.read_u32()?;
+                let mut arr = Vec::new();
+
+                for _ in 0..len {
+                    arr.push(self.read_real()?);
+                }
+
+                VarType::FloatArr(arr)
+            }
+            VARIANT_STRING_ARRAY => {
+                let len = self.read_u32()?;
+                let mut arr = Vec::new();
+
+                for _ in 0..len {
+                    arr.push(self.read_unicode_string()?);
+                }
+
+                VarType::StrArr(arr)
+            }
+            VARIANT_VECTOR2_ARRAY => {
+                let len = self.read_u32()?;
+                let mut arr = Vec::new();
+
+                for _ in 0..len {
+                    arr.push(self.read_vector2()?);
+                }
+
+                VarType::VectorArr(arr)
+            }
+            VARIANT_RID => return Err("RID values can not be stored".to_string()),
+            _ => {
+                let (_, name, size, fixed) = REAL_CONSTRUCTORS
+                    .iter()
+                    .find(|(variant, ..)| *variant == vtype)
+                    .ok_or(format!("Unsupported variant type {}", vtype))?;
+
+                let len = if *fixed {
+                    *size
+                } else {
+                    self.read_u32()? as usize * size
+                };
+
+                let mut reals: Vec<String> = Vec::new();
+                for _ in 0..len {
+                    // Colors are always stored in single precision
+                    let real = if vtype == VARIANT_COLOR || vtype == VARIANT_COLOR_ARRAY {
+                        self.read_f32()?
+                    } else {
+                        self.read_real()?
+                    };
+                    reals.push(real.to_string());
+                }
+
+                VarType::None(format!("{}( {} )", name, reals.join(", ")))
+            }
+        };
+
+        Ok(value)
+    }
+
+    fn read_vector2(&mut self) -> Result<Vector2<f32>, String> {
+        Ok(Vector2::new(self.read_real()?, self.read_real()?))
+    }
+
+    /// Reads string table reference, or inline string when high bit is set
+    fn read_string(&mut self) -> Result<String, String> {
+        let id = self.read_u32()?;
+
+        if id & 0x8000_0000 != 0 {
+            let len = (id & 0x7FFF_FFFF) as usize;
+            let bytes = self.read_bytes(len)?;
+
+            return Ok(String::from_utf8_lossy(bytes)
+                .trim_end_matches('\0')
+                .to_string());
+        }
+
+        self.strings
+            .get(id as usize)
+            .cloned()
+            .ok_or(format!("Invalid string index {}", id))
+    }
+
+    fn read_unicode_string(&mut self) -> Result<String, String> {
+        let len = self.read_u32()? as usize;
+        let bytes = self.read_bytes(len)?;
+
+        Ok(String::from_utf8_lossy(bytes)
+            .trim_end_matches('\0')
+            .to_string())
+    }
+
+    fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let data: &'a [u8] = self.data;
+        let bytes = data
+            .get(self.offset..self.offset + len)
+            .ok_or("Unexpected end of binary resource")?;
+        self.offset += len;
+
+        Ok(bytes)
+    }
+
+    fn read_u16(&mut self) -> Result<u16, String> {
+        let bytes = self.read_bytes(2)?.try_into().unwrap();
+
+        Ok(if self.big_endian {
+            u16::from_be_bytes(bytes)
+        } else {
+            u16::from_le_bytes(bytes)
+        })
+    }
+
+    fn read_u32(&mut self) -> Result<u32, String> {
+        let bytes = self.read_bytes(4)?.try_into().unwrap();
+
+        Ok(if self.big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        })
+    }
+
+    fn read_u64(&mut self) -> Result<u64, String> {
+        let bytes = self.read_bytes(8)?.try_into().unwrap();
+
+        Ok(if self.big_endian {
+            u64::from_be_bytes(bytes)
+        } else {
+            u64::from_le_bytes(bytes)
+        })
+    }
+
+    fn read_f32(&mut self) -> Result<f32, String> {
+        Ok(f32::from_bits(self.read_u32()?))
+    }
+
+    fn read_f64(&mut self) -> Result<f64, String> {
+        Ok(f64::from_bits(self.read_u64()?))
+    }
+
+    fn read_real(&mut self) -> Result<f32, String> {
+        if self.real64 {
+            Ok(self.read_f64()? as f32)
+        } else {
+            self.read_f32()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::binary::reader::*;
+
+    fn push_u32(data: &mut Vec<u8>, value: u32) {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn push_string(data: &mut Vec<u8>, string: &str) {
+        push_u32(data, string.len() as u32 + 1);
+        data.extend_from_slice(string.as_bytes());
+        data.push(0);
+    }
+
+    #[test]
+    fn test_read_resource() {
+        let mut data: Vec<u8> = MAGIC.to_vec();
+        for value in &[0, 0, 3, 2, FORMAT_VERSION] {
+            push_u32(&mut data, *value);
+        }
+        push_string(&mut data, "RectangleShape2D");
+        data.extend_from_slice(&[0; 8 + 14 * 4]);
+
+        push_u32(&mut data, 2);
+        push_string(&mut data, "extents");
+        push_string(&mut data, "custom_solver_bias");
+        push_u32(&mut data, 0);
+        push_u32(&mut data, 1);
+        push_string(&mut data, "res://shape.res");
+        let offset = data.len() as u64 + 8;
+        data.extend_from_slice(&offset.to_le_bytes());
+
+        push_string(&mut data, "RectangleShape2D");
+        push_u32(&mut data, 2);
+        push_u32(&mut data, 0);
+        push_u32(&mut data, VARIANT_VECTOR2);
+        data.extend_from_slice(&8f32.to_le_bytes());
+        data.extend_from_slice(&16f32.to_le_bytes());
+        push_u32(&mut data, 1);
+        push_u32(&mut data, VARIANT_REAL);
+        data.extend_from_slice(&0.5f32.to_le_bytes());
+        data.extend_from_slice(MAGIC);
+
+        let tscn = BinaryReader::read(&data).unwrap();
+        assert_eq!(tscn.rtype, "RectangleShape2D");
+        assert_eq!(
+            tscn.resource.get("extents"),
+            Some(&VarType::Vector(Vector2::new(8.0, 16.0)))
+        );
+        assert_eq!(
+            tscn.resource.get("custom_solver_bias"),
+            Some(&VarType::Float(0.5))
+        );
+        assert!(BinaryReader::read(b"[gd_resource]").is_err());
+    }
+
+    #[test]
+    fn test_read_bundled() {
+        let names = ["Level", "Node2D", "Player", "position", "players"];
+        let mut bundled: HashMap<String, VarType> = HashMap::new();
+        bundled.insert(
+            "names".to_string(),
+            VarType::StrArr(names.iter().map(|name| name.to_string()).collect()),
+        );
+        bundled.insert(
+            "variants".to_string(),
+            VarType::Arr(vec![VarType::Vector(Vector2::new(1.0, 2.0))]),
+        );
+        bundled.insert("node_count".to_string(), VarType::Num(2));
+        bundled.insert("version".to_string(), VarType::Num(PACKED_SCENE_VERSION));
+
+        let nodes = |parent: isize| {
+            VarType::IntArr(vec![
+                -1, -1, 1, 0, -1, 0, 0, // Level
+                parent, 0, 1, 2, -1, 1, 3, 0, 1, 4, // Player
+            ])
+        };
+
+        bundled.insert("nodes".to_string(), nodes(0));
+        let mut tscn = crate::Loader::new().parse_tscn("");
+        BinaryReader::read_bundled(&bundled, &mut tscn).unwrap();
+
+        assert_eq!(tscn.nodes[&0].childrens, vec![1]);
+        assert_eq!(tscn.nodes[&1].path, "/Level/Player");
+        assert_eq!(tscn.nodes[&1].groups, vec!["players"]);
+        assert_eq!(
+            tscn.nodes[&1].properties.get("position"),
+            Some(&VarType::Vector(Vector2::new(1.0, 2.0)))
+        );
+
+        bundled.insert("nodes".to_string(), nodes(5));
+        assert_eq!(
+            BinaryReader::read_bundled(&bundled, &mut crate::Loader::new().parse_tscn("")),
+            Err("Invalid parent 5".to_string())
+        );
+    }
+}
@@ -0,0 +1,531 @@
+use crate::binary::*;
+use crate::types::{Transform2DTrait, Transform3DTrait, VarType};
+use crate::{PropertyMap, PropertyTrait, Tscn};
+
+use indexmap::IndexSet;
+use regex::Regex;
+use std::collections::HashMap;
+
+lazy_static! {
+    static ref RE_CONSTRUCTOR: Regex =
+        Regex::new(r"^(\w+)\( ?(.*?) ?\)$").expect("Failed to read regex pattern");
+    static ref RE_NODE_PATH: Regex =
+        Regex::new(r#"^NodePath\("(.*)"\)$"#).expect("Failed to read regex pattern");
+}
+
+/// Encoder of Godot 3 binary resources (`RSRC` format)
+pub struct BinaryWriter<'a> {
+    tscn: &'a Tscn,
+    data: Vec<u8>,
+    strings: IndexSet<String>,
+}
+
+impl<'a> BinaryWriter<'a> {
+    const VERSION_MAJOR: u32 = 3;
+    const VERSION_MINOR: u32 = 2;
+
+    /// Encodes scene (saved as `PackedScene`) or resource
+    ///
+    /// Ext resources are stored by position, so their ids should be sequential starting from 1.
+    pub fn write(tscn: &'a Tscn) -> Result<Vec<u8>, String> {
+        let mut writer = BinaryWriter {
+            tscn,
+            data: Vec::new(),
+            strings: IndexSet::new(),
+        };
+
+        writer.write_tscn()
+    }
+
+    fn write_tscn(&mut self) -> Result<Vec<u8>, String> {
+        let tscn = self.tscn;
+
+        let (main_type, main_properties) = if tscn.rtype == "Scene" {
+            let mut properties = PropertyMap::new();
+            properties.insert("_bundled".to_string(), self.bundle_scene()?);
+
+            ("PackedScene".to_string(), properties)
+        } else {
+            (tscn.rtype.clone(), tscn.resource.clone())
+        };
+
+        let mut resources: Vec<(String, &str, &PropertyMap)> = Vec::new();
+        for (id, sub) in &tscn.sub_resources {
+            resources.push((format!("local://{}", id), &sub.rtype, &sub.properties));
+        }
+        let main_id = tscn.sub_resources.keys().max().map_or(0, |id| id + 1);
+        resources.push((format!("local://{}", main_id), &main_type, &main_properties));
+
+        for (_, _, properties) in &resources {
+            for (name, _) in properties.flatten() {
+                self.strings.insert(name);
+            }
+        }
+
+        self.data.extend_from_slice(MAGIC);
+        self.write_u32(0);
+        self.write_u32(0);
+        self.write_u32(Self::VERSION_MAJOR);
+        self.write_u32(Self::VERSION_MINOR);
+        self.write_u32(FORMAT_VERSION);
+        self.write_unicode_string(&main_type);
+        self.write_u64(0);
+        for _ in 0..14 {
+            self.write_u32(0);
+        }
+
+        self.write_u32(self.strings.len() as u32);
+        for string in self.strings.clone() {
+            self.write_unicode_string(&string);
+        }
+
+        self.write_u32(tscn.ext_resource_entries.len() as u32);
+        for ext in tscn.ext_resource_entries.values() {
+            self.write_unicode_string(&ext.rtype);
+            self.write_unicode_string(&ext.path);
+        }
+
+        self.write_u32(resources.len() as u32);
+        let mut offset_positions: Vec<usize> = Vec::new();
+        for (path, _, _) in &resources {
+            self.write_unicode_string(path);
+            offset_positions.push(self.data.len());
+            self.write_u64(0);
+        }
+
+        for ((_, rtype, properties), position) in resources.iter().zip(offset_positions) {
+            let offset = (self.data.len() as u64).to_le_bytes();
+            self.data[position..position + 8].copy_from_slice(&offset);
+
+            self.write_unicode_string(rtype);
+
+            let flattened = properties.flatten();
+            self.write_u32(flattened.len() as u32);
+            for (name, value) in flattened {
+                let index = self.strings.get_index_of(&name).unwrap();
+                self.write_u32(index as u32);
+                self.write_variant(value)?;
+            }
+        }
+
+        self.data.extend_from_slice(MAGIC);
+
+        Ok(std::mem::take(&mut self.data))
+    }
+
+    /// Builds `SceneState` bundle out of nodes and connections
+    fn bundle_scene(&self) -> Result<VarType, String> {
+        let tscn = self.tscn;
+
+        let mut names: IndexSet<String> = IndexSet::new();
+        let mut variants: Vec<VarType> = Vec::new();
+        let mut nodes: Vec<isize> = Vec::new();
+        let mut conns: Vec<isize> = Vec::new();
+        let mut bundled: HashMap<String, VarType> = HashMap::new();
+
+        let mut name = |name: &str| names.insert_full(name.to_string()).0 as isize;
+        let mut variant = |value: VarType| {
+            variants.push(value);
+            variants.len() as isize - 1
+        };
+
+        for (index, (_, node)) in tscn.nodes.iter().enumerate() {
+            let is_root = index == 0;
+            let parent = if is_root {
+                -1
+            } else {
+                tscn.nodes
+                    .get_index_of(&node.parent_id)
+                    .ok_or(format!("Missing parent of `{}`", node.path))? as isize
+            };
+
+            nodes.push(parent);
+            nodes.push(if is_root { -1 } else { 0 });
+            nodes.push(if node.rtype.is_empty() {
+                TYPE_INSTANCED
+            } else {
+                name(&node.rtype)
+            });
+            nodes.push(name(&node.name));
+
+            if node.instance != 0 && is_root {
+                let base_scene = variant(VarType::ExtResource(node.instance));
+                bundled.insert("base_scene".to_string(), VarType::Num(base_scene));
+                nodes.push(-1);
+            } else if node.instance != 0 {
+                nodes.push(variant(VarType::ExtResource(node.instance)));
+            } else {
+                nodes.push(-1);
+            }
+
+            let properties = node.properties.flatten();
+            nodes.push(properties.len() as isize);
+            for (prop_name, value) in properties {
+                nodes.push(name(&prop_name));
+                nodes.push(variant(value.clone()));
+            }
+
+            nodes.push(node.groups.len() as isize);
+            for group in &node.groups {
+                nodes.push(name(group));
+            }
+        }
+
+        for connection in &tscn.connections {
+            for path in &[&connection.from, &connection.to] {
+                let id = tscn
+                    .find_node(path)
+                    .ok_or(format!("Missing connection node `{}`", path))?;
+                conns.push(tscn.nodes.get_index_of(&id).unwrap() as isize);
+            }
+
+            conns.push(name(&connection.signal));
+            conns.push(name(&connection.method));
+            conns.push(connection.flags as isize);
+            conns.push(0);
+        }
+
+        let entries = vec![
+            ("names", VarType::StrArr(names.into_iter().collect())),
+            ("variants", VarType::Arr(variants)),
+            ("node_count", VarType::Num(tscn.nodes.len() as isize)),
+            ("nodes", VarType::IntArr(nodes)),
+            ("conn_count", VarType::Num(tscn.connections.len() as isize)),
+            ("conns", VarType::IntArr(conns)),
+            ("node_paths", VarType::Arr(Vec::new())),
+            (
+                "editable_instances",
+                VarType::Arr(
+                    tscn.editable_instances
+                        .iter()
+                        .map(|path| VarType::None(format!("NodePath(\"{}\")", path)))
+                        .collect(),
+                ),
+            ),
+            ("version", VarType::Num(PACKED_SCENE_VERSION)),
+        ];
+        for (key, value) in entries {
+            bundled.insert(key.to_string(), value);
+        }
+
+        Ok(VarType::Dict(bundled))
+    }
+
+    fn write_variant(&mut self, value: &VarType) -> Result<(), String> {
+        match value {
+            VarType::Num(num) => {
+                if *num > i32::MAX as isize || *num < i32::MIN as isize {
+                    self.write_u32(VARIANT_INT64);
+                    self.write_u64(*num as u64);
+                } else {
+                    self.write_u32(VARIANT_INT);
+                    self.write_u32(*num as i32 as u32);
+                }
+            }
+            VarType::Bool(boolean) => {
+                self.write_u32(VARIANT_BOOL);
+                self.write_u32(*boolean as u32);
+            }
+            VarType::Float(fl) => {
+                self.write_u32(VARIANT_REAL);
+                self.write_f32(*fl);
+            }
+            VarType::Str(string) => {
+                self.write_u32(VARIANT_STRING);
+                self.write_unicode_string(string);
+            }
+            VarType::Curve(curve) => {
+                self.write_variant(&VarType::Arr(curve.to_data()))?;
+            }
+            VarType::Rect2(rect) => {
+                self.write_u32(VARIANT_RECT2);
+                for real in &[rect[0].x, rect[0].y, rect[1].x, rect[1].y] {
+                    self.write_f32(*real);
+                }
+            }
+            VarType::Transform2D(transform) => {
+                self.write_u32(VARIANT_MATRIX32);
+                for real in &transform.to_reals() {
+                    self.write_f32(*real);
+                }
+            }
+            VarType::Basis(basis) => {
+                self.write_u32(VARIANT_MATRIX3);
+                for real in basis.transpose().iter() {
+                    self.write_f32(*real);
+                }
+            }
+            VarType::Transform3D(transform) => {
+                self.write_u32(VARIANT_TRANSFORM);
+                for real in &transform.to_reals() {
+                    self.write_f32(*real);
+                }
+            }
+            VarType::IntArr(arr) => {
+                self.write_u32(VARIANT_INT_ARRAY);
+                self.write_u32(arr.len() as u32);
+                for num in arr {
+                    self.write_u32(*num as i32 as u32);
+                }
+            }
+            VarType::StrArr(arr) => {
+                self.write_u32(VARIANT_STRING_ARRAY);
+                self.write_u32(arr.len() as u32);
+                for string in arr {
+                    self.write_unicode_string(string);
+                }
+            }
+            VarType::FloatArr(arr) => {
+                self.write_u32(VARIANT_REAL_ARRAY);
+                self.write_u32(arr.len() as u32);
+                for real in arr {
+                    self.write_f32(*real);
+                }
+            }
+            VarType::Vector(vector) => {
+                self.write_u32(VARIANT_VECTOR2);
+                self.write_f32(vector.x);
+                self.write_f32(vector.y);
+            }
+            VarType::VectorArr(arr) => {
+                self.write_u32(VARIANT_VECTOR2_ARRAY);
+                self.write_u32(arr.len() as u32);
+                for vector in arr {
+                    self.write_f32(vector.x);
+                    self.write_f32(vector.y);
+                }
+            }
+            VarType::ArrMap(arr_map) => {
+                self.write_u32(VARIANT_ARRAY);
+                self.write_u32(arr_map.len() as u32);
+                for map in arr_map {
+                    self.write_dictionary(map)?;
+                }
+            }
+            VarType::Map(map) | VarType::Dict(map) => self.write_dictionary(map)?,
+            VarType::SubResource(id) => {
+                self.write_u32(VARIANT_OBJECT);
+                self.write_u32(OBJECT_INTERNAL_RESOURCE);
+                self.write_u32(*id as u32);
+            }
+            VarType::ExtResource(id) => {
+                let index = self
+                    .tscn
+                    .ext_resource_entries
+                    .get_index_of(id)
+                    .ok_or(format!("Missing ext resource {}", id))?;
+
+                self.write_u32(VARIANT_OBJECT);
+                self.write_u32(OBJECT_EXTERNAL_RESOURCE_INDEX);
+                self.write_u32(index as u32);
+            }
+            VarType::Arr(arr) => {
+                self.write_u32(VARIANT_ARRAY);
+                self.write_u32(arr.len() as u32);
+                for value in arr {
+                    self.write_variant(value)?;
+                }
+            }
+            VarType::None(text) => self.write_text_variant(text)?,
+        }
+
+        Ok(())
+    }
+
+    fn write_dictionary(&mut self, map: &HashMap<String, VarType>) -> Result<(), String> {
+        let mut keys: Vec<&String> = map.keys().collect();
+        keys.sort();
+
+        self.write_u32(VARIANT_DICTIONARY);
+        self.write_u32(keys.len() as u32);
+        for key in keys {
+            self.write_u32(VARIANT_STRING);
+            self.write_unicode_string(key);
+            self.write_variant(&map[key])?;
+        }
+
+        Ok(())
+    }
+
+    /// Encodes values kept in their text form (i.e. `Color( 1, 1, 1, 1 )`)
+    fn write_text_variant(&mut self, text: &str) -> Result<(), String> {
+        if text == "null" {
+            self.write_u32(VARIANT_NIL);
+            return Ok(());
+        }
+
+        if let Some(caps) = RE_NODE_PATH.captures(text) {
+            let path = caps.get(1).unwrap().as_str();
+            let absolute = path.starts_with('/');
+            let mut split = path.trim_start_matches('/').split(':');
+            let names: Vec<&str> = split
+                .next()
+                .unwrap()
+                .split('/')
+                .filter(|n| !n.is_empty())
+                .collect();
+            let subnames: Vec<&str> = split.collect();
+
+            self.write_u32(VARIANT_NODE_PATH);
+            self.write_u16(names.len() as u16);
+            self.write_u16(subnames.len() as u16 | if absolute { 0x8000 } else { 0 });
+            for name in names.iter().chain(subnames.iter()) {
+                // Inline string, flagged by the high bit and NUL terminated
+                self.write_u32((name.len() as u32 + 1) | 0x8000_0000);
+                self.data.extend_from_slice(name.as_bytes());
+                self.data.push(0);
+            }
+
+            return Ok(());
+        }
+
+        let caps = RE_CONSTRUCTOR
+            .captures(text)
+            .ok_or(format!("Unsupported value `{}`", text))?;
+        let name = caps.get(1).unwrap().as_str();
+        let args: Vec<&str> = caps
+            .get(2)
+            .unwrap()
+            .as_str()
+            .split(", ")
+            .filter(|a| !a.trim().is_empty())
+            .collect();
+
+        if name == "PoolByteArray" {
+            let bytes = args
+                .iter()
+                .map(|a| a.parse::<u8>())
+                .collect::<Result<Vec<u8>, _>>()
+                .map_err(|e| e.to_string())?;
+
+            self.write_u32(VARIANT_RAW_ARRAY);
+            self.write_u32(bytes.len() as u32);
+            self.data.extend_from_slice(&bytes);
+            self.data
+                .resize(self.data.len() + (4 - bytes.len() % 4) % 4, 0);
+
+            return Ok(());
+        }
+
+        let (vtype, _, size, fixed) = REAL_CONSTRUCTORS
+            .iter()
+            .find(|(_, constructor, ..)| *constructor == name)
+            .ok_or(format!("Unsupported value `{}`", text))?;
+        let reals = args
+            .iter()
+            .map(|a| a.parse::<f32>())
+            .collect::<Result<Vec<f32>, _>>()
+            .map_err(|e| e.to_string())?;
+
+        if (*fixed && reals.len() != *size) || reals.len() % size != 0 {
+            return Err(format!("Invalid value `{}`", text));
+        }
+
+        self.write_u32(*vtype);
+        if !fixed {
+            self.write_u32((reals.len() / size) as u32);
+        }
+        for real in reals {
+            self.write_f32(real);
+        }
+
+        Ok(())
+    }
+
+    fn write_unicode_string(&mut self, string: &str) {
+        self.write_u32(string.len() as u32 + 1);
+        self.data.extend_from_slice(string.as_bytes());
+        self.data.push(0);
+    }
+
+    fn write_u16(&mut self, value: u16) {
+        self.data.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn write_u32(&mut self, value: u32) {
+        self.data.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn write_u64(&mut self, value: u64) {
+        self.data.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn write_f32(&mut self, value: f32) {
+        self.data.extend_from_slice(&value.to_le_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::binary::*;
+    use crate::Loader;
+
+    const SCENE: &str = r#"[gd_scene load_steps=4 format=2]
+
+[ext_resource path="res://icon.png" type="Texture" id=1]
+
+[ext_resource path="res://enemy.tscn" type="PackedScene" id=2]
+
+[sub_resource type="Curve" id=1]
+_data = [ Vector2( 0, 0 ), 0.0, 1.5, 0, 0, Vector2( 1, 1 ), 0.0, 0.0, 0, 0 ]
+
+[node name="Level" type="Node2D"]
+modulate = Color( 1, 0.5, 0, 1 )
+
+[node name="Player" type="KinematicBody2D" parent="."]
+collision/layer = 3
+position = Vector2( 32, 48.5 )
+speed = 120.0
+tags = PoolStringArray( "hero", "player" )
+target = NodePath("../Enemy")
+
+[node name="Sprite" type="Sprite" parent="Player"]
+region_rect = Rect2( 0, 0, 16, 16 )
+texture = ExtResource( 1 )
+
+[node name="Enemy" parent="." instance=ExtResource( 2 ) groups=[ "enemies" ]]
+falloff = SubResource( 1 )
+path = PoolVector2Array( 0, 0, 16, 8.25 )
+waypoints = [ 1, "two", Vector2( 3, 3 ) ]
+
+[node name="Label" type="Label" parent="."]
+__meta__ = {
+"_edit_lock_": true
+}
+text = "Hello"
+
+[connection signal="body_entered" from="Player" to="." method="_on_body_entered" flags=3]
+"#;
+
+    const RESOURCE: &str = r#"[gd_resource type="SpriteFrames" load_steps=2 format=2]
+
+[ext_resource path="res://icon.png" type="Texture" id=1]
+
+[resource]
+animations = [ {
+"frames": [ ExtResource( 1 ) ],
+"loop": true,
+"name": "default",
+"speed": 5.0
+} ]
+"#;
+
+    #[test]
+    fn test_binary_round_trip() {
+        for text in &[SCENE, RESOURCE] {
+            let tscn = Loader::new().parse_tscn(text);
+            let binary = tscn.to_binary().unwrap();
+
+            assert!(BinaryReader::is_binary(&binary));
+            if *text == SCENE {
+                // `..` of `NodePath("../Enemy")` is inlined with its NUL
+                let inline_name = [3, 0, 0, 0x80, b'.', b'.', 0];
+                assert!(binary.windows(7).any(|w| w == inline_name));
+            }
+
+            let decoded = BinaryReader::read(&binary).unwrap();
+            assert_eq!(decoded, tscn);
+            assert_eq!(decoded.to_tscn(), *text);
+        }
+    }
+}
@@ -0,0 +1,483 @@
+//! Offline validation of scenes against Godot's class database, loaded from
+//! `extension_api.json` (`godot --dump-extension-api`, Godot 4) or `api.json`
+//! (`godot --gdnative-generate-json-api`, Godot 3).
+
+use crate::types::{GDScript, VarType};
+use crate::{PropertyMap, PropertyTrait, Section, SourceMap, Tscn};
+
+use indexmap::IndexMap;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Property groups which are created dynamically, so aren't listed in the API
+const DYNAMIC_PREFIXES: &[&str] = &[
+    "_",
+    "metadata/",
+    "script",
+    "custom_",
+    "theme_override_",
+    "shader_param/",
+    "shader_parameter/",
+    "parameters/",
+    "tracks/",
+    "layer_",
+    "physics_layer_",
+    "navigation_layer_",
+    "occlusion_layer_",
+    "terrain_set_",
+    "custom_data_layer_",
+    "sources/",
+    "surfaces/",
+    "bones/",
+    "nodes/",
+    "states/",
+    "blend_point_",
+    "editor/",
+];
+
+/// Theme item keys, `<Type>/<data type>/<name>` or `<Type>/base_type`
+fn is_theme_item(property: &str) -> bool {
+    let parts: Vec<&str> = property.splitn(3, '/').collect();
+    if parts.iter().any(|part| part.is_empty()) {
+        return false;
+    }
+
+    match parts.as_slice() {
+        [_, "base_type"] => true,
+        [_, kind, _] => matches!(
+            *kind,
+            "colors" | "constants" | "fonts" | "font_sizes" | "icons" | "styles"
+        ),
+        _ => false,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClassInfo {
+    pub name: String,
+    pub inherits: Option<String>,
+    /// Property types by name
+    pub properties: IndexMap<String, String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ClassDb {
+    pub classes: HashMap<String, ClassInfo>,
+    /// Property prefixes which aren't validated (i.e. `custom_`, `tracks/`)
+    pub dynamic_prefixes: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DiagnosticKind {
+    UnknownClass(String),
+    UnknownProperty {
+        class: String,
+        property: String,
+    },
+    TypeMismatch {
+        property: String,
+        expected: String,
+        value: VarType,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub file: String,
+    /// 1-based line, `None` without source map
+    pub line: Option<usize>,
+    pub section: Section,
+    pub kind: DiagnosticKind,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{}:{}: ", self.file, line)?,
+            None => write!(f, "{}: ", self.file)?,
+        }
+
+        match &self.kind {
+            DiagnosticKind::UnknownClass(class) => write!(f, "unknown class `{}`", class),
+            DiagnosticKind::UnknownProperty { class, property } => {
+                write!(f, "`{}` has no property `{}`", class, property)
+            }
+            DiagnosticKind::TypeMismatch {
+                property,
+                expected,
+                value,
+            } => write!(
+                f,
+                "`{}` expects {}, got `{}`",
+                property,
+                expected,
+                crate::TscnWriter::write_value(value)
+            ),
+        }
+    }
+}
+
+impl ClassDb {
+    /// Reads `extension_api.json` (object with `classes`) or Godot 3 `api.json` (array)
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let api: Value = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        let classes = match &api {
+            Value::Object(api) => api.get("classes").and_then(Value::as_array),
+            Value::Array(classes) => Some(classes),
+            _ => None,
+        }
+        .ok_or("Missing classes")?;
+
+        let mut db = ClassDb {
+            dynamic_prefixes: DYNAMIC_PREFIXES.iter().map(|p| p.to_string()).collect(),
+            ..ClassDb::default()
+        };
+
+        for class in classes {
+            let name = class["name"].as_str().ok_or("Class without name")?;
+            let inherits = class
+                .get("inherits")
+                .or_else(|| class.get("base_class"))
+                .and_then(Value::as_str)
+                .filter(|base| !base.is_empty())
+                .map(str::to_string);
+
+            let properties = class
+                .get("properties")
+                .and_then(Value::as_array)
+                .map(|properties| {
+                    properties
+                        .iter()
+                        .filter_map(|property| {
+                            Some((
+                                property["name"].as_str()?.to_string(),
+                                property["type"].as_str()?.to_string(),
+                            ))
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+
+            db.classes.insert(
+                name.to_string(),
+                ClassInfo {
+                    name: name.to_string(),
+                    inherits,
+                    properties,
+                },
+            );
+        }
+
+        Ok(db)
+    }
+
+    pub fn class(&self, name: &str) -> Option<&ClassInfo> {
+        self.classes.get(name)
+    }
+
+    /// Whether `class` is `ancestor` or inherits it
+    pub fn inherits(&self, class: &str, ancestor: &str) -> bool {
+        self.ancestors(class).any(|info| info.name == ancestor)
+    }
+
+    /// Type of the property declared on the class or its ancestors
+    pub fn property_type(&self, class: &str, property: &str) -> Option<&str> {
+        self.ancestors(class)
+            .find_map(|info| info.properties.get(property))
+            .map(String::as_str)
+    }
+
+    /// Class followed by its ancestors
+    fn ancestors<'a>(&'a self, class: &str) -> impl Iterator<Item = &'a ClassInfo> + 'a {
+        let mut next = self.classes.get(class);
+        let mut depth = 0;
+
+        std::iter::from_fn(move || {
+            let info = next?;
+            depth += 1;
+            next = match &info.inherits {
+                Some(base) if depth < 64 => self.classes.get(base),
+                _ => None,
+            };
+
+            Some(info)
+        })
+    }
+
+    /// Validates nodes, sub resources and the resource of `tscn`, loaded from `file`. Lines are taken
+    /// from `source_map` (see [`crate::Loader::source_map`]).
+    ///
+    /// Properties of nodes with scripts are only checked against the script exports when
+    /// the script is loaded, otherwise unknown properties are not reported
+    pub fn validate(
+        &self,
+        tscn: &Tscn,
+        file: &str,
+        source_map: Option<&SourceMap>,
+    ) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+
+        // `.tres` resource
+        if !tscn.resource.is_empty() {
+            self.validate_entry(
+                tscn,
+                &tscn.resource,
+                &tscn.rtype,
+                Section::Resource,
+                None,
+                &mut diagnostics,
+            );
+        }
+
+        for (&id, sub) in &tscn.sub_resources {
+            self.validate_entry(
+                tscn,
+                &sub.properties,
+                &sub.rtype,
+                Section::SubResource(id),
+                None,
+                &mut diagnostics,
+            );
+        }
+
+        for (&id, node) in &tscn.nodes {
+            let script = tscn
+                .node_property(id, "script")
+                .map(|_| GDScript::from_node(tscn, id).ok());
+            self.validate_entry(
+                tscn,
+                &node.properties,
+                tscn.node_type(id),
+                Section::Node(id),
+                script,
+                &mut diagnostics,
+            );
+        }
+
+        for diagnostic in &mut diagnostics {
+            diagnostic.file = file.to_string();
+        }
+        if let Some(source_map) = source_map {
+            for diagnostic in &mut diagnostics {
+                diagnostic.line = match &diagnostic.kind {
+                    DiagnosticKind::UnknownProperty { property, .. }
+                    | DiagnosticKind::TypeMismatch { property, .. } => {
+                        source_map.property_line(diagnostic.section, property)
+                    }
+                    DiagnosticKind::UnknownClass(_) => None,
+                }
+                .or_else(|| source_map.section_line(diagnostic.section));
+            }
+        }
+
+        diagnostics
+    }
+
+    /// `script` is `Some(None)` for nodes with scripts which couldn't be loaded
+    fn validate_entry(
+        &self,
+        tscn: &Tscn,
+        properties: &PropertyMap,
+        class: &str,
+        section: Section,
+        script: Option<Option<GDScript>>,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) {
+        // Instances of scenes which are not loaded
+        if class.is_empty() {
+            return;
+        }
+
+        let mut report = |kind: DiagnosticKind| {
+            diagnostics.push(Diagnostic {
+                file: String::new(),
+                line: None,
+                section,
+                kind,
+            })
+        };
+
+        if self.class(class).is_none() {
+            report(DiagnosticKind::UnknownClass(class.to_string()));
+            return;
+        }
+
+        for (property, value) in properties.flatten() {
+            let declared = match self.property_type(class, &property) {
+                Some(declared) => declared,
+                None => {
+                    let is_export = match &script {
+                        Some(Some(script)) => script.export(&property).is_some(),
+                        Some(None) => true,
+                        None => false,
+                    };
+                    let is_dynamic = self
+                        .dynamic_prefixes
+                        .iter()
+                        .any(|prefix| property.starts_with(prefix.as_str()))
+                        || property.starts_with(|c: char| c.is_ascii_digit())
+                        || (self.inherits(class, "Theme") && is_theme_item(&property));
+
+                    if !is_export && !is_dynamic {
+                        report(DiagnosticKind::UnknownProperty {
+                            class: class.to_string(),
+                            property,
+                        });
+                    }
+                    continue;
+                }
+            };
+
+            if self.accepts(tscn, declared, value) == Some(false) {
+                report(DiagnosticKind::TypeMismatch {
+                    property,
+                    expected: declared.to_string(),
+                    value: value.clone(),
+                });
+            }
+        }
+    }
+
+    /// Whether `value` fits the declared property type, `None` when it can't be told
+    pub fn accepts(&self, tscn: &Tscn, declared: &str, value: &VarType) -> Option<bool> {
+        let declared = declared.trim();
+        if declared.starts_with("enum::") || declared.starts_with("bitfield::") {
+            return Some(matches!(value, VarType::Num(_)));
+        }
+        if declared.starts_with("typedarray::") {
+            return value.fits_type("Array");
+        }
+        if declared == "Variant" || declared == "Nil" {
+            return None;
+        }
+        if let Some(fits) = value.fits_type(declared) {
+            return Some(fits);
+        }
+
+        // Resource classes, possibly listing several (i.e. `Texture2D,AnimatedTexture`)
+        if !declared.split(',').all(|class| self.class(class).is_some()) {
+            return value.variant_type().map(|vtype| vtype == declared);
+        }
+
+        let rtype = match value {
+            VarType::SubResource(id) => tscn.sub_resources.get(id).map(|s| &s.rtype),
+            VarType::ExtResource(id) => tscn.ext_resource_entries.get(id).map(|e| &e.rtype),
+            VarType::Curve(_) => return Some(declared == "Curve"),
+            _ => return Some(value.variant_type() == Some("Nil")),
+        };
+
+        Some(match rtype {
+            // Types of unknown (i.e. script) resources can't be checked
+            Some(rtype) if self.class(rtype).is_some() => {
+                declared.split(',').any(|class| self.inherits(rtype, class))
+            }
+            _ => true,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::class_db::*;
+    use crate::Loader;
+
+    const API: &str = r#"{
+  "header": { "version_major": 4 },
+  "classes": [
+    { "name": "Object" },
+    { "name": "Node", "inherits": "Object", "properties": [
+      { "type": "StringName", "name": "name" },
+      { "type": "int", "name": "process_mode" }
+    ] },
+    { "name": "CanvasItem", "inherits": "Node", "properties": [
+      { "type": "bool", "name": "visible" },
+      { "type": "Color", "name": "modulate" }
+    ] },
+    { "name": "Node2D", "inherits": "CanvasItem", "properties": [
+      { "type": "Vector2", "name": "position" },
+      { "type": "float", "name": "rotation" }
+    ] },
+    { "name": "Sprite2D", "inherits": "Node2D", "properties": [
+      { "type": "Texture2D", "name": "texture" },
+      { "type": "bool", "name": "centered" }
+    ] },
+    { "name": "Resource", "inherits": "Object" },
+    { "name": "Texture2D", "inherits": "Resource" },
+    { "name": "Shape2D", "inherits": "Resource" },
+    { "name": "CircleShape2D", "inherits": "Shape2D", "properties": [
+      { "type": "float", "name": "radius" }
+    ] },
+    { "name": "Theme", "inherits": "Resource", "properties": [
+      { "type": "float", "name": "default_base_scale" }
+    ] }
+  ]
+}"#;
+
+    const SCENE: &str = r#"[gd_scene load_steps=4 format=3 uid="uid://c8s2k1v3lqj0a"]
+
+[ext_resource type="Texture2D" uid="uid://b2v1k3x8mw7qd" path="res://icon.png" id="1_w4k1x"]
+
+[sub_resource type="CircleShape2D" id="CircleShape2D_x3f0a"]
+radius = 8.0
+
+[sub_resource type="Theme" id="Theme_l2m8c"]
+default_base_scale = 1.0
+Button/base_type = &"Label"
+Button/colors/font_color = Color(1, 0, 0, 1)
+Button/font_sizes/font_size = 12
+Button/shadow = 2
+
+[node name="Level" type="Node2D"]
+position = Vector2(4, 4)
+rotation = "left"
+
+[node name="Icon" type="Sprite2D" parent="."]
+texture = SubResource("CircleShape2D_x3f0a")
+centered = true
+offset_x = 3
+
+[node name="Area" type="Area2D" parent="."]
+"#;
+
+    #[test]
+    fn test_validate() {
+        let db = ClassDb::from_json(API).unwrap();
+        assert!(db.inherits("Sprite2D", "CanvasItem"));
+        assert_eq!(db.property_type("Sprite2D", "visible"), Some("bool"));
+
+        let mut loader = Loader::new();
+        let tscn = loader.parse_tscn(SCENE);
+        let diagnostics = db.validate(&tscn, "res://level.tscn", Some(loader.source_map()));
+
+        assert_eq!(
+            diagnostics
+                .iter()
+                .map(|diagnostic| diagnostic.to_string())
+                .collect::<Vec<_>>(),
+            vec![
+                "res://level.tscn:13: `Theme` has no property `Button/shadow`",
+                "res://level.tscn:17: `rotation` expects float, got `\"left\"`",
+                "res://level.tscn:22: `Sprite2D` has no property `offset_x`",
+                "res://level.tscn:20: `texture` expects Texture2D, got `SubResource( 1 )`",
+                "res://level.tscn:24: unknown class `Area2D`",
+            ]
+        );
+
+        let raw = |text: &str| VarType::None(text.to_string());
+        assert_eq!(
+            db.accepts(&tscn, "StringName", &raw("&\"idle\"")),
+            Some(true)
+        );
+        assert_eq!(db.accepts(&tscn, "Color", &raw("null")), Some(false));
+        assert_eq!(db.accepts(&tscn, "Texture2D", &raw("null")), Some(true));
+        assert_eq!(
+            db.accepts(&tscn, "Transform3D", &raw("Transform( 1, 0, 0, 0, 1, 0 )")),
+            Some(true)
+        );
+        assert_eq!(
+            db.accepts(&tscn, "PackedFloat32Array", &VarType::IntArr(vec![1])),
+            Some(true)
+        );
+    }
+}
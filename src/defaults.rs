@@ -0,0 +1,419 @@
+#[cfg(feature = "json")]
+use crate::class_db::ClassDb;
+use crate::{PropertyMap, PropertyTrait, TscnHelper, VarType, TEXT_FORMAT};
+
+use std::collections::HashMap;
+
+/// Sprite (Godot 3) and Sprite2D (Godot 4)
+const SPRITE: &str = "texture = null
+centered = true
+offset = Vector2( 0, 0 )
+flip_h = false
+flip_v = false
+hframes = 1
+vframes = 1
+frame = 0
+region_enabled = false";
+
+/// Spatial (Godot 3) and Node3D (Godot 4)
+const SPATIAL: &str = "transform = Transform( 1, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0 )
+visible = true";
+
+/// Default values of common Godot 3 and 4 classes, in `.tscn` property syntax:
+/// (class, base class, properties). Properties are named the Godot 4 way, see [`GODOT3_NAMES`]
+const BUILTIN: &[(&str, Option<&str>, &str)] = &[
+    ("Node", None, ""),
+    (
+        "CanvasItem",
+        Some("Node"),
+        "visible = true
+modulate = Color( 1, 1, 1, 1 )
+self_modulate = Color( 1, 1, 1, 1 )
+show_behind_parent = false
+light_mask = 1",
+    ),
+    (
+        "Node2D",
+        Some("CanvasItem"),
+        "position = Vector2( 0, 0 )
+rotation = 0.0
+scale = Vector2( 1, 1 )
+skew = 0.0
+z_index = 0
+z_as_relative = true",
+    ),
+    ("Sprite", Some("Node2D"), SPRITE),
+    ("Sprite2D", Some("Node2D"), SPRITE),
+    ("AnimatedSprite", Some("Node2D"), ""),
+    ("AnimatedSprite2D", Some("Node2D"), ""),
+    ("Position2D", Some("Node2D"), ""),
+    ("Marker2D", Some("Node2D"), ""),
+    ("YSort", Some("Node2D"), ""),
+    ("Polygon2D", Some("Node2D"), ""),
+    ("Line2D", Some("Node2D"), ""),
+    ("Path2D", Some("Node2D"), ""),
+    ("PathFollow2D", Some("Node2D"), ""),
+    ("TileMap", Some("Node2D"), ""),
+    ("ParallaxLayer", Some("Node2D"), ""),
+    ("RemoteTransform2D", Some("Node2D"), ""),
+    ("CollisionPolygon2D", Some("Node2D"), ""),
+    (
+        "Camera2D",
+        Some("Node2D"),
+        "offset = Vector2( 0, 0 )
+zoom = Vector2( 1, 1 )",
+    ),
+    (
+        "CollisionObject2D",
+        Some("Node2D"),
+        "collision_layer = 1
+collision_mask = 1",
+    ),
+    (
+        "Area2D",
+        Some("CollisionObject2D"),
+        "monitoring = true
+monitorable = true",
+    ),
+    ("PhysicsBody2D", Some("CollisionObject2D"), ""),
+    ("StaticBody2D", Some("PhysicsBody2D"), ""),
+    ("KinematicBody2D", Some("PhysicsBody2D"), ""),
+    ("CharacterBody2D", Some("PhysicsBody2D"), ""),
+    (
+        "RigidBody2D",
+        Some("PhysicsBody2D"),
+        "mass = 1.0
+gravity_scale = 1.0",
+    ),
+    (
+        "CollisionShape2D",
+        Some("Node2D"),
+        "shape = null
+disabled = false
+one_way_collision = false",
+    ),
+    (
+        "Control",
+        Some("CanvasItem"),
+        "anchor_left = 0.0
+anchor_top = 0.0
+anchor_right = 0.0
+anchor_bottom = 0.0
+offset_left = 0.0
+offset_top = 0.0
+offset_right = 0.0
+offset_bottom = 0.0
+size_flags_horizontal = 1
+size_flags_vertical = 1
+size_flags_stretch_ratio = 1.0
+grow_horizontal = 1
+grow_vertical = 1
+mouse_filter = 0
+clip_contents = false",
+    ),
+    ("Container", Some("Control"), ""),
+    ("BoxContainer", Some("Container"), ""),
+    ("HBoxContainer", Some("BoxContainer"), ""),
+    ("VBoxContainer", Some("BoxContainer"), ""),
+    ("MarginContainer", Some("Container"), ""),
+    ("CenterContainer", Some("Container"), ""),
+    ("GridContainer", Some("Container"), ""),
+    ("PanelContainer", Some("Container"), ""),
+    ("ScrollContainer", Some("Container"), ""),
+    ("TabContainer", Some("Container"), ""),
+    ("SplitContainer", Some("Container"), ""),
+    ("HSplitContainer", Some("SplitContainer"), ""),
+    ("VSplitContainer", Some("SplitContainer"), ""),
+    ("FlowContainer", Some("Container"), ""),
+    ("HFlowContainer", Some("FlowContainer"), ""),
+    ("VFlowContainer", Some("FlowContainer"), ""),
+    ("AspectRatioContainer", Some("Container"), ""),
+    ("ViewportContainer", Some("Container"), ""),
+    ("SubViewportContainer", Some("Container"), ""),
+    ("GraphNode", Some("Container"), ""),
+    ("ColorPicker", Some("BoxContainer"), ""),
+    ("Panel", Some("Control"), ""),
+    ("ColorRect", Some("Control"), ""),
+    ("TextureRect", Some("Control"), ""),
+    ("NinePatchRect", Some("Control"), ""),
+    ("ReferenceRect", Some("Control"), ""),
+    ("Separator", Some("Control"), ""),
+    ("HSeparator", Some("Separator"), ""),
+    ("VSeparator", Some("Separator"), ""),
+    ("ItemList", Some("Control"), ""),
+    ("Tree", Some("Control"), ""),
+    ("Tabs", Some("Control"), ""),
+    ("TabBar", Some("Control"), ""),
+    ("GraphEdit", Some("Control"), ""),
+    ("VideoPlayer", Some("Control"), ""),
+    ("VideoStreamPlayer", Some("Control"), ""),
+    (
+        "Label",
+        Some("Control"),
+        "text = \"\"
+uppercase = false",
+    ),
+    (
+        "BaseButton",
+        Some("Control"),
+        "disabled = false
+toggle_mode = false",
+    ),
+    (
+        "Button",
+        Some("BaseButton"),
+        "text = \"\"
+flat = false",
+    ),
+    ("CheckBox", Some("Button"), ""),
+    ("CheckButton", Some("Button"), ""),
+    ("MenuButton", Some("Button"), ""),
+    ("OptionButton", Some("Button"), ""),
+    ("LinkButton", Some("BaseButton"), ""),
+    ("TextureButton", Some("BaseButton"), ""),
+    ("ColorPickerButton", Some("Button"), ""),
+    ("RichTextLabel", Some("Control"), ""),
+    ("LineEdit", Some("Control"), ""),
+    ("TextEdit", Some("Control"), ""),
+    ("CodeEdit", Some("TextEdit"), ""),
+    ("Range", Some("Control"), ""),
+    ("ProgressBar", Some("Range"), ""),
+    ("TextureProgress", Some("Range"), ""),
+    ("TextureProgressBar", Some("Range"), ""),
+    ("Slider", Some("Range"), ""),
+    ("HSlider", Some("Slider"), ""),
+    ("VSlider", Some("Slider"), ""),
+    ("ScrollBar", Some("Range"), ""),
+    ("HScrollBar", Some("ScrollBar"), ""),
+    ("VScrollBar", Some("ScrollBar"), ""),
+    ("SpinBox", Some("Range"), ""),
+    // Godot 3 popups are Controls, Godot 4 moved them under Window
+    ("Popup", Some("Control"), ""),
+    ("PopupDialog", Some("Popup"), ""),
+    ("PopupPanel", Some("Popup"), ""),
+    ("PopupMenu", Some("Popup"), ""),
+    ("WindowDialog", Some("Popup"), ""),
+    ("AcceptDialog", Some("WindowDialog"), ""),
+    ("ConfirmationDialog", Some("AcceptDialog"), ""),
+    ("FileDialog", Some("ConfirmationDialog"), ""),
+    ("Spatial", Some("Node"), SPATIAL),
+    ("Node3D", Some("Node"), SPATIAL),
+    ("VisualInstance", Some("Spatial"), ""),
+    ("GeometryInstance", Some("VisualInstance"), ""),
+    ("MeshInstance", Some("GeometryInstance"), ""),
+    ("Sprite3D", Some("GeometryInstance"), ""),
+    ("VisualInstance3D", Some("Node3D"), ""),
+    ("GeometryInstance3D", Some("VisualInstance3D"), ""),
+    ("MeshInstance3D", Some("GeometryInstance3D"), ""),
+    ("Camera", Some("Spatial"), ""),
+    ("Camera3D", Some("Node3D"), ""),
+    ("Position3D", Some("Spatial"), ""),
+    ("Marker3D", Some("Node3D"), ""),
+    ("CollisionObject", Some("Spatial"), ""),
+    ("PhysicsBody", Some("CollisionObject"), ""),
+    ("StaticBody", Some("PhysicsBody"), ""),
+    ("KinematicBody", Some("PhysicsBody"), ""),
+    ("RigidBody", Some("PhysicsBody"), ""),
+    ("Area", Some("CollisionObject"), ""),
+    ("CollisionShape", Some("Spatial"), ""),
+    ("CollisionObject3D", Some("Node3D"), ""),
+    ("PhysicsBody3D", Some("CollisionObject3D"), ""),
+    ("StaticBody3D", Some("PhysicsBody3D"), ""),
+    ("CharacterBody3D", Some("PhysicsBody3D"), ""),
+    ("RigidBody3D", Some("PhysicsBody3D"), ""),
+    ("Area3D", Some("CollisionObject3D"), ""),
+    ("CollisionShape3D", Some("Node3D"), ""),
+    (
+        "CanvasLayer",
+        Some("Node"),
+        "layer = 1
+offset = Vector2( 0, 0 )
+rotation = 0.0
+scale = Vector2( 1, 1 )",
+    ),
+    (
+        "Timer",
+        Some("Node"),
+        "wait_time = 1.0
+one_shot = false
+autostart = false",
+    ),
+    (
+        "AudioStreamPlayer",
+        Some("Node"),
+        "volume_db = 0.0
+pitch_scale = 1.0
+autoplay = false
+bus = \"Master\"",
+    ),
+    ("AnimationPlayer", Some("Node"), "autoplay = \"\""),
+    ("Viewport", Some("Node"), ""),
+    ("SubViewport", Some("Viewport"), ""),
+    ("ParallaxBackground", Some("CanvasLayer"), ""),
+];
+
+/// Properties saved under another name by Godot 3 (Godot 4 name, Godot 3 name), properties
+/// missing from Godot 3 have no Godot 3 name
+const GODOT3_NAMES: &[(&str, Option<&str>)] = &[
+    ("rotation", Some("rotation_degrees")),
+    ("skew", None),
+    ("offset_left", Some("margin_left")),
+    ("offset_top", Some("margin_top")),
+    ("offset_right", Some("margin_right")),
+    ("offset_bottom", Some("margin_bottom")),
+];
+
+lazy_static! {
+    static ref BUILTIN_DEFAULTS: ClassDefaults = ClassDefaults::from_builtin(false);
+    static ref BUILTIN_DEFAULTS_GODOT3: ClassDefaults = ClassDefaults::from_builtin(true);
+}
+
+/// Where the effective value of a property comes from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Provenance {
+    /// Class default, the property isn't saved
+    Default,
+    /// Value of the instanced scene, by ext resource id
+    Instance(usize),
+    /// Value saved on the node itself
+    Local,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EffectiveProperty {
+    pub value: VarType,
+    pub provenance: Provenance,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+struct ClassDefault {
+    inherits: Option<String>,
+    properties: PropertyMap,
+}
+
+/// Default property values by class, with class inheritance
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ClassDefaults {
+    classes: HashMap<String, ClassDefault>,
+}
+
+impl ClassDefaults {
+    /// Built-in table of common node classes, with properties named the way scenes of the
+    /// crate's default text format (Godot 3) save them
+    pub fn builtin() -> Self {
+        Self::builtin_for(TEXT_FORMAT)
+    }
+
+    /// Built-in table of common node classes, with properties named the way scenes of the
+    /// text format save them: Godot 3 (`format=2`) or Godot 4 (`format=3`)
+    pub fn builtin_for(format: usize) -> Self {
+        if format >= 3 {
+            BUILTIN_DEFAULTS.clone()
+        } else {
+            BUILTIN_DEFAULTS_GODOT3.clone()
+        }
+    }
+
+    fn from_builtin(godot3: bool) -> Self {
+        let mut defaults = ClassDefaults::default();
+        for (class, inherits, properties) in BUILTIN {
+            let mut map = PropertyMap::new();
+            for line in properties.lines() {
+                if let Some(command) = TscnHelper::parse_command(line, class) {
+                    let renamed = GODOT3_NAMES.iter().find(|(name, _)| *name == command.lhs);
+                    match renamed {
+                        Some((_, Some(name))) if godot3 => {
+                            map.insert_to(name.to_string(), command.rhs)
+                        }
+                        Some((_, None)) if godot3 => continue,
+                        _ => map.insert_to(command.lhs, command.rhs),
+                    }
+                }
+            }
+
+            defaults.insert(class, *inherits, map);
+        }
+
+        defaults
+    }
+
+    /// Built-in table without copying it, for class ancestry lookups
+    pub(crate) fn builtin_ref() -> &'static Self {
+        &BUILTIN_DEFAULTS
+    }
+
+    /// The class followed by its ancestors, as far as they are in the table
+    pub fn class_chain<'a>(&'a self, class: &'a str) -> Vec<&'a str> {
+        let mut chain = vec![class];
+        while let Some(base) = self
+            .classes
+            .get(chain[chain.len() - 1])
+            .and_then(|class| class.inherits.as_deref())
+        {
+            if chain.len() >= 64 {
+                break;
+            }
+            chain.push(base);
+        }
+
+        chain
+    }
+
+    /// Whether the class is in the table
+    pub fn contains(&self, class: &str) -> bool {
+        self.classes.contains_key(class)
+    }
+
+    /// Whether the class is the ancestor or derives from it
+    pub fn inherits(&self, class: &str, ancestor: &str) -> bool {
+        self.class_chain(class).contains(&ancestor)
+    }
+
+    /// Sets defaults of the class, overriding its previous defaults
+    pub fn insert(&mut self, class: &str, inherits: Option<&str>, properties: PropertyMap) {
+        self.classes.insert(
+            class.to_string(),
+            ClassDefault {
+                inherits: inherits.map(str::to_string),
+                properties,
+            },
+        );
+    }
+
+    /// Adds inheritance of the classes which are not in the table yet, so they get their
+    /// ancestors' defaults. `extension_api.json` carries no default values
+    #[cfg(feature = "json")]
+    pub fn extend_from_class_db(&mut self, class_db: &ClassDb) {
+        for (name, class) in &class_db.classes {
+            self.classes
+                .entry(name.clone())
+                .or_insert_with(|| ClassDefault {
+                    inherits: class.inherits.clone(),
+                    properties: PropertyMap::new(),
+                });
+        }
+    }
+
+    /// Defaults of the class merged with its ancestors', as flattened (`group/name`) keys
+    pub fn defaults(&self, class: &str) -> PropertyMap {
+        let mut chain = Vec::new();
+        let mut next = self.classes.get(class);
+        while let Some(class) = next {
+            if chain.len() >= 64 {
+                break;
+            }
+            chain.push(class);
+            next = class
+                .inherits
+                .as_ref()
+                .and_then(|base| self.classes.get(base));
+        }
+
+        chain
+            .iter()
+            .rev()
+            .flat_map(|class| class.properties.flatten())
+            .map(|(key, value)| (key, value.clone()))
+            .collect()
+    }
+}
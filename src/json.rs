@@ -0,0 +1,282 @@
+//! JSON export and import of scenes and resources
+//!
+//! Schema (format `1`), object keys are always written in sorted order:
+//!
+//! ```json
+//! {
+//!   "format": 1,
+//!   "text_format": 2,
+//!   "type": "Scene",
+//!   "ext_resources": [{ "id": 1, "type": "Texture", "path": "res://icon.png", "uid": null }],
+//!   "sub_resources": [{ "id": 1, "type": "RectangleShape2D", "properties": { "extents": { "Vector": [8.0, 8.0] } } }],
+//!   "resource": {},
+//!   "root": {
+//!     "name": "Level",
+//!     "path": "/Level",
+//!     "type": "Node2D",
+//!     "instance": null,
+//!     "groups": ["levels"],
+//!     "properties": { "texture": { "ExtResource": 1 }, "shape": { "SubResource": 1 } },
+//!     "children": []
+//!   },
+//!   "connections": [{ "signal": "pressed", "from": "Button", "to": ".", "method": "_on_pressed", "flags": 0 }],
+//!   "editable_instances": ["Enemy"]
+//! }
+//! ```
+//!
+//! Property values use the `VarType` encoding: `{ "<variant>": <value> }`, with
+//! vectors as `[x, y]` and rectangles as `{ "position": [x, y], "size": [w, h] }`.
+//! `root` is `null` for resources, whose properties live in `resource`.
+//! Node `path` is informative only, on import it is rebuilt from the names.
+//! Node `groups` and `editable_instances` are omitted when empty.
+//! `text_format` is the `.tscn` format version of the source (`2` when missing).
+
+use crate::uid::Uid;
+use crate::{Connection, ExtResourceEntry, NodeEntry, PropertyMap, Tscn, TEXT_FORMAT};
+
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+pub const FORMAT: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonScene {
+    pub format: u32,
+    #[serde(default = "text_format")]
+    pub text_format: usize,
+    #[serde(rename = "type")]
+    pub rtype: String,
+    pub ext_resources: Vec<JsonExtResource>,
+    pub sub_resources: Vec<JsonSubResource>,
+    pub resource: PropertyMap,
+    pub root: Option<JsonNode>,
+    pub connections: Vec<Connection>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub editable_instances: Vec<String>,
+}
+
+fn text_format() -> usize {
+    TEXT_FORMAT
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonExtResource {
+    pub id: usize,
+    #[serde(rename = "type")]
+    pub rtype: String,
+    pub path: String,
+    pub uid: Option<Uid>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonSubResource {
+    pub id: usize,
+    #[serde(rename = "type")]
+    pub rtype: String,
+    pub properties: PropertyMap,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonNode {
+    pub name: String,
+    pub path: String,
+    #[serde(rename = "type")]
+    pub rtype: String,
+    pub instance: Option<usize>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub groups: Vec<String>,
+    pub properties: PropertyMap,
+    pub children: Vec<JsonNode>,
+}
+
+impl JsonScene {
+    pub fn from_tscn(tscn: &Tscn) -> Self {
+        JsonScene {
+            format: FORMAT,
+            text_format: tscn.format,
+            rtype: tscn.rtype.clone(),
+            ext_resources: tscn
+                .ext_resource_entries
+                .iter()
+                .map(|(id, ext)| JsonExtResource {
+                    id: *id,
+                    rtype: ext.rtype.clone(),
+                    path: ext.path.clone(),
+                    uid: ext.uid,
+                })
+                .collect(),
+            sub_resources: tscn
+                .sub_resources
+                .iter()
+                .map(|(id, sub)| JsonSubResource {
+                    id: *id,
+                    rtype: sub.rtype.clone(),
+                    properties: sub.properties.clone(),
+                })
+                .collect(),
+            resource: tscn.resource.clone(),
+            root: tscn
+                .nodes
+                .keys()
+                .next()
+                .map(|id| Self::node_from_tscn(tscn, *id)),
+            connections: tscn.connections.clone(),
+            editable_instances: tscn.editable_instances.clone(),
+        }
+    }
+
+    pub fn into_tscn(self) -> Tscn {
+        let mut nodes: IndexMap<usize, NodeEntry> = IndexMap::new();
+        if let Some(root) = self.root {
+            Self::node_into_tscn(root, None, &mut nodes);
+        }
+
+        Tscn {
+            rtype: self.rtype,
+            nodes,
+            resource: self.resource,
+            sub_resources: self
+                .sub_resources
+                .into_iter()
+                .map(|sub| {
+                    let entry = NodeEntry {
+                        properties: sub.properties,
+                        ..NodeEntry::new_type(&sub.rtype)
+                    };
+
+                    (sub.id, entry)
+                })
+                .collect(),
+            ext_resources: HashMap::new(),
+            ext_resource_entries: self
+                .ext_resources
+                .into_iter()
+                .map(|ext| {
+                    let entry = ExtResourceEntry {
+                        path: ext.path,
+                        rtype: ext.rtype,
+                        uid: ext.uid,
+                    };
+
+                    (ext.id, entry)
+                })
+                .collect(),
+            connections: self.connections,
+            format: self.text_format,
+            editable_instances: self.editable_instances,
+        }
+    }
+
+    fn node_from_tscn(tscn: &Tscn, id: usize) -> JsonNode {
+        let node = &tscn.nodes[&id];
+
+        JsonNode {
+            name: node.name.clone(),
+            path: node.path.clone(),
+            rtype: node.rtype.clone(),
+            instance: if node.instance != 0 {
+                Some(node.instance)
+            } else {
+                None
+            },
+            groups: node.groups.clone(),
+            properties: node.properties.clone(),
+            children: node
+                .childrens
+                .iter()
+                .map(|child| Self::node_from_tscn(tscn, *child))
+                .collect(),
+        }
+    }
+
+    fn node_into_tscn(
+        node: JsonNode,
+        parent_id: Option<usize>,
+        nodes: &mut IndexMap<usize, NodeEntry>,
+    ) {
+        let id = nodes.len();
+        let (path, level) = match parent_id {
+            Some(parent_id) => {
+                let parent = nodes.get_mut(&parent_id).expect("Missing parent node");
+                parent.childrens.push(id);
+
+                (format!("{}/{}", parent.path, node.name), parent.level + 1)
+            }
+            None => (format!("/{}", node.name), 0),
+        };
+
+        nodes.insert(
+            id,
+            NodeEntry {
+                path,
+                level,
+                name: node.name,
+                rtype: node.rtype,
+                instance: node.instance.unwrap_or(0),
+                groups: node.groups,
+                parent_id: parent_id.unwrap_or(0),
+                properties: node.properties,
+                ..NodeEntry::default()
+            },
+        );
+
+        for child in node.children {
+            Self::node_into_tscn(child, Some(id), nodes);
+        }
+    }
+}
+
+/// Exports scene into pretty-printed JSON
+pub fn to_json(tscn: &Tscn) -> Result<String, String> {
+    // Going through `Value` sorts all object keys, which keeps the output stable
+    let value = serde_json::to_value(JsonScene::from_tscn(tscn)).map_err(|e| e.to_string())?;
+
+    serde_json::to_string_pretty(&value).map_err(|e| e.to_string())
+}
+
+/// Imports scene from JSON, loaded ext resources are not restored
+pub fn from_json(json: &str) -> Result<Tscn, String> {
+    let scene: JsonScene = serde_json::from_str(json).map_err(|e| e.to_string())?;
+
+    if scene.format != FORMAT {
+        return Err(format!("Unsupported JSON scene format {}", scene.format));
+    }
+
+    Ok(scene.into_tscn())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::json::*;
+    use crate::Loader;
+
+    const SCENE: &str = r#"[gd_scene load_steps=2 format=2]
+
+[sub_resource type="Curve" id=1]
+_data = [ Vector2( 0, 0 ), 0.0, 1.0, 0, 0, Vector2( 1, 1 ), 1.0, 0.0, 0, 0 ]
+
+[node name="Level" type="Node2D"]
+
+[node name="Spawn" type="Position2D" parent="."]
+position = Vector2( 16, -8.5 )
+region = Rect2( 0, 0, 32, 32 )
+
+[node name="Marker" type="Sprite" parent="Spawn"]
+falloff = SubResource( 1 )
+
+[editable path="Spawn"]
+"#;
+
+    #[test]
+    fn test_json_round_trip() {
+        let tscn = Loader::new().parse_tscn(SCENE);
+        let json = tscn.to_json().unwrap();
+
+        assert!(json.contains(r#""Vector": ["#));
+        assert!(json.contains(r#""position": ["#));
+        assert!(json.contains(r#""editable_instances": ["#));
+        assert_eq!(Tscn::from_json(&json).unwrap(), tscn);
+        assert_eq!(Tscn::from_json(&json).unwrap().to_tscn(), SCENE);
+    }
+}
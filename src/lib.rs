@@ -3,22 +3,46 @@ extern crate lazy_static;
 
 use indexmap::IndexMap;
 use std::collections::HashMap;
+use std::convert::TryFrom;
 use std::fs;
 use std::path::Path;
 use std::path::PathBuf;
 
+use binary::{BinaryReader, BinaryWriter};
+pub use defaults::{ClassDefaults, EffectiveProperty, Provenance};
+use nalgebra::Vector2;
+pub use source_map::{Section, SourceMap};
 use str_helper::StrHelper;
 pub use tscn_helper::{Node, NodeType, TscnHelper};
+pub use tscn_writer::TscnWriter;
 pub use types::VarType;
+use types::{Transform2D, Transform2DTrait, Transform3D};
+pub use uid::{Uid, UidCache};
 
-// pub mod nodes;
+pub mod binary;
+#[cfg(feature = "json")]
+pub mod class_db;
+pub mod defaults;
+#[cfg(feature = "json")]
+pub mod json;
+#[cfg(feature = "lint")]
+pub mod lint;
+pub mod nodes;
+#[cfg(feature = "serde")]
+mod serde_helper;
+pub mod source_map;
 pub mod str_helper;
+#[cfg(feature = "tiled")]
+pub mod tiled;
 pub mod tscn_helper;
+pub mod tscn_writer;
 pub mod types;
+pub mod uid;
 
-type PropertyMap = HashMap<String, VarType>;
+pub type PropertyMap = HashMap<String, VarType>;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct NodeEntry {
     pub path: String,
     pub level: usize,
@@ -28,18 +52,48 @@ pub struct NodeEntry {
     pub parent_id: usize,
     pub node_type: NodeType,
     pub childrens: Vec<usize>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub groups: Vec<String>,
     pub properties: PropertyMap,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExtResourceEntry {
+    pub path: String,
+    pub rtype: String,
+    pub uid: Option<Uid>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Connection {
+    pub signal: String,
+    pub from: String,
+    pub to: String,
+    pub method: String,
+    pub flags: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Tscn {
     pub rtype: String,
     pub nodes: IndexMap<usize, NodeEntry>,
     pub resource: PropertyMap,
     pub sub_resources: IndexMap<usize, NodeEntry>,
     pub ext_resources: HashMap<usize, Tscn>,
+    pub ext_resource_entries: IndexMap<usize, ExtResourceEntry>,
+    pub connections: Vec<Connection>,
+    /// Text format version, `2` for Godot 3 and `3` for Godot 4
+    pub format: usize,
+    /// Paths of instanced nodes with editable children (`[editable path="..."]`)
+    pub editable_instances: Vec<String>,
 }
 
+/// Text format version written when the source one is unknown
+pub const TEXT_FORMAT: usize = 2;
+
 impl Default for NodeEntry {
     fn default() -> Self {
         NodeEntry {
@@ -50,12 +104,399 @@ impl Default for NodeEntry {
             rtype: "".to_string(),
             parent_id: 0,
             childrens: Vec::new(),
+            groups: Vec::new(),
             node_type: NodeType::Node,
             properties: HashMap::new(),
         }
     }
 }
 
+impl Tscn {
+    /// Node path relative to scene root (i.e. `.` or `Player/Sprite`)
+    pub fn relative_path(&self, node_id: usize) -> Option<String> {
+        let root = self.nodes.values().next()?;
+        let node = self.nodes.get(&node_id)?;
+
+        Some(
+            node.path
+                .strip_prefix(&format!("{}/", root.path))
+                .unwrap_or(".")
+                .to_string(),
+        )
+    }
+
+    /// Finds node id by path relative to scene root
+    pub fn find_node(&self, relative_path: &str) -> Option<usize> {
+        self.nodes
+            .keys()
+            .find(|id| self.relative_path(**id).as_deref() == Some(relative_path))
+            .copied()
+    }
+
+    /// Resolves node path relative to the `from` node (i.e. `../AnimationPlayer`)
+    pub fn resolve_node_path(&self, from: usize, path: &str) -> Option<usize> {
+        let mut segments: Vec<String> = match self.relative_path(from)?.as_str() {
+            "." => Vec::new(),
+            relative => relative.split('/').map(String::from).collect(),
+        };
+
+        for segment in path.split('/') {
+            match segment {
+                "" | "." => (),
+                ".." => {
+                    segments.pop()?;
+                }
+                _ => segments.push(segment.to_string()),
+            }
+        }
+
+        if segments.is_empty() {
+            self.find_node(".")
+        } else {
+            self.find_node(&segments.join("/"))
+        }
+    }
+
+    /// Local 2D transform of the node, from `transform` or `position`, `rotation`, `scale`
+    /// and `skew`; Godot 3 Controls use `rect_position`, `rect_rotation` and `rect_scale`,
+    /// around `rect_pivot_offset`. Instanced scene roots fall back to the loaded scene's root values
+    pub fn local_transform(&self, node_id: usize) -> Option<Transform2D> {
+        self.nodes.get(&node_id)?;
+
+        if let Some(transform) = self.node_property(node_id, "transform") {
+            if let Ok(transform) = Transform2D::try_from(transform) {
+                return Some(transform);
+            }
+        }
+
+        let real = |key: &str| {
+            self.node_property(node_id, key)
+                .and_then(|v| f32::try_from(v).ok())
+        };
+        let vector = |key: &str| {
+            self.node_property(node_id, key)
+                .and_then(|v| Vector2::try_from(v).ok())
+        };
+
+        let position = vector("position")
+            .or_else(|| vector("rect_position"))
+            .unwrap_or_else(Vector2::zeros);
+        let rotation = real("rotation")
+            .or_else(|| real("rotation_degrees").map(f32::to_radians))
+            .or_else(|| real("rect_rotation").map(f32::to_radians))
+            .unwrap_or(0.0);
+        let scale = vector("scale")
+            .or_else(|| vector("rect_scale"))
+            .unwrap_or_else(|| Vector2::new(1.0, 1.0));
+        let pivot = vector("pivot_offset")
+            .or_else(|| vector("rect_pivot_offset"))
+            .unwrap_or_else(Vector2::zeros);
+
+        let transform = Transform2D::from_components(
+            position + pivot,
+            rotation,
+            scale,
+            real("skew").unwrap_or(0.0),
+        );
+        Some(transform * Transform2D::new_translation(&-pivot))
+    }
+
+    /// Transform of the node within its canvas: local transforms are composed up to the root,
+    /// the first parent known not to be a CanvasItem (i.e. CanvasLayer, Node), or a `top_level`
+    /// node
+    pub fn global_transform(&self, node_id: usize) -> Option<Transform2D> {
+        self.compose_transforms(node_id, |id| self.local_transform(id), &["CanvasItem"])
+    }
+
+    /// Local 3D transform of the node (`transform`), instanced scene roots fall back to
+    /// the loaded scene's root value
+    pub fn local_transform_3d(&self, node_id: usize) -> Option<Transform3D> {
+        self.nodes.get(&node_id)?;
+
+        Some(
+            self.node_property(node_id, "transform")
+                .and_then(|transform| Transform3D::try_from(transform).ok())
+                .unwrap_or_else(Transform3D::identity),
+        )
+    }
+
+    /// Transform of the node in the world: local transforms are composed up to the root,
+    /// the first parent known not to be a Spatial (Node3D), or a `top_level` node
+    pub fn global_transform_3d(&self, node_id: usize) -> Option<Transform3D> {
+        self.compose_transforms(
+            node_id,
+            |id| self.local_transform_3d(id),
+            &["Spatial", "Node3D"],
+        )
+    }
+
+    /// Composes parents' local transforms until the root, a `top_level` node or a parent
+    /// which the built-in [`ClassDefaults`] know not to derive from any of `bases`. Parents
+    /// of unknown classes and instances of scenes which weren't loaded continue the chain
+    fn compose_transforms<T: std::ops::Mul<Output = T>>(
+        &self,
+        node_id: usize,
+        local: impl Fn(usize) -> Option<T>,
+        bases: &[&str],
+    ) -> Option<T> {
+        let classes = ClassDefaults::builtin_ref();
+        let root_id = *self.nodes.keys().next()?;
+        let mut transform = local(node_id)?;
+        let mut id = node_id;
+
+        loop {
+            let top_level = ["top_level", "toplevel"]
+                .iter()
+                .any(|key| self.node_property(id, key) == Some(&VarType::Bool(true)));
+            if id == root_id || top_level {
+                break;
+            }
+
+            id = self.nodes[&id].parent_id;
+            let rtype = self.node_type(id);
+            if classes.contains(rtype) && !bases.iter().any(|base| classes.inherits(rtype, base)) {
+                break;
+            }
+
+            transform = local(id)? * transform;
+        }
+
+        Some(transform)
+    }
+
+    /// Node type, looked up in the instanced scene when the node is an instance
+    fn node_type(&self, node_id: usize) -> &str {
+        match self.nodes.get(&node_id) {
+            Some(node) if node.rtype.is_empty() && node.instance != 0 => self
+                .ext_resources
+                .get(&node.instance)
+                .and_then(|scene| Some(scene.node_type(*scene.nodes.keys().next()?)))
+                .unwrap_or(""),
+            Some(node) => &node.rtype,
+            None => "",
+        }
+    }
+
+    /// Node property, falling back to the instanced scene's root
+    fn node_property(&self, node_id: usize, key: &str) -> Option<&VarType> {
+        let node = self.nodes.get(&node_id)?;
+
+        node.properties.get(key).or_else(|| {
+            let scene = self.ext_resources.get(&node.instance)?;
+            scene.node_property(*scene.nodes.keys().next()?, key)
+        })
+    }
+
+    /// Instanced scene the node is based on: ext resource id, the loaded scene and the node
+    /// id inside it. Editable children are looked up by path below their instanced ancestor
+    fn instance_base(&self, node_id: usize) -> Option<(usize, &Tscn, usize)> {
+        let root_id = *self.nodes.keys().next()?;
+        let node = self.nodes.get(&node_id)?;
+        if node.instance != 0 {
+            let scene = self.ext_resources.get(&node.instance)?;
+            return Some((node.instance, scene, *scene.nodes.keys().next()?));
+        }
+        if !node.rtype.is_empty() || node_id == root_id {
+            return None;
+        }
+
+        let mut ancestor = self.nodes.get(&node.parent_id)?;
+        while ancestor.instance == 0 {
+            if ancestor.path == self.nodes[&root_id].path {
+                return None;
+            }
+            ancestor = self.nodes.get(&ancestor.parent_id)?;
+        }
+
+        let scene = self.ext_resources.get(&ancestor.instance)?;
+        let relative = node.path.strip_prefix(&format!("{}/", ancestor.path))?;
+        Some((ancestor.instance, scene, scene.find_node(relative)?))
+    }
+
+    /// Effective properties of the node against the built-in class defaults table of the
+    /// scene's text format, see [`Tscn::effective_properties_with`]
+    pub fn effective_properties(
+        &self,
+        node_id: usize,
+    ) -> Option<IndexMap<String, EffectiveProperty>> {
+        self.effective_properties_with(node_id, &ClassDefaults::builtin_for(self.format))
+    }
+
+    /// Class defaults, overridden by the instanced scene's values (when it was loaded), overridden
+    /// by the node's own properties. Keys are flattened (`group/name`) and sorted
+    pub fn effective_properties_with(
+        &self,
+        node_id: usize,
+        defaults: &ClassDefaults,
+    ) -> Option<IndexMap<String, EffectiveProperty>> {
+        let node = self.nodes.get(&node_id)?;
+
+        let mut effective: IndexMap<String, EffectiveProperty> = match self.instance_base(node_id) {
+            Some((ext_id, scene, base_id)) => scene
+                .effective_properties_with(base_id, defaults)?
+                .into_iter()
+                .map(|(key, mut property)| {
+                    if property.provenance != Provenance::Default {
+                        property.provenance = Provenance::Instance(ext_id);
+                    }
+                    (key, property)
+                })
+                .collect(),
+            None => defaults
+                .defaults(self.node_type(node_id))
+                .into_iter()
+                .map(|(key, value)| {
+                    let provenance = Provenance::Default;
+                    (key, EffectiveProperty { value, provenance })
+                })
+                .collect(),
+        };
+
+        for (key, value) in node.properties.flatten() {
+            let value = value.clone();
+            let provenance = Provenance::Local;
+            effective.insert(key, EffectiveProperty { value, provenance });
+        }
+
+        effective.sort_keys();
+        Some(effective)
+    }
+
+    /// Resolves `SubResource` or `ExtResource` reference into resource type and properties,
+    /// ext resources are only available when they were loaded
+    pub fn resource_properties(&self, reference: &VarType) -> Option<(&str, &PropertyMap)> {
+        match reference {
+            VarType::SubResource(id) => {
+                let sub = self.sub_resources.get(id)?;
+                Some((&sub.rtype, &sub.properties))
+            }
+            VarType::ExtResource(id) => {
+                let ext = self.ext_resources.get(id)?;
+                Some((&ext.rtype, &ext.resource))
+            }
+            _ => None,
+        }
+    }
+
+    /// Resolves reference like `resource_properties`, along with the scene (resource) owning
+    /// the properties. References nested in the properties are resolved within the owner
+    pub fn resource_owner(&self, reference: &VarType) -> Option<(&Tscn, &str, &PropertyMap)> {
+        match reference {
+            VarType::ExtResource(id) => {
+                let ext = self.ext_resources.get(id)?;
+                Some((ext, &ext.rtype, &ext.resource))
+            }
+            _ => {
+                let (rtype, properties) = self.resource_properties(reference)?;
+                Some((self, rtype, properties))
+            }
+        }
+    }
+
+    /// Sources of built-in scripts (`[sub_resource type="GDScript"]`) by sub resource id
+    pub fn builtin_scripts(&self) -> IndexMap<usize, &str> {
+        self.sub_resources
+            .iter()
+            .filter(|(_, sub)| sub.rtype == "GDScript")
+            .filter_map(
+                |(&id, sub)| match sub.properties.get_from("script/source") {
+                    Some(VarType::Str(source)) => Some((id, source.as_str())),
+                    _ => None,
+                },
+            )
+            .collect()
+    }
+
+    /// Moves built-in scripts into `<path_prefix><id>.gd` (i.e. `res://player_1.gd`) ext
+    /// resources, references are rewritten. Returns sources by path, to be written by caller
+    pub fn extract_builtin_scripts(&mut self, path_prefix: &str) -> IndexMap<String, String> {
+        let scripts: Vec<(usize, String)> = self
+            .builtin_scripts()
+            .into_iter()
+            .map(|(id, source)| (id, source.to_string()))
+            .collect();
+        let mut extracted = IndexMap::new();
+
+        for (sub_id, source) in scripts {
+            let ext_id = self
+                .ext_resource_entries
+                .keys()
+                .max()
+                .map_or(1, |id| id + 1);
+            let path = format!("{}{}.gd", path_prefix, sub_id);
+
+            self.ext_resource_entries.insert(
+                ext_id,
+                ExtResourceEntry {
+                    path: path.clone(),
+                    rtype: "Script".to_string(),
+                    uid: None,
+                },
+            );
+            if let Some(script) = Loader::parse_source(&path, &source) {
+                self.ext_resources.insert(ext_id, script);
+            }
+            self.sub_resources.shift_remove(&sub_id);
+
+            let (from, to) = (VarType::SubResource(sub_id), VarType::ExtResource(ext_id));
+            let properties = self
+                .nodes
+                .values_mut()
+                .chain(self.sub_resources.values_mut())
+                .map(|entry| &mut entry.properties)
+                .chain(std::iter::once(&mut self.resource));
+            for properties in properties {
+                for value in properties.values_mut() {
+                    Self::replace_reference(value, &from, &to);
+                }
+            }
+
+            extracted.insert(path, source);
+        }
+
+        extracted
+    }
+
+    fn replace_reference(value: &mut VarType, from: &VarType, to: &VarType) {
+        match value {
+            _ if value == from => *value = to.clone(),
+            VarType::Arr(values) => values
+                .iter_mut()
+                .for_each(|value| Self::replace_reference(value, from, to)),
+            VarType::Map(map) | VarType::Dict(map) => map
+                .values_mut()
+                .for_each(|value| Self::replace_reference(value, from, to)),
+            VarType::ArrMap(maps) => maps
+                .iter_mut()
+                .flat_map(|map| map.values_mut())
+                .for_each(|value| Self::replace_reference(value, from, to)),
+            _ => {}
+        }
+    }
+
+    /// Serializes into `.tscn` text (`.tres` for resources)
+    pub fn to_tscn(&self) -> String {
+        TscnWriter::write(self)
+    }
+
+    /// Serializes into binary `RSRC` format (`.scn` for scenes, `.res` for resources)
+    pub fn to_binary(&self) -> Result<Vec<u8>, String> {
+        BinaryWriter::write(self)
+    }
+
+    /// Exports into JSON, see [`json`] for the schema
+    #[cfg(feature = "json")]
+    pub fn to_json(&self) -> Result<String, String> {
+        json::to_json(self)
+    }
+
+    /// Imports from JSON, see [`json`] for the schema
+    #[cfg(feature = "json")]
+    pub fn from_json(json: &str) -> Result<Tscn, String> {
+        json::from_json(json)
+    }
+}
+
 impl NodeEntry {
     fn new_type(rtype: &str) -> Self {
         NodeEntry {
@@ -69,9 +510,13 @@ pub trait PropertyTrait {
     /// Inserts value into path (i.e. `into/path`)
     fn insert_to(&mut self, path: String, value: VarType);
     /// Gets value from path (i.e. `from/path`)
-    fn get_from(&self, path: &String) -> Option<&VarType>;
+    fn get_from(&self, path: &str) -> Option<&VarType>;
+    /// Gets value from path converted into `T`, `None` when missing or of other type
+    fn get_as<'a, T: TryFrom<&'a VarType>>(&'a self, path: &str) -> Option<T>;
     /// Get value mutably from path (i.e. `from/path`)
-    fn get_from_mut(&mut self, path: &String) -> Option<&mut VarType>;
+    fn get_from_mut(&mut self, path: &str) -> Option<&mut VarType>;
+    /// Unfolds nested values back into sorted `into/path` keys
+    fn flatten(&self) -> Vec<(String, &VarType)>;
 }
 
 impl PropertyTrait for PropertyMap {
@@ -85,7 +530,7 @@ impl PropertyTrait for PropertyMap {
                     Some(map)
                 } else {
                     self.insert(split[0].to_string(), VarType::Map(HashMap::new()));
-                    if let Some(VarType::Map(map)) = self.get_mut(&split[0].to_string()) {
+                    if let Some(VarType::Map(map)) = self.get_mut(split[0]) {
                         Some(map)
                     } else {
                         None
@@ -98,21 +543,23 @@ impl PropertyTrait for PropertyMap {
         }
     }
 
-    fn get_from(&self, path: &String) -> Option<&VarType> {
+    fn get_from(&self, path: &str) -> Option<&VarType> {
         let split: Vec<&str> = path.split('/').collect();
 
         if split.len() == 1 {
             self.get(path)
+        } else if let VarType::Map(map) = self.get(split[0])? {
+            map.get_from(&split[1..].join("/"))
         } else {
-            if let VarType::Map(map) = self.get(path)? {
-                map.get_from(&split[1..].join("/"))
-            } else {
-                None
-            }
+            None
         }
     }
 
-    fn get_from_mut(&mut self, path: &String) -> Option<&mut VarType> {
+    fn get_as<'a, T: TryFrom<&'a VarType>>(&'a self, path: &str) -> Option<T> {
+        T::try_from(self.get_from(path)?).ok()
+    }
+
+    fn get_from_mut(&mut self, path: &str) -> Option<&mut VarType> {
         let split: Vec<&str> = path.split('/').collect();
 
         if split.len() == 1 {
@@ -127,10 +574,28 @@ impl PropertyTrait for PropertyMap {
             None
         }
     }
+
+    fn flatten(&self) -> Vec<(String, &VarType)> {
+        let mut flattened: Vec<(String, &VarType)> = Vec::new();
+
+        for (key, value) in self {
+            if let VarType::Map(map) = value {
+                for (path, value) in map.flatten() {
+                    flattened.push((format!("{}/{}", key, path), value));
+                }
+            } else {
+                flattened.push((key.clone(), value));
+            }
+        }
+
+        flattened.sort_by(|a, b| a.0.cmp(&b.0));
+        flattened
+    }
 }
 
 pub struct Loader<'a> {
     map_path: HashMap<String, &'a Path>,
+    uid_cache: UidCache,
     ctx: IndexMap<String, usize>,
     context: Option<Node>,
     node_id: usize,
@@ -138,8 +603,27 @@ pub struct Loader<'a> {
     resource: HashMap<String, VarType>,
     sub_resources: IndexMap<usize, NodeEntry>,
     ext_resources: HashMap<usize, Tscn>,
+    ext_resource_entries: IndexMap<usize, ExtResourceEntry>,
+    connections: Vec<Connection>,
     nodes: IndexMap<usize, NodeEntry>,
-    last_prop: Option<String>,
+    format: usize,
+    editable_instances: Vec<String>,
+    /// Godot 4 string ids of ext and sub resources mapped to numeric ones
+    ext_ids: HashMap<String, usize>,
+    sub_ids: HashMap<String, usize>,
+    /// Property spanning several lines, until its value is closed
+    pending: Option<String>,
+    /// Current text line and line where the pending property started
+    line: usize,
+    pending_line: usize,
+    section: Option<Section>,
+    source_map: SourceMap,
+}
+
+impl<'a> Default for Loader<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<'a> Loader<'a> {
@@ -154,12 +638,13 @@ impl<'a> Loader<'a> {
         let nodes: IndexMap<usize, NodeEntry> = IndexMap::new();
         let sub_resources = IndexMap::new();
         let ext_resources: HashMap<usize, Tscn> = HashMap::new();
+        let ext_resource_entries = IndexMap::new();
 
         let node_id: usize = 0;
-        let last_prop: Option<String> = None;
 
         Loader {
             map_path,
+            uid_cache: UidCache::new(),
             ctx,
             context,
             node_id,
@@ -167,8 +652,18 @@ impl<'a> Loader<'a> {
             resource,
             sub_resources,
             ext_resources,
+            ext_resource_entries,
+            connections: Vec::new(),
             nodes,
-            last_prop,
+            format: TEXT_FORMAT,
+            editable_instances: Vec::new(),
+            ext_ids: HashMap::new(),
+            sub_ids: HashMap::new(),
+            pending: None,
+            line: 0,
+            pending_line: 0,
+            section: None,
+            source_map: SourceMap::default(),
         }
     }
 
@@ -176,25 +671,91 @@ impl<'a> Loader<'a> {
         self.map_path.insert(gdpath, syspath);
     }
 
+    /// Registers uid to path mappings used for `uid://` resolution
+    pub fn register_uid_cache(&mut self, cache: UidCache) {
+        self.uid_cache.extend(cache);
+    }
+
+    /// Resolves `uid://` path to the resource path it refers to
+    pub fn resolve_uid(&self, uid: &str) -> Option<String> {
+        self.uid_cache.resolve(uid).cloned()
+    }
+
+    /// Loads text or binary scene (resource), the decoder is picked by file magic
     pub fn load(&mut self, gdpath: String) -> Result<Tscn, String> {
-        if let Some(path) = self.get_path(gdpath) {
-            let contents = fs::read_to_string(path).expect("Something went wrong reading the file");
+        if let Some(path) = self.get_path(gdpath.clone()) {
+            let contents = fs::read(path).map_err(|e| e.to_string())?;
+
+            if BinaryReader::is_binary(&contents) {
+                return self.parse_binary(&contents);
+            }
+
+            let contents = String::from_utf8(contents).map_err(|e| e.to_string())?;
+
+            if let Some(tscn) = Self::parse_source(&gdpath, &contents) {
+                return Ok(tscn);
+            }
+
             return Ok(self.parse_tscn(&contents));
         }
 
         Err("Path not found".to_string())
     }
 
+    /// Shader and script files keep plain code, loaded as Shader (`code`) or GDScript
+    /// (`script/source`) resource
+    pub(crate) fn parse_source(gdpath: &str, contents: &str) -> Option<Tscn> {
+        let (rtype, key) = match gdpath.rsplit('.').next()? {
+            "shader" | "gdshader" => ("Shader", "code"),
+            "gd" => ("GDScript", "script/source"),
+            _ => return None,
+        };
+
+        let mut resource = PropertyMap::new();
+        resource.insert_to(key.to_string(), VarType::Str(contents.to_string()));
+
+        Some(Tscn {
+            rtype: rtype.to_string(),
+            nodes: IndexMap::new(),
+            resource,
+            sub_resources: IndexMap::new(),
+            ext_resources: HashMap::new(),
+            ext_resource_entries: IndexMap::new(),
+            connections: Vec::new(),
+            format: TEXT_FORMAT,
+            editable_instances: Vec::new(),
+        })
+    }
+
+    pub fn parse_binary(&mut self, data: &[u8]) -> Result<Tscn, String> {
+        let mut tscn = BinaryReader::read(data)?;
+
+        for (id, ext) in tscn.ext_resource_entries.clone() {
+            let mut loader = self.clone_loader();
+
+            if let Ok(ext_tscn) = loader.load(ext.path) {
+                tscn.ext_resources.insert(id, ext_tscn);
+            }
+        }
+
+        Ok(tscn)
+    }
+
     fn clone_loader(&self) -> Self {
         let mut loader = Loader::new();
         loader.map_path = self.map_path.clone();
+        loader.uid_cache = self.uid_cache.clone();
 
         loader
     }
 
     fn get_path(&self, gdpath: String) -> Option<PathBuf> {
+        if gdpath.starts_with(Uid::PREFIX) {
+            return self.get_path(self.resolve_uid(&gdpath)?);
+        }
+
         for (spath, syspath) in &self.map_path {
-            if gdpath.len() >= spath.len() + 1 && spath.as_str() == &gdpath[0..spath.len()] {
+            if gdpath.len() > spath.len() && spath.as_str() == &gdpath[0..spath.len()] {
                 let jpath = &gdpath[spath.len() + 1..];
                 let joined_path = syspath.join(jpath);
 
@@ -209,13 +770,36 @@ impl<'a> Loader<'a> {
 
     fn parse_node(&mut self, line: &str) {
         let (node_type, attributes) = TscnHelper::parse_node(line);
-        self.context = Some(TscnHelper::get_node(node_type, attributes));
+        let mut node = TscnHelper::get_node(node_type, attributes);
+        if !node.string_id.is_empty() {
+            node.id = match node.node_type {
+                NodeType::ExtResource => Self::map_id(&mut self.ext_ids, &node.string_id),
+                _ => Self::map_id(&mut self.sub_ids, &node.string_id),
+            };
+        }
+        if !node.instance_string_id.is_empty() {
+            node.instance_resource_id = Self::map_id(&mut self.ext_ids, &node.instance_string_id);
+        }
+        self.context = Some(node);
 
         if self.context.is_none() {
             return;
         }
 
         let node = self.context.clone().unwrap();
+        self.section = match node.node_type {
+            NodeType::GdScene | NodeType::GdResource => Some(Section::Header),
+            NodeType::ExtResource => Some(Section::ExtResource(node.id)),
+            NodeType::SubResource => Some(Section::SubResource(node.id)),
+            NodeType::Node => Some(Section::Node(self.node_id)),
+            NodeType::Resource => Some(Section::Resource),
+            NodeType::Connection => Some(Section::Connection(self.connections.len())),
+            NodeType::Editable => None,
+        };
+        if let Some(section) = self.section {
+            self.source_map.insert_section(section, self.line);
+        }
+
         match node.node_type {
             NodeType::SubResource => {
                 self.sub_resources
@@ -223,13 +807,15 @@ impl<'a> Loader<'a> {
             }
 
             NodeType::Node => {
-                let entry: NodeEntry = if node.parent == String::from("") {
+                let entry: NodeEntry = if node.parent.is_empty() {
                     self.ctx.insert(".".to_string(), self.node_id);
 
                     NodeEntry {
                         path: format!("/{}", node.name),
                         name: node.name,
                         rtype: node.rtype,
+                        instance: node.instance_resource_id,
+                        groups: node.groups,
                         ..NodeEntry::default()
                     }
                 } else {
@@ -259,6 +845,7 @@ impl<'a> Loader<'a> {
                         rtype: node.rtype,
                         parent_id: *parent_id,
                         instance: node.instance_resource_id,
+                        groups: node.groups,
                         ..NodeEntry::default()
                     }
                 };
@@ -267,15 +854,45 @@ impl<'a> Loader<'a> {
                 self.node_id += 1;
             }
 
-            NodeType::GdResource => {
-                self.rtype = node.rtype;
+            NodeType::GdScene | NodeType::GdResource => {
+                if node.node_type == NodeType::GdResource {
+                    self.rtype = node.rtype;
+                }
+                if node.format != 0 {
+                    self.format = node.format;
+                }
+                self.source_map.set_load_steps(node.load_steps);
             }
 
             NodeType::Resource => (),
-            NodeType::GdScene => (),
+            NodeType::Editable => self.editable_instances.push(node.path),
+            NodeType::Connection => {
+                self.connections.push(Connection {
+                    signal: node.signal,
+                    from: node.from,
+                    to: node.to,
+                    method: node.method,
+                    flags: node.flags,
+                });
+            }
             NodeType::ExtResource => {
+                // Stale uids fall back to the declared path
+                let path = self
+                    .resolve_uid(&node.uid)
+                    .filter(|path| self.get_path(path.clone()).is_some())
+                    .unwrap_or(node.path);
+
+                self.ext_resource_entries.insert(
+                    node.id,
+                    ExtResourceEntry {
+                        path: path.clone(),
+                        rtype: node.rtype,
+                        uid: Uid::from_text(&node.uid),
+                    },
+                );
+
                 let mut loader = self.clone_loader();
-                let load = loader.load(node.path);
+                let load = loader.load(path);
 
                 if let Ok(tscn) = load {
                     self.ext_resources.insert(node.id, tscn);
@@ -284,6 +901,49 @@ impl<'a> Loader<'a> {
         }
     }
 
+    /// Maps Godot 4 string id to numeric one, the numeric prefix (`"1_x5k2p"`) is kept when
+    /// it's still free
+    fn map_id(ids: &mut HashMap<String, usize>, string_id: &str) -> usize {
+        if let Some(id) = ids.get(string_id) {
+            return *id;
+        }
+
+        let prefix = string_id
+            .split('_')
+            .next()
+            .and_then(|n| n.parse::<usize>().ok())
+            .filter(|id| *id > 0 && !ids.values().any(|v| v == id));
+        let id = prefix.unwrap_or_else(|| ids.values().max().map_or(1, |max| max + 1));
+
+        ids.insert(string_id.to_string(), id);
+        id
+    }
+
+    /// Replaces Godot 4 string id references with the mapped numeric ones
+    fn map_references(&mut self, value: &mut VarType) {
+        match value {
+            VarType::None(text) => {
+                let mapped = match TscnHelper::string_reference(text) {
+                    Some((NodeType::SubResource, id)) => {
+                        VarType::SubResource(Self::map_id(&mut self.sub_ids, id))
+                    }
+                    Some((_, id)) => VarType::ExtResource(Self::map_id(&mut self.ext_ids, id)),
+                    None => return,
+                };
+                *value = mapped;
+            }
+            VarType::Arr(values) => values.iter_mut().for_each(|v| self.map_references(v)),
+            VarType::Map(map) | VarType::Dict(map) => {
+                map.values_mut().for_each(|v| self.map_references(v))
+            }
+            VarType::ArrMap(maps) => maps
+                .iter_mut()
+                .flat_map(|map| map.values_mut())
+                .for_each(|v| self.map_references(v)),
+            _ => (),
+        }
+    }
+
     fn get_ctxnode_props(&mut self) -> Option<(String, &mut PropertyMap)> {
         if let Some(node) = self.context.clone() {
             match node.node_type {
@@ -307,46 +967,59 @@ impl<'a> Loader<'a> {
     }
 
     pub fn parse_line(&mut self, line: &str) {
-        if line.is_empty() || line.trim() == "}" || line.trim_matches(' ') == "}]" {
+        self.line += 1;
+
+        if let Some(mut pending) = self.pending.take() {
+            pending.push('\n');
+            pending.push_str(line);
+
+            if TscnHelper::is_complete(&pending) {
+                self.parse_property(&pending);
+            } else {
+                self.pending = Some(pending);
+            }
             return;
         }
 
-        let lprop_clone = self.last_prop.clone();
+        if line.trim().is_empty() {
+            return;
+        }
 
         // If it is node block definition
         if line.check_borders('[', ']') {
-            self.parse_node(&line);
+            self.parse_node(line);
             return;
         }
 
-        if let Some((rtype, ctxprops)) = self.get_ctxnode_props() {
-            if line.trim() == "}, {" {
-                if let Some(prop) = lprop_clone {
-                    if let Some(VarType::ArrMap(arr_map)) = ctxprops.get_from_mut(&prop) {
-                        arr_map.push(HashMap::default());
-                    }
-                }
-                return;
-            }
+        self.pending_line = self.line;
+        if TscnHelper::is_complete(line) {
+            self.parse_property(line);
+        } else {
+            self.pending = Some(line.to_string());
+        }
+    }
 
-            if let Some(command) = TscnHelper::parse_command(line, rtype.as_str()) {
-                ctxprops.insert_to(command.lhs.clone(), command.rhs);
-                self.last_prop = Some(command.lhs);
-                return;
-            }
+    /// Lines of the parsed text, empty for binary resources
+    pub fn source_map(&self) -> &SourceMap {
+        &self.source_map
+    }
 
-            if let Some(command) = TscnHelper::parse_obj(line, rtype.as_str()) {
-                if let Some(prop) = lprop_clone {
-                    let node_prop = ctxprops.get_from_mut(&prop);
-                    if let Some(VarType::Map(obj)) = node_prop {
-                        obj.insert(command.lhs, command.rhs);
-                        return;
-                    }
-                    if let Some(VarType::ArrMap(arr_map)) = node_prop {
-                        if let Some(obj) = arr_map.last_mut() {
-                            obj.insert(command.lhs, command.rhs);
-                        }
-                    }
+    fn parse_property(&mut self, text: &str) {
+        let (section, line) = (self.section, self.pending_line);
+        let rtype = match self.get_ctxnode_props() {
+            Some((rtype, _)) => rtype,
+            None => return,
+        };
+
+        if let Some(mut command) = TscnHelper::parse_command(text, rtype.as_str()) {
+            self.map_references(&mut command.rhs);
+
+            if let Some((_, ctxprops)) = self.get_ctxnode_props() {
+                let key = command.lhs.clone();
+                ctxprops.insert_to(command.lhs, command.rhs);
+
+                if let Some(section) = section {
+                    self.source_map.insert_property(section, key, line);
                 }
             }
         }
@@ -363,6 +1036,368 @@ impl<'a> Loader<'a> {
             resource: self.resource.clone(),
             sub_resources: self.sub_resources.clone(),
             ext_resources: self.ext_resources.clone(),
+            ext_resource_entries: self.ext_resource_entries.clone(),
+            connections: self.connections.clone(),
+            format: self.format,
+            editable_instances: self.editable_instances.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::types::{Transform2D, Transform2DTrait};
+    use crate::*;
+
+    const SCENE: &str = r#"[gd_scene load_steps=2 format=2]
+
+[ext_resource path="res://Enemy.tscn" type="PackedScene" id=1]
+
+[node name="Level" type="Node2D"]
+position = Vector2( 100, 0 )
+
+[node name="Room" type="Node2D" parent="."]
+position = Vector2( 10, 20 )
+rotation = 1.5708
+scale = Vector2( 2, 2 )
+
+[node name="Spawn" type="Position2D" parent="Room"]
+position = Vector2( 5, 0 )
+
+[node name="Marker" type="Node2D" parent="Room/Spawn"]
+position = Vector2( 1, 1 )
+top_level = true
+
+[node name="Enemy" parent="Room" instance=ExtResource( 1 )]
+
+[node name="HUD" type="CanvasLayer" parent="."]
+
+[node name="Icon" type="Sprite" parent="HUD"]
+position = Vector2( 16, 16 )
+"#;
+
+    const ENEMY: &str = r#"[gd_scene format=2]
+
+[node name="Enemy" type="KinematicBody2D"]
+position = Vector2( 0, 3 )
+"#;
+
+    fn assert_origin(transform: Transform2D, x: f32, y: f32) {
+        let origin = transform.get_origin();
+        assert!(
+            (origin.x - x).abs() < 1e-3 && (origin.y - y).abs() < 1e-3,
+            "{}",
+            origin
+        );
+    }
+
+    #[test]
+    fn test_global_transform() {
+        let mut tscn = Loader::new().parse_tscn(SCENE);
+        tscn.ext_resources
+            .insert(1, Loader::new().parse_tscn(ENEMY));
+
+        // Rotated by 90 degrees and scaled twice, `x` points down
+        assert_origin(tscn.global_transform(2).unwrap(), 110.0, 30.0);
+        assert!((tscn.global_transform(2).unwrap().get_scale().x - 2.0).abs() < 1e-3);
+        assert_origin(tscn.global_transform(3).unwrap(), 1.0, 1.0);
+        assert_origin(tscn.global_transform(4).unwrap(), 104.0, 20.0);
+        assert_origin(tscn.global_transform(6).unwrap(), 16.0, 16.0);
+        assert_eq!(tscn.global_transform(7), None);
+
+        let godot4 = Loader::new().parse_tscn(
+            "[gd_scene format=3 uid=\"uid://c5bx2bt8hvq1y\"]\n\n\
+             [node name=\"Root\" type=\"Node2D\"]\n\
+             position = Vector2(100, 50)\n\n\
+             [node name=\"Child\" type=\"Node2D\" parent=\".\"]\n\
+             position = Vector2(10, 5)\n\
+             scale = Vector2(1e+06, 2)\n",
+        );
+        assert_origin(godot4.global_transform(1).unwrap(), 110.0, 55.0);
+        assert_eq!(
+            godot4.nodes[&1].properties["scale"],
+            VarType::Vector(Vector2::new(1e6, 2.0))
+        );
+    }
+
+    const SCENE_CANVAS: &str = r#"[gd_scene format=2]
+
+[node name="Level" type="Node2D"]
+position = Vector2( 100, 0 )
+
+[node name="Spawner" type="Timer" parent="."]
+
+[node name="Enemy" type="Sprite" parent="Spawner"]
+position = Vector2( 5, 5 )
+
+[node name="Menu" type="Control" parent="."]
+rect_position = Vector2( 10, 20 )
+rect_rotation = 90.0
+rect_pivot_offset = Vector2( 4, 0 )
+
+[node name="Button" type="Button" parent="Menu"]
+rect_position = Vector2( 2, 0 )
+
+[node name="Skeleton" type="Skeleton2D" parent="."]
+
+[node name="Arm" type="Sprite" parent="Skeleton"]
+position = Vector2( 10, 5 )
+"#;
+
+    #[test]
+    fn test_global_transform_canvas() {
+        let tscn = Loader::new().parse_tscn(SCENE_CANVAS);
+
+        // Timer isn't a CanvasItem, the sprite starts a new chain
+        assert_origin(tscn.global_transform(2).unwrap(), 5.0, 5.0);
+
+        // Rotated by 90 degrees around the pivot, (0, 0) lands at (14, 16)
+        assert_origin(tscn.local_transform(3).unwrap(), 14.0, 16.0);
+        assert_origin(tscn.global_transform(4).unwrap(), 114.0, 18.0);
+
+        // Skeleton2D isn't in the class table, the chain goes on through it
+        assert_origin(tscn.global_transform(6).unwrap(), 110.0, 5.0);
+    }
+
+    const SCENE_3D: &str = r#"[gd_scene format=2]
+
+[node name="World" type="Spatial"]
+transform = Transform( 1, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 10 )
+
+[node name="Turret" type="Spatial" parent="."]
+transform = Transform( 0, 0, 2, 0, 2, 0, -2, 0, 0, 1, 2, 3 )
+
+[node name="Barrel" type="MeshInstance" parent="Turret"]
+transform = Transform3D(1, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 1)
+
+[node name="Effects" type="Node" parent="."]
+
+[node name="Light" type="OmniLight" parent="Effects"]
+transform = Transform( 1, 0, 0, 0, 1, 0, 0, 0, 1, 0, 5, 0 )
+"#;
+
+    #[test]
+    fn test_global_transform_3d() {
+        use crate::types::Transform3DTrait;
+        use nalgebra::Vector3;
+
+        let tscn = Loader::new().parse_tscn(SCENE_3D);
+        assert!(matches!(
+            tscn.nodes[&1].properties["transform"],
+            VarType::Transform3D(_)
+        ));
+        assert!(matches!(
+            tscn.nodes[&2].properties["transform"],
+            VarType::Transform3D(_)
+        ));
+        assert_eq!(
+            TscnWriter::write_value(&tscn.nodes[&1].properties["transform"]),
+            "Transform( 0, 0, 2, 0, 2, 0, -2, 0, 0, 1, 2, 3 )"
+        );
+
+        // Turret is rotated by 90 degrees around `y` and scaled twice
+        let turret = tscn.local_transform_3d(1).unwrap();
+        assert!(
+            (turret.get_euler() - Vector3::new(0.0, std::f32::consts::FRAC_PI_2, 0.0)).norm()
+                < 1e-5
+        );
+        assert_eq!(turret.get_scale(), Vector3::new(2.0, 2.0, 2.0));
+
+        let barrel = tscn.global_transform_3d(2).unwrap();
+        assert_eq!(barrel.get_origin(), Vector3::new(3.0, 2.0, 13.0));
+        assert_eq!(
+            barrel.xform(Vector3::new(1.0, 0.0, 0.0)),
+            Vector3::new(3.0, 2.0, 11.0)
+        );
+
+        // Effects is a plain Node, the light starts a new chain
+        let light = tscn.global_transform_3d(4).unwrap();
+        assert_eq!(light.get_origin(), Vector3::new(0.0, 5.0, 0.0));
+    }
+
+    const SCENE_SCRIPT: &str = r#"[gd_scene load_steps=3 format=2]
+
+[sub_resource type="GDScript" id=1]
+script/source = "extends Sprite
+
+export var greeting = \"hello\"
+"
+
+[sub_resource type="RectangleShape2D" id=2]
+
+[node name="Player" type="Sprite"]
+script = SubResource( 1 )
+"#;
+
+    #[test]
+    fn test_extract_builtin_scripts() {
+        let mut tscn = Loader::new().parse_tscn(SCENE_SCRIPT);
+        let source = "extends Sprite\n\nexport var greeting = \"hello\"\n";
+        assert_eq!(
+            tscn.builtin_scripts().into_iter().collect::<Vec<_>>(),
+            vec![(1, source)]
+        );
+
+        let extracted = tscn.extract_builtin_scripts("res://player_");
+        assert_eq!(extracted["res://player_1.gd"], source);
+        assert!(tscn.builtin_scripts().is_empty());
+        assert_eq!(tscn.nodes[&0].properties["script"], VarType::ExtResource(1));
+
+        let written = tscn.to_tscn();
+        assert!(written.contains("[ext_resource path=\"res://player_1.gd\" type=\"Script\" id=1]"));
+        assert!(!written.contains("GDScript"));
+
+        let script = types::GDScript::from_node(&tscn, 0).unwrap();
+        assert_eq!(
+            script.export("greeting").unwrap().default.as_deref(),
+            Some("\"hello\"")
+        );
+    }
+
+    const SCENE_LEVEL: &str = r#"[gd_scene load_steps=2 format=2]
+
+[ext_resource path="res://Player.tscn" type="PackedScene" id=1]
+
+[node name="Level" type="Node2D"]
+
+[node name="Player" parent="." instance=ExtResource( 1 )]
+position = Vector2( 4, 4 )
+
+[node name="Sprite" parent="Player"]
+flip_h = true
+
+[editable path="Player"]
+"#;
+
+    const SCENE_PLAYER: &str = r#"[gd_scene format=2]
+
+[node name="Player" type="KinematicBody2D"]
+position = Vector2( 1, 1 )
+collision_mask = 3
+
+[node name="Sprite" type="Sprite" parent="."]
+frame = 2
+"#;
+
+    #[test]
+    fn test_effective_properties() {
+        let mut tscn = Loader::new().parse_tscn(SCENE_LEVEL);
+        tscn.ext_resources
+            .insert(1, Loader::new().parse_tscn(SCENE_PLAYER));
+
+        let player = tscn.effective_properties(1).unwrap();
+        assert_eq!(player["position"].provenance, Provenance::Local);
+        assert_eq!(
+            player["position"].value,
+            VarType::Vector(Vector2::new(4.0, 4.0))
+        );
+        assert_eq!(player["collision_mask"].provenance, Provenance::Instance(1));
+        assert_eq!(player["collision_layer"].provenance, Provenance::Default);
+        assert_eq!(player["collision_layer"].value, VarType::Num(1));
+
+        let sprite = tscn.effective_properties(2).unwrap();
+        assert_eq!(sprite["flip_h"].provenance, Provenance::Local);
+        assert_eq!(sprite["frame"].provenance, Provenance::Instance(1));
+        assert_eq!(sprite["centered"].value, VarType::Bool(true));
+        assert_eq!(sprite["centered"].provenance, Provenance::Default);
+
+        let keys: Vec<&String> = sprite.keys().collect();
+        assert!(keys.windows(2).all(|pair| pair[0] < pair[1]));
+
+        // Godot 3 saves `rotation_degrees` and `margin_*`, Godot 4 `rotation` and `offset_*`
+        assert_eq!(player["rotation_degrees"].value, VarType::Float(0.0));
+        assert!(!player.contains_key("rotation") && !player.contains_key("skew"));
+
+        let godot3 = ClassDefaults::builtin_for(2).defaults("Button");
+        assert_eq!(godot3.get("margin_left"), Some(&VarType::Float(0.0)));
+        assert!(!godot3.contains_key("offset_left"));
+        let godot4 = ClassDefaults::builtin_for(3).defaults("Button");
+        assert_eq!(godot4.get("offset_left"), Some(&VarType::Float(0.0)));
+        assert!(!godot4.contains_key("margin_left"));
+    }
+
+    const SCENE_GODOT4: &str = r#"[gd_scene load_steps=4 format=3 uid="uid://b6x0qmsiqa1qj"]
+
+[ext_resource type="PackedScene" path="res://enemy.tscn" id="2_enemy"]
+[ext_resource type="Texture2D" path="res://icon.svg" id="1_x5k2p"]
+
+[sub_resource type="RectangleShape2D" id="RectangleShape2D_abc12"]
+custom_solver_bias = 0.25
+
+[sub_resource type="RectangleShape2D" id="RectangleShape2D_def34"]
+custom_solver_bias = 0.75
+
+[node name="Level" type="Node2D"]
+
+[node name="Wall" type="CollisionShape2D" parent="."]
+shape = SubResource("RectangleShape2D_abc12")
+
+[node name="Floor" type="CollisionShape2D" parent="."]
+shape = SubResource("RectangleShape2D_def34")
+
+[node name="Icon" type="Sprite2D" parent="."]
+texture = ExtResource("1_x5k2p")
+
+[node name="Enemy" parent="." instance=ExtResource("2_enemy")]
+"#;
+
+    #[test]
+    fn test_string_ids() {
+        let tscn = Loader::new().parse_tscn(SCENE_GODOT4);
+        assert_eq!(tscn.sub_resources.len(), 2);
+        assert_eq!(tscn.ext_resource_entries[&1].path, "res://icon.svg");
+        assert_eq!(tscn.ext_resource_entries[&2].path, "res://enemy.tscn");
+        assert_eq!(tscn.nodes[&4].instance, 2);
+        assert_eq!(
+            tscn.nodes[&3].properties["texture"],
+            VarType::ExtResource(1)
+        );
+
+        for (node_id, bias) in [(1, 0.25), (2, 0.75)] {
+            let id = match tscn.nodes[&node_id].properties["shape"] {
+                VarType::SubResource(id) => id,
+                ref value => panic!("{:?}", value),
+            };
+            assert_eq!(
+                tscn.sub_resources[&id].properties["custom_solver_bias"],
+                VarType::Float(bias)
+            );
         }
     }
+
+    #[test]
+    fn test_resolve_uid() {
+        let dir = std::env::temp_dir().join(format!("tscn-parser-uid-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("enemy.tscn"), ENEMY).unwrap();
+
+        let mut cache = UidCache::new();
+        cache.insert(Uid::new(34).unwrap(), "res://moved/enemy.tscn".to_string());
+        cache.insert(Uid::new(35).unwrap(), "res://enemy.tscn".to_string());
+
+        let mut loader = Loader::new();
+        loader.register_path("res:/".to_string(), &dir);
+        loader.register_uid_cache(cache);
+        assert_eq!(
+            loader.resolve_uid("uid://ba"),
+            Some("res://moved/enemy.tscn".to_string())
+        );
+        assert_eq!(loader.resolve_uid("uid://cecaux1sm7mo0"), None);
+
+        let tscn = loader.parse_tscn(
+            r#"[gd_scene load_steps=3 format=3]
+
+[ext_resource type="PackedScene" uid="uid://ba" path="res://enemy.tscn" id="1_stale"]
+[ext_resource type="PackedScene" uid="uid://bb" path="res://old.tscn" id="2_moved"]
+"#,
+        );
+        fs::remove_dir_all(&dir).unwrap();
+
+        // Stale uid falls back to the declared path, valid one wins over it
+        assert_eq!(tscn.ext_resource_entries[&1].path, "res://enemy.tscn");
+        assert_eq!(tscn.ext_resource_entries[&2].path, "res://enemy.tscn");
+        assert_eq!(tscn.ext_resource_entries[&1].uid, Uid::new(34));
+        assert_eq!(tscn.ext_resources[&1].nodes[&0].name, "Enemy");
+        assert_eq!(tscn.ext_resources[&2].nodes[&0].name, "Enemy");
+    }
 }
@@ -0,0 +1,587 @@
+//! Scene linter, rules run over a parsed `Tscn` and are configured from TOML:
+//!
+//! ```toml
+//! # Directory `res://` paths are resolved against, missing ext resources
+//! # are only reported when it's set
+//! project_root = "game"
+//!
+//! [rules]
+//! unused_sub_resource = "error"
+//! empty_group = "off"
+//! ```
+//!
+//! Severities are `off`, `info`, `warning` and `error`, rules which aren't listed keep
+//! their default severity. Reports are written as `file:line: severity[rule]: message`
+//! lines, or as JSON for CI.
+
+use crate::{PropertyMap, Section, SourceMap, Tscn, VarType};
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::PathBuf;
+
+/// Physics bodies and areas, which need a collision shape child
+const COLLISION_OBJECTS: &[&str] = &[
+    "Area2D",
+    "StaticBody2D",
+    "KinematicBody2D",
+    "CharacterBody2D",
+    "RigidBody2D",
+    "AnimatableBody2D",
+    "Area",
+    "Area3D",
+    "StaticBody",
+    "StaticBody3D",
+    "KinematicBody",
+    "CharacterBody3D",
+    "RigidBody",
+    "RigidBody3D",
+    "AnimatableBody3D",
+    "VehicleBody",
+    "VehicleBody3D",
+];
+
+const COLLISION_SHAPES: &[&str] = &[
+    "CollisionShape2D",
+    "CollisionPolygon2D",
+    "CollisionShape",
+    "CollisionPolygon",
+    "CollisionShape3D",
+    "CollisionPolygon3D",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Rule {
+    /// Ext resource file doesn't exist under the project root
+    MissingExtResource,
+    /// Sub resource isn't reachable from nodes or the resource
+    UnusedSubResource,
+    /// Ext resource isn't referenced or instanced
+    UnusedExtResource,
+    /// Siblings with the same name
+    DuplicateSiblingName,
+    /// Header `load_steps` doesn't match resource count
+    LoadSteps,
+    /// Connection from or to a node which doesn't exist
+    MissingConnectionNode,
+    /// Physics body or area without collision shape children
+    MissingCollisionShape,
+    /// Node group with empty name
+    EmptyGroup,
+}
+
+impl Rule {
+    pub const ALL: [Rule; 8] = [
+        Rule::MissingExtResource,
+        Rule::UnusedSubResource,
+        Rule::UnusedExtResource,
+        Rule::DuplicateSiblingName,
+        Rule::LoadSteps,
+        Rule::MissingConnectionNode,
+        Rule::MissingCollisionShape,
+        Rule::EmptyGroup,
+    ];
+
+    /// Name used in the config and reports (i.e. `unused_sub_resource`)
+    pub fn name(self) -> &'static str {
+        match self {
+            Rule::MissingExtResource => "missing_ext_resource",
+            Rule::UnusedSubResource => "unused_sub_resource",
+            Rule::UnusedExtResource => "unused_ext_resource",
+            Rule::DuplicateSiblingName => "duplicate_sibling_name",
+            Rule::LoadSteps => "load_steps",
+            Rule::MissingConnectionNode => "missing_connection_node",
+            Rule::MissingCollisionShape => "missing_collision_shape",
+            Rule::EmptyGroup => "empty_group",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Rule> {
+        Rule::ALL.iter().copied().find(|rule| rule.name() == name)
+    }
+
+    /// Broken scenes are errors, leftovers and suspicious setups are warnings
+    pub fn default_severity(self) -> Severity {
+        match self {
+            Rule::MissingExtResource | Rule::DuplicateSiblingName | Rule::MissingConnectionNode => {
+                Severity::Error
+            }
+            Rule::UnusedSubResource
+            | Rule::UnusedExtResource
+            | Rule::LoadSteps
+            | Rule::MissingCollisionShape
+            | Rule::EmptyGroup => Severity::Warning,
+        }
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Off,
+    Info,
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Severity::Off => "off",
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct LintConfig {
+    /// Directory `res://` paths are resolved against
+    pub project_root: Option<PathBuf>,
+    /// Severity overrides by rule
+    pub rules: HashMap<Rule, Severity>,
+}
+
+/// Config as written, rules are keyed by name
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct TomlConfig {
+    project_root: Option<PathBuf>,
+    rules: HashMap<String, Severity>,
+}
+
+impl LintConfig {
+    /// Reads config, unknown keys and rule names are errors
+    pub fn from_toml(toml: &str) -> Result<Self, String> {
+        let config: TomlConfig = toml::from_str(toml).map_err(|e| e.to_string())?;
+
+        let mut rules = HashMap::new();
+        for (name, severity) in config.rules {
+            let rule = Rule::from_name(&name).ok_or(format!("Unknown rule `{}`", name))?;
+            rules.insert(rule, severity);
+        }
+
+        Ok(LintConfig {
+            project_root: config.project_root,
+            rules,
+        })
+    }
+
+    pub fn severity(&self, rule: Rule) -> Severity {
+        self.rules
+            .get(&rule)
+            .copied()
+            .unwrap_or_else(|| rule.default_severity())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Finding {
+    pub rule: Rule,
+    pub severity: Severity,
+    pub file: String,
+    /// 1-based line, `None` without source map
+    pub line: Option<usize>,
+    pub section: Section,
+    pub message: String,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{}:{}: ", self.file, line)?,
+            None => write!(f, "{}: ", self.file)?,
+        }
+
+        write!(f, "{}[{}]: {}", self.severity, self.rule, self.message)
+    }
+}
+
+/// Findings of one or several files, in rule order per file
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct Report {
+    pub findings: Vec<Finding>,
+}
+
+impl Report {
+    pub fn extend(&mut self, report: Report) {
+        self.findings.extend(report.findings);
+    }
+
+    pub fn count(&self, severity: Severity) -> usize {
+        self.findings
+            .iter()
+            .filter(|finding| finding.severity == severity)
+            .count()
+    }
+
+    /// CI should fail on errors
+    pub fn has_errors(&self) -> bool {
+        self.count(Severity::Error) > 0
+    }
+
+    /// `{ "findings": [{ "rule", "severity", "file", "line", "section", "message" }] }`
+    pub fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string_pretty(self).map_err(|e| e.to_string())
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for finding in &self.findings {
+            writeln!(f, "{}", finding)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Finding before severity and line are resolved
+struct Issue {
+    rule: Rule,
+    section: Section,
+    message: String,
+}
+
+impl Issue {
+    fn new(rule: Rule, section: Section, message: String) -> Self {
+        Issue {
+            rule,
+            section,
+            message,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Linter {
+    pub config: LintConfig,
+}
+
+impl Linter {
+    pub fn new(config: LintConfig) -> Self {
+        Linter { config }
+    }
+
+    /// Runs enabled rules, lines are resolved with the source map of the text loader.
+    /// `load_steps` is only checked with source map, as it isn't kept in `Tscn`
+    pub fn lint(&self, tscn: &Tscn, file: &str, source_map: Option<&SourceMap>) -> Report {
+        let mut issues = Vec::new();
+
+        for rule in Rule::ALL.iter().copied() {
+            if self.config.severity(rule) == Severity::Off {
+                continue;
+            }
+
+            match rule {
+                Rule::MissingExtResource => self.missing_ext_resources(tscn, &mut issues),
+                Rule::UnusedSubResource => Self::unused_sub_resources(tscn, &mut issues),
+                Rule::UnusedExtResource => Self::unused_ext_resources(tscn, &mut issues),
+                Rule::DuplicateSiblingName => Self::duplicate_sibling_names(tscn, &mut issues),
+                Rule::LoadSteps => Self::load_steps(tscn, source_map, &mut issues),
+                Rule::MissingConnectionNode => Self::missing_connection_nodes(tscn, &mut issues),
+                Rule::MissingCollisionShape => Self::missing_collision_shapes(tscn, &mut issues),
+                Rule::EmptyGroup => Self::empty_groups(tscn, &mut issues),
+            }
+        }
+
+        let findings = issues
+            .into_iter()
+            .map(|issue| Finding {
+                rule: issue.rule,
+                severity: self.config.severity(issue.rule),
+                file: file.to_string(),
+                line: source_map.and_then(|source_map| source_map.section_line(issue.section)),
+                section: issue.section,
+                message: issue.message,
+            })
+            .collect();
+
+        Report { findings }
+    }
+
+    fn missing_ext_resources(&self, tscn: &Tscn, issues: &mut Vec<Issue>) {
+        let root = match &self.config.project_root {
+            Some(root) => root,
+            None => return,
+        };
+
+        for (&id, ext) in &tscn.ext_resource_entries {
+            if let Some(path) = ext.path.strip_prefix("res://") {
+                if !root.join(path).exists() {
+                    issues.push(Issue::new(
+                        Rule::MissingExtResource,
+                        Section::ExtResource(id),
+                        format!("`{}` doesn't exist", ext.path),
+                    ));
+                }
+            }
+        }
+    }
+
+    /// Sub resources referenced from nodes or the resource, directly or through other
+    /// sub resources, and ext resources referenced along the way
+    fn references(tscn: &Tscn) -> (HashSet<usize>, HashSet<usize>) {
+        let mut subs = HashSet::new();
+        let mut exts: HashSet<usize> = tscn.nodes.values().map(|node| node.instance).collect();
+
+        let mut pending: Vec<&PropertyMap> = tscn.nodes.values().map(|n| &n.properties).collect();
+        pending.push(&tscn.resource);
+
+        while let Some(properties) = pending.pop() {
+            let mut values: Vec<&VarType> = properties.values().collect();
+            while let Some(value) = values.pop() {
+                match value {
+                    VarType::SubResource(id) if subs.insert(*id) => {
+                        if let Some(sub) = tscn.sub_resources.get(id) {
+                            pending.push(&sub.properties);
+                        }
+                    }
+                    VarType::ExtResource(id) => {
+                        exts.insert(*id);
+                    }
+                    VarType::Arr(arr) => values.extend(arr),
+                    VarType::Map(map) | VarType::Dict(map) => values.extend(map.values()),
+                    VarType::ArrMap(maps) => values.extend(maps.iter().flat_map(|m| m.values())),
+                    _ => {}
+                }
+            }
+        }
+
+        (subs, exts)
+    }
+
+    fn unused_sub_resources(tscn: &Tscn, issues: &mut Vec<Issue>) {
+        let (subs, _) = Self::references(tscn);
+
+        for (id, sub) in &tscn.sub_resources {
+            if !subs.contains(id) {
+                issues.push(Issue::new(
+                    Rule::UnusedSubResource,
+                    Section::SubResource(*id),
+                    format!("{} `SubResource( {} )` is never used", sub.rtype, id),
+                ));
+            }
+        }
+    }
+
+    fn unused_ext_resources(tscn: &Tscn, issues: &mut Vec<Issue>) {
+        let (_, exts) = Self::references(tscn);
+
+        for (id, ext) in &tscn.ext_resource_entries {
+            if !exts.contains(id) {
+                issues.push(Issue::new(
+                    Rule::UnusedExtResource,
+                    Section::ExtResource(*id),
+                    format!("`{}` is never used", ext.path),
+                ));
+            }
+        }
+    }
+
+    fn duplicate_sibling_names(tscn: &Tscn, issues: &mut Vec<Issue>) {
+        for node in tscn.nodes.values() {
+            let mut names = HashSet::new();
+            for child in &node.childrens {
+                let name = &tscn.nodes[child].name;
+                if !names.insert(name) {
+                    issues.push(Issue::new(
+                        Rule::DuplicateSiblingName,
+                        Section::Node(*child),
+                        format!("`{}` already has a child named `{}`", node.name, name),
+                    ));
+                }
+            }
+        }
+    }
+
+    fn load_steps(tscn: &Tscn, source_map: Option<&SourceMap>, issues: &mut Vec<Issue>) {
+        let declared = match source_map.and_then(SourceMap::load_steps) {
+            Some(declared) => declared,
+            None => return,
+        };
+
+        let expected = tscn.ext_resource_entries.len() + tscn.sub_resources.len() + 1;
+        if declared != expected {
+            issues.push(Issue::new(
+                Rule::LoadSteps,
+                Section::Header,
+                format!("`load_steps` is {}, expected {}", declared, expected),
+            ));
+        }
+    }
+
+    /// Paths through instanced scenes are looked up in the loaded scene, and are assumed
+    /// to exist when it wasn't loaded
+    fn node_exists(tscn: &Tscn, path: &str) -> bool {
+        if tscn.find_node(path).is_some() {
+            return true;
+        }
+
+        let segments: Vec<&str> = path.split('/').collect();
+        (1..segments.len()).any(|len| {
+            let instance = tscn
+                .find_node(&segments[..len].join("/"))
+                .map(|id| tscn.nodes[&id].instance)
+                .filter(|instance| *instance != 0);
+
+            match instance.map(|instance| tscn.ext_resources.get(&instance)) {
+                Some(Some(scene)) => Self::node_exists(scene, &segments[len..].join("/")),
+                Some(None) => true,
+                None => false,
+            }
+        })
+    }
+
+    fn missing_connection_nodes(tscn: &Tscn, issues: &mut Vec<Issue>) {
+        for (index, connection) in tscn.connections.iter().enumerate() {
+            for path in &[&connection.from, &connection.to] {
+                if !Self::node_exists(tscn, path) {
+                    issues.push(Issue::new(
+                        Rule::MissingConnectionNode,
+                        Section::Connection(index),
+                        format!("`{}` of signal `{}` doesn't exist", path, connection.signal),
+                    ));
+                }
+            }
+        }
+    }
+
+    fn missing_collision_shapes(tscn: &Tscn, issues: &mut Vec<Issue>) {
+        for (&id, node) in &tscn.nodes {
+            let rtype = tscn.node_type(id);
+            if !COLLISION_OBJECTS.contains(&rtype) {
+                continue;
+            }
+
+            let has_shape = |scene: &Tscn, children: &[usize]| {
+                children
+                    .iter()
+                    .any(|child| COLLISION_SHAPES.contains(&scene.node_type(*child)))
+            };
+
+            // Shapes of instanced bodies may come from the instanced scene
+            let instanced = match tscn.ext_resources.get(&node.instance) {
+                Some(scene) => scene
+                    .nodes
+                    .values()
+                    .next()
+                    .is_some_and(|root| has_shape(scene, &root.childrens)),
+                None if node.instance != 0 => continue,
+                None => false,
+            };
+
+            if !instanced && !has_shape(tscn, &node.childrens) {
+                issues.push(Issue::new(
+                    Rule::MissingCollisionShape,
+                    Section::Node(id),
+                    format!("{} `{}` has no collision shape", rtype, node.name),
+                ));
+            }
+        }
+    }
+
+    fn empty_groups(tscn: &Tscn, issues: &mut Vec<Issue>) {
+        for (&id, node) in &tscn.nodes {
+            if node.groups.iter().any(|group| group.trim().is_empty()) {
+                issues.push(Issue::new(
+                    Rule::EmptyGroup,
+                    Section::Node(id),
+                    format!("`{}` is in a group with empty name", node.name),
+                ));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lint::*;
+    use crate::Loader;
+
+    const SCENE: &str = r#"[gd_scene load_steps=3 format=2]
+
+[ext_resource path="res://level.gd" type="Script" id=1]
+[ext_resource path="res://icon.png" type="Texture" id=2]
+
+[sub_resource type="RectangleShape2D" id=1]
+
+[sub_resource type="CircleShape2D" id=2]
+
+[node name="Level" type="Node2D"]
+script = ExtResource( 1 )
+
+[node name="Wall" type="StaticBody2D" parent="." groups=[ "walls", "" ]]
+
+[node name="Shape" type="CollisionShape2D" parent="Wall"]
+shape = SubResource( 1 )
+
+[node name="Wall" type="Area2D" parent="."]
+
+[connection signal="body_entered" from="Wall" to="." method="_on_body_entered"]
+[connection signal="timeout" from="Timer" to="." method="_on_timeout"]
+"#;
+
+    const CONFIG: &str = r#"
+project_root = "."
+
+[rules]
+unused_ext_resource = "error"
+empty_group = "off"
+"#;
+
+    #[test]
+    fn test_lint() {
+        let mut config = LintConfig::from_toml(CONFIG).unwrap();
+        assert_eq!(config.project_root, Some(PathBuf::from(".")));
+
+        // Project with the script only, the texture is missing
+        let dir = std::env::temp_dir().join(format!("tscn-parser-lint-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("level.gd"), "extends Node2D\n").unwrap();
+        config.project_root = Some(dir.clone());
+        assert!(LintConfig::from_toml("[rules]\nunknown_rule = \"off\"").is_err());
+
+        let mut loader = Loader::new();
+        let tscn = loader.parse_tscn(SCENE);
+        let report = Linter::new(config).lint(&tscn, "level.tscn", Some(loader.source_map()));
+
+        let lines: Vec<String> = report.findings.iter().map(|f| f.to_string()).collect();
+        assert_eq!(
+            lines,
+            vec![
+                "level.tscn:4: error[missing_ext_resource]: `res://icon.png` doesn't exist",
+                "level.tscn:8: warning[unused_sub_resource]: CircleShape2D `SubResource( 2 )` is never used",
+                "level.tscn:4: error[unused_ext_resource]: `res://icon.png` is never used",
+                "level.tscn:18: error[duplicate_sibling_name]: `Level` already has a child named `Wall`",
+                "level.tscn:1: warning[load_steps]: `load_steps` is 3, expected 5",
+                "level.tscn:21: error[missing_connection_node]: `Timer` of signal `timeout` doesn't exist",
+                "level.tscn:18: warning[missing_collision_shape]: Area2D `Wall` has no collision shape",
+            ]
+        );
+        assert!(report.has_errors());
+        assert_eq!(report.count(Severity::Warning), 3);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let json: serde_json::Value = serde_json::from_str(&report.to_json().unwrap()).unwrap();
+        assert_eq!(json["findings"][0]["rule"], "missing_ext_resource");
+        assert_eq!(json["findings"][0]["line"], 4);
+        assert_eq!(json["findings"][0]["section"]["ExtResource"], 2);
+
+        let tscn = Loader::new().parse_tscn(SCENE);
+        let report = Linter::default().lint(&tscn, "level.tscn", None);
+        assert_eq!(report.count(Severity::Warning), 4);
+        assert!(report.findings.iter().all(|f| f.line.is_none()));
+    }
+}
@@ -0,0 +1,66 @@
+use crate::types::{SpriteFrame, SpriteFrames, VarType};
+use crate::{NodeEntry, PropertyTrait, Tscn};
+
+/// Playback state of AnimatedSprite (or AnimatedSprite2D / AnimatedSprite3D) node
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnimatedSprite {
+    /// SpriteFrames resource reference
+    pub frames: Option<VarType>,
+    pub animation: String,
+    pub frame: usize,
+    pub playing: bool,
+    pub speed_scale: f32,
+}
+
+impl AnimatedSprite {
+    pub fn from_node(node: &NodeEntry) -> Result<Self, String> {
+        if !node.rtype.starts_with("AnimatedSprite") {
+            return Err(format!("Expected AnimatedSprite node, got {}", node.rtype));
+        }
+
+        let properties = &node.properties;
+        // Godot 4 renamed `frames` and replaced `playing` with `autoplay`
+        let frames = properties
+            .get("frames")
+            .or_else(|| properties.get("sprite_frames"))
+            .cloned();
+        let playing = properties.get_as("playing").unwrap_or(false)
+            || properties
+                .get_as::<String>("autoplay")
+                .is_some_and(|autoplay| !autoplay.is_empty());
+
+        Ok(AnimatedSprite {
+            frames,
+            animation: properties
+                .get_as("animation")
+                .unwrap_or_else(|| "default".to_string()),
+            frame: properties.get_as::<isize>("frame").unwrap_or(0).max(0) as usize,
+            playing,
+            speed_scale: properties.get_as("speed_scale").unwrap_or(1.0),
+        })
+    }
+
+    /// Resolves the frames resource, ext resources must have been loaded
+    pub fn sprite_frames(&self, tscn: &Tscn) -> Result<SpriteFrames, String> {
+        let reference = self.frames.as_ref().ok_or("Sprite has no frames")?;
+        let (owner, rtype, properties) = tscn
+            .resource_owner(reference)
+            .ok_or("SpriteFrames resource is not loaded")?;
+
+        if rtype != "SpriteFrames" {
+            return Err(format!("Expected SpriteFrames resource, got {}", rtype));
+        }
+
+        SpriteFrames::from_properties(owner, properties)
+    }
+
+    /// Current frame of the current animation
+    pub fn current_frame(&self, frames: &SpriteFrames) -> Option<SpriteFrame> {
+        frames
+            .animations
+            .get(&self.animation)?
+            .frames
+            .get(self.frame)
+            .cloned()
+    }
+}
@@ -0,0 +1,158 @@
+use crate::types::{Animation, VarType};
+use crate::{NodeEntry, PropertyTrait, Tscn};
+
+use indexmap::IndexMap;
+use std::convert::TryFrom;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlendTime {
+    pub from: String,
+    pub to: String,
+    pub time: f32,
+}
+
+/// Animations and playback settings of AnimationPlayer node
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnimationPlayer {
+    /// Animation resource references (`anims/*`) by name, sorted by name
+    pub anims: IndexMap<String, VarType>,
+    pub autoplay: Option<String>,
+    pub current_animation: Option<String>,
+    pub playback_speed: f32,
+    pub default_blend_time: f32,
+    pub blend_times: Vec<BlendTime>,
+    pub root_node: String,
+}
+
+impl AnimationPlayer {
+    pub fn from_node(node: &NodeEntry) -> Result<Self, String> {
+        if node.rtype != "AnimationPlayer" {
+            return Err(format!("Expected AnimationPlayer node, got {}", node.rtype));
+        }
+
+        let properties = &node.properties;
+        let mut anims: Vec<(String, VarType)> = match properties.get("anims") {
+            Some(VarType::Map(anims)) => anims
+                .iter()
+                .map(|(name, reference)| (name.clone(), reference.clone()))
+                .collect(),
+            _ => Vec::new(),
+        };
+        anims.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let blend_times = match properties.get("blend_times") {
+            Some(VarType::Arr(arr)) => arr
+                .chunks_exact(3)
+                .filter_map(|blend| match blend {
+                    [VarType::Str(from), VarType::Str(to), time] => Some(BlendTime {
+                        from: from.clone(),
+                        to: to.clone(),
+                        time: f32::try_from(time).ok()?,
+                    }),
+                    _ => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        let non_empty = |key: &str| {
+            properties
+                .get_as::<String>(key)
+                .filter(|name| !name.is_empty())
+        };
+
+        Ok(AnimationPlayer {
+            anims: anims.into_iter().collect(),
+            autoplay: non_empty("autoplay"),
+            current_animation: non_empty("current_animation"),
+            playback_speed: properties.get_as("playback_speed").unwrap_or(1.0),
+            default_blend_time: properties
+                .get_as("playback_default_blend_time")
+                .unwrap_or(0.0),
+            blend_times,
+            root_node: match properties.get("root_node") {
+                Some(VarType::None(raw)) => raw
+                    .trim_start_matches("NodePath(")
+                    .trim_end_matches(')')
+                    .trim_matches('"')
+                    .to_string(),
+                _ => "..".to_string(),
+            },
+        })
+    }
+
+    /// Builds animation of the given name, ext resources must have been loaded
+    pub fn animation(&self, tscn: &Tscn, name: &str) -> Result<Animation, String> {
+        let reference = self
+            .anims
+            .get(name)
+            .ok_or(format!("Missing animation `{}`", name))?;
+        let (rtype, properties) = tscn
+            .resource_properties(reference)
+            .ok_or(format!("Animation `{}` resource is not loaded", name))?;
+
+        if rtype != "Animation" {
+            return Err(format!("Expected Animation resource, got {}", rtype));
+        }
+
+        Animation::from_properties(properties)
+    }
+
+    /// Blend time between two animations, falls back to the default one
+    pub fn blend_time(&self, from: &str, to: &str) -> f32 {
+        self.blend_times
+            .iter()
+            .find(|blend| blend.from == from && blend.to == to)
+            .map(|blend| blend.time)
+            .unwrap_or(self.default_blend_time)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::nodes::animation_player::*;
+    use crate::Loader;
+
+    const SCENE: &str = r#"[gd_scene load_steps=3 format=2]
+
+[ext_resource path="res://walk.tres" type="Animation" id=1]
+
+[sub_resource type="Animation" id=1]
+resource_name = "idle"
+length = 2.0
+
+[node name="AnimationPlayer" type="AnimationPlayer"]
+root_node = NodePath("../Player")
+current_animation = "walk"
+playback_speed = 1.5
+anims/walk = ExtResource( 1 )
+anims/idle = SubResource( 1 )
+"#;
+
+    #[test]
+    fn test_animation_player() {
+        let mut tscn = Loader::new().parse_tscn(SCENE);
+        tscn.ext_resources.insert(
+            1,
+            Loader::new().parse_tscn(
+                "[gd_resource type=\"Animation\" format=2]\n\n[resource]\nlength = 0.5\n",
+            ),
+        );
+
+        let player = AnimationPlayer::from_node(&tscn.nodes[&0]).unwrap();
+        assert_eq!(
+            player.anims.keys().collect::<Vec<_>>(),
+            vec!["idle", "walk"]
+        );
+        assert_eq!(player.autoplay, None);
+        assert_eq!(player.current_animation.as_deref(), Some("walk"));
+        assert_eq!(player.playback_speed, 1.5);
+        assert_eq!(player.root_node, "../Player");
+        assert_eq!(player.blend_time("idle", "walk"), 0.0);
+
+        assert_eq!(player.animation(&tscn, "idle").unwrap().length, 2.0);
+        assert_eq!(player.animation(&tscn, "walk").unwrap().length, 0.5);
+        assert!(player.animation(&tscn, "run").is_err());
+        assert!(AnimationPlayer::from_node(&NodeEntry::new_type("Node2D")).is_err());
+    }
+}
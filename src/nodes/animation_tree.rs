@@ -0,0 +1,271 @@
+use crate::nodes::AnimationPlayer;
+use crate::types::{AnimationNode, VarType};
+use crate::{PropertyTrait, Tscn};
+
+use indexmap::IndexMap;
+
+/// Tree root and parameters of AnimationTree node
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnimationTree {
+    pub tree_root: Option<AnimationNode>,
+    /// Path to AnimationPlayer relative to the tree node
+    pub anim_player: String,
+    pub active: bool,
+    /// Advance conditions (`parameters/conditions/*`) by name, sorted by name
+    pub conditions: IndexMap<String, bool>,
+}
+
+impl AnimationTree {
+    pub fn from_node(tscn: &Tscn, node_id: usize) -> Result<Self, String> {
+        let node = tscn
+            .nodes
+            .get(&node_id)
+            .ok_or(format!("Missing node {}", node_id))?;
+        if node.rtype != "AnimationTree" {
+            return Err(format!("Expected AnimationTree node, got {}", node.rtype));
+        }
+
+        let properties = &node.properties;
+        let tree_root = match properties.get("tree_root") {
+            Some(reference) => Some(AnimationNode::from_reference(tscn, reference)?),
+            None => None,
+        };
+
+        let mut conditions: Vec<(String, bool)> = match properties.get_from("parameters/conditions")
+        {
+            Some(VarType::Map(map)) => map
+                .iter()
+                .filter_map(|(name, value)| match value {
+                    VarType::Bool(value) => Some((name.clone(), *value)),
+                    _ => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+        conditions.sort_by(|a, b| a.0.cmp(&b.0));
+
+        Ok(AnimationTree {
+            tree_root,
+            anim_player: match properties.get("anim_player") {
+                Some(VarType::None(raw)) => raw
+                    .trim_start_matches("NodePath(")
+                    .trim_end_matches(')')
+                    .trim_matches('"')
+                    .to_string(),
+                _ => "".to_string(),
+            },
+            active: properties.get_as("active").unwrap_or(false),
+            conditions: conditions.into_iter().collect(),
+        })
+    }
+
+    /// Finds AnimationPlayer node id the tree at `node_id` plays with
+    pub fn player_node(&self, tscn: &Tscn, node_id: usize) -> Option<usize> {
+        if self.anim_player.is_empty() {
+            return None;
+        }
+
+        tscn.resolve_node_path(node_id, &self.anim_player)
+    }
+
+    /// Animations referenced by the tree which the player does not have
+    pub fn missing_animations(&self, player: &AnimationPlayer) -> Vec<String> {
+        self.tree_root
+            .as_ref()
+            .map(|root| root.animations())
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|name| !player.anims.contains_key(*name))
+            .map(String::from)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::nodes::animation_tree::*;
+    use crate::types::{StateMachine, SwitchMode};
+    use crate::Loader;
+
+    use nalgebra::Vector2;
+
+    const SCENE: &str = r#"[gd_scene load_steps=7 format=2]
+
+[sub_resource type="Animation" id=1]
+resource_name = "idle"
+length = 1.0
+
+[sub_resource type="AnimationNodeAnimation" id=2]
+animation = "idle"
+
+[sub_resource type="AnimationNodeAnimation" id=3]
+animation = "run"
+
+[sub_resource type="AnimationNodeStateMachineTransition" id=4]
+advance_condition = "moving"
+xfade_time = 0.2
+
+[sub_resource type="AnimationNodeStateMachineTransition" id=5]
+switch_mode = 2
+auto_advance = true
+
+[sub_resource type="AnimationNodeStateMachine" id=6]
+states/idle/node = SubResource( 2 )
+states/idle/position = Vector2( 100, 50 )
+states/run/node = SubResource( 3 )
+states/run/position = Vector2( 300, 50 )
+transitions = [ "idle", "run", SubResource( 4 ), "run", "idle", SubResource( 5 ) ]
+start_node = "idle"
+
+[node name="Player" type="KinematicBody2D"]
+
+[node name="AnimationPlayer" type="AnimationPlayer" parent="."]
+autoplay = "idle"
+playback_default_blend_time = 0.1
+anims/idle = SubResource( 1 )
+blend_times = [ "idle", "run", 0.3 ]
+
+[node name="AnimationTree" type="AnimationTree" parent="."]
+tree_root = SubResource( 6 )
+anim_player = NodePath("../AnimationPlayer")
+active = true
+parameters/playback = null
+parameters/conditions/moving = false
+"#;
+
+    #[test]
+    fn test_animation_tree() {
+        let tscn = Loader::new().parse_tscn(SCENE);
+
+        let player = AnimationPlayer::from_node(&tscn.nodes[&1]).unwrap();
+        assert_eq!(player.autoplay.as_deref(), Some("idle"));
+        assert_eq!(player.blend_time("idle", "run"), 0.3);
+        assert_eq!(player.blend_time("run", "idle"), 0.1);
+        assert_eq!(player.animation(&tscn, "idle").unwrap().length, 1.0);
+
+        let tree = AnimationTree::from_node(&tscn, 2).unwrap();
+        assert_eq!(tree.player_node(&tscn, 2), Some(1));
+        assert_eq!(tree.conditions.get("moving"), Some(&false));
+        assert_eq!(tree.missing_animations(&player), vec!["run".to_string()]);
+
+        let machine: &StateMachine = match &tree.tree_root {
+            Some(AnimationNode::StateMachine(machine)) => machine,
+            root => panic!("Unexpected tree root {:?}", root),
+        };
+        assert_eq!(machine.start_node.as_deref(), Some("idle"));
+        assert_eq!(machine.conditions(), vec!["moving"]);
+        assert_eq!(machine.transitions[1].switch_mode, SwitchMode::AtEnd);
+        assert_eq!(
+            machine.to_dot(),
+            "digraph StateMachine {\n    \"idle\" [label=\"idle\", shape=doublecircle];\n    \
+             \"run\" [label=\"run\", shape=ellipse];\n    \"idle\" -> \"run\" [label=\"moving\"];\n    \
+             \"run\" -> \"idle\" [style=bold];\n}\n"
+        );
+    }
+
+    const MACHINE: &str = r#"[gd_resource type="AnimationNodeStateMachine" load_steps=3 format=2]
+
+[sub_resource type="AnimationNodeAnimation" id=1]
+animation = "jump"
+
+[sub_resource type="AnimationNodeStateMachineTransition" id=2]
+switch_mode = 1
+
+[resource]
+states/jump/node = SubResource( 1 )
+states/jump/position = Vector2( 0, 0 )
+transitions = [ "jump", "jump", SubResource( 2 ) ]
+"#;
+
+    #[test]
+    fn test_external_tree_root() {
+        let mut tscn = Loader::new().parse_tscn(
+            r#"[gd_scene load_steps=2 format=2]
+
+[ext_resource path="res://machine.tres" type="AnimationNodeStateMachine" id=1]
+
+[node name="AnimationTree" type="AnimationTree"]
+tree_root = ExtResource( 1 )
+"#,
+        );
+        tscn.ext_resources
+            .insert(1, Loader::new().parse_tscn(MACHINE));
+
+        // Sub resources of the state machine are looked up in `machine.tres`
+        let tree = AnimationTree::from_node(&tscn, 0).unwrap();
+        let root = tree.tree_root.unwrap();
+        assert_eq!(root.animations(), vec!["jump"]);
+        match root {
+            AnimationNode::StateMachine(machine) => {
+                assert_eq!(machine.transitions[0].switch_mode, SwitchMode::Sync)
+            }
+            root => panic!("Unexpected tree root {:?}", root),
+        }
+    }
+
+    const SCENE_GODOT4: &str = r#"[gd_scene load_steps=7 format=3 uid="uid://dq1v3k2y8n4xa"]
+
+[sub_resource type="AnimationNodeAnimation" id="AnimationNodeAnimation_k4c0p"]
+animation = &"idle"
+
+[sub_resource type="AnimationNodeAnimation" id="AnimationNodeAnimation_3lq7h"]
+animation = &"jump"
+
+[sub_resource type="AnimationNodeStateMachineTransition" id="AnimationNodeStateMachineTransition_vd1ku"]
+advance_mode = 2
+
+[sub_resource type="AnimationNodeStateMachineTransition" id="AnimationNodeStateMachineTransition_o6m2r"]
+advance_condition = &"jumping"
+
+[sub_resource type="AnimationNodeStateMachineTransition" id="AnimationNodeStateMachineTransition_2bq8w"]
+switch_mode = 2
+advance_mode = 0
+
+[sub_resource type="AnimationNodeStateMachine" id="AnimationNodeStateMachine_x0yj5"]
+states/End/position = Vector2(500, 100)
+states/Start/position = Vector2(100, 100)
+states/idle/node = SubResource("AnimationNodeAnimation_k4c0p")
+states/idle/position = Vector2(250, 100)
+states/jump/node = SubResource("AnimationNodeAnimation_3lq7h")
+states/jump/position = Vector2(400, 100)
+transitions = ["Start", "idle", SubResource("AnimationNodeStateMachineTransition_vd1ku"), "idle", "jump", SubResource("AnimationNodeStateMachineTransition_o6m2r"), "jump", "End", SubResource("AnimationNodeStateMachineTransition_2bq8w")]
+
+[node name="AnimationTree" type="AnimationTree"]
+tree_root = SubResource("AnimationNodeStateMachine_x0yj5")
+"#;
+
+    #[test]
+    fn test_godot4_state_machine() {
+        let tscn = Loader::new().parse_tscn(SCENE_GODOT4);
+        let tree = AnimationTree::from_node(&tscn, 0).unwrap();
+        let machine = match tree.tree_root {
+            Some(AnimationNode::StateMachine(machine)) => machine,
+            root => panic!("Unexpected tree root {:?}", root),
+        };
+
+        assert_eq!(
+            machine.states.keys().collect::<Vec<_>>(),
+            vec!["idle", "jump"]
+        );
+        assert_eq!(machine.states["idle"].position, Vector2::new(250.0, 100.0));
+        assert_eq!(machine.start_node.as_deref(), Some("Start"));
+        assert_eq!(machine.end_node.as_deref(), Some("End"));
+        assert_eq!(machine.conditions(), vec!["jumping"]);
+        assert!(machine.transitions[0].auto_advance && !machine.transitions[0].disabled);
+        assert!(!machine.transitions[1].auto_advance && !machine.transitions[1].disabled);
+        assert!(machine.transitions[2].disabled);
+
+        let mut machine = machine;
+        machine.transitions[2].auto_advance = true;
+        assert_eq!(
+            machine.to_dot(),
+            "digraph StateMachine {\n    \"idle\" [label=\"idle\", shape=ellipse];\n    \
+             \"jump\" [label=\"jump\", shape=ellipse];\n    \
+             \"Start\" [label=\"Start\", shape=doublecircle];\n    \
+             \"End\" [label=\"End\", shape=doubleoctagon];\n    \
+             \"Start\" -> \"idle\" [style=bold];\n    \
+             \"idle\" -> \"jump\" [label=\"jumping\"];\n    \
+             \"jump\" -> \"End\" [style=\"bold,dashed\"];\n}\n"
+        );
+    }
+}
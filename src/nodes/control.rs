@@ -0,0 +1,902 @@
+use crate::types::{Theme, ThemeDataType, ThemeItem, VarType};
+use crate::{ClassDefaults, NodeEntry, PropertyMap, PropertyTrait, Tscn};
+
+use indexmap::IndexMap;
+use nalgebra::Vector2;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+/// Rect in viewport coordinates (position, size)
+pub type Rect = [Vector2<f32>; 2];
+
+/// `size_flags_horizontal` / `size_flags_vertical` bits
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SizeFlags {
+    pub fill: bool,
+    pub expand: bool,
+    pub shrink_center: bool,
+    pub shrink_end: bool,
+}
+
+impl Default for SizeFlags {
+    fn default() -> Self {
+        SizeFlags::from(1)
+    }
+}
+
+impl From<isize> for SizeFlags {
+    fn from(flags: isize) -> Self {
+        SizeFlags {
+            fill: flags & 1 != 0,
+            expand: flags & 2 != 0,
+            shrink_center: flags & 4 != 0,
+            shrink_end: flags & 8 != 0,
+        }
+    }
+}
+
+/// Direction a control grows in when its anchored rect is smaller than its minimum size
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GrowDirection {
+    Begin,
+    #[default]
+    End,
+    Both,
+}
+
+impl TryFrom<isize> for GrowDirection {
+    type Error = ();
+
+    fn try_from(value: isize) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(GrowDirection::Begin),
+            1 => Ok(GrowDirection::End),
+            2 => Ok(GrowDirection::Both),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Layout properties of Control node, axis pairs are (horizontal, vertical)
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Control {
+    /// Left, top, right and bottom anchors
+    pub anchors: [f32; 4],
+    /// Left, top, right and bottom offsets (`margin_*` in Godot 3)
+    pub offsets: [f32; 4],
+    /// `rect_min_size` (`custom_minimum_size` in Godot 4)
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helper::vector2"))]
+    pub min_size: Vector2<f32>,
+    pub size_flags: [SizeFlags; 2],
+    pub stretch_ratio: f32,
+    pub grow: [GrowDirection; 2],
+    pub visible: bool,
+}
+
+/// Children placement of container nodes
+#[derive(Debug, Clone, PartialEq)]
+enum Container {
+    /// Children keep their anchored rects
+    None,
+    Box {
+        vertical: bool,
+        separation: f32,
+        /// Begin, center or end
+        alignment: isize,
+    },
+    /// Left, top, right and bottom margins
+    Margin([f32; 4]),
+    Center,
+    Grid {
+        columns: usize,
+        separation: Vector2<f32>,
+    },
+    /// Every child fills the container (PanelContainer, ScrollContainer, ...)
+    Fill,
+}
+
+/// Final rects of scene's Control nodes for a viewport size, computed the way Godot sorts
+/// anchors and containers.
+///
+/// Minimum sizes only come from `rect_min_size` and children, as text and texture sizes
+/// would need the fonts and images. Children of instanced scenes are not expanded.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ControlLayout {
+    /// Rects by node id, in scene order
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helper::rect2_map"))]
+    pub rects: IndexMap<usize, Rect>,
+}
+
+impl Control {
+    pub fn from_node(node: &NodeEntry) -> Result<Self, String> {
+        if !Self::is_control(&node.rtype, &node.properties) {
+            return Err(format!("Expected Control node, got {}", node.rtype));
+        }
+
+        Ok(Self::from_properties(&node.properties))
+    }
+
+    /// Whether the node of `rtype` is a Control, by the built-in class table. Unknown types (i.e.
+    /// scripted or GDExtension classes) are recognized by their layout properties
+    pub fn is_control(rtype: &str, properties: &PropertyMap) -> bool {
+        let classes = ClassDefaults::builtin_ref();
+        if classes.contains(rtype) {
+            return classes.inherits(rtype, "Control");
+        }
+
+        properties.keys().any(|key| {
+            // Not just `offset_`, Camera2D has `offset_h` and `offset_v`
+            key.starts_with("anchor_")
+                || key.starts_with("margin_")
+                || key.starts_with("size_flags_")
+                || ["offset_left", "offset_top", "offset_right", "offset_bottom"]
+                    .contains(&key.as_str())
+        })
+    }
+
+    /// Reads Godot 3 (`margin_*`, `rect_min_size`) or Godot 4 (`offset_*`,
+    /// `custom_minimum_size`) properties
+    pub fn from_properties(properties: &PropertyMap) -> Self {
+        let side = |prefix: &str, fallback: &str, side: &str| {
+            properties
+                .get_as(&format!("{}_{}", prefix, side))
+                .or_else(|| properties.get_as(&format!("{}_{}", fallback, side)))
+                .unwrap_or(0.0)
+        };
+        let sides = |prefix: &str, fallback: &str| {
+            ["left", "top", "right", "bottom"].map(|name| side(prefix, fallback, name))
+        };
+
+        Control {
+            anchors: sides("anchor", "anchor"),
+            offsets: sides("offset", "margin"),
+            min_size: properties
+                .get_as("custom_minimum_size")
+                .or_else(|| properties.get_as("rect_min_size"))
+                .unwrap_or_else(Vector2::zeros),
+            size_flags: [
+                properties.get_as::<isize>("size_flags_horizontal"),
+                properties.get_as::<isize>("size_flags_vertical"),
+            ]
+            .map(|flags| flags.map(SizeFlags::from).unwrap_or_default()),
+            stretch_ratio: properties.get_as("size_flags_stretch_ratio").unwrap_or(1.0),
+            grow: [
+                properties.get_as::<isize>("grow_horizontal"),
+                properties.get_as::<isize>("grow_vertical"),
+            ]
+            .map(|grow| {
+                grow.and_then(|grow| GrowDirection::try_from(grow).ok())
+                    .unwrap_or_default()
+            }),
+            visible: properties.get_as("visible").unwrap_or(true),
+        }
+    }
+
+    /// Effective theme item of the node: its own `theme_override_*` (`custom_*` in Godot 3)
+    /// properties, then `theme` of the node and its Control ancestors.
+    ///
+    /// Items are looked up for `theme_type_variation` (and its base types), then the node's
+    /// class and its ancestors up to Control (i.e. HBoxContainer, BoxContainer, Container,
+    /// Control) as known to the built-in [`ClassDefaults`]. The engine's default theme is not
+    /// available
+    pub fn theme_item(
+        tscn: &Tscn,
+        node_id: usize,
+        data_type: ThemeDataType,
+        name: &str,
+    ) -> Option<ThemeItem> {
+        let group = data_type.group();
+        for prefix in ["theme_override", "custom"] {
+            let key = format!("{}_{}/{}", prefix, group, name);
+            let item = tscn
+                .nodes
+                .get(&node_id)?
+                .properties
+                .get_from(&key)
+                .and_then(|value| ThemeItem::from_value(tscn, data_type, value));
+            if item.is_some() {
+                return item;
+            }
+        }
+
+        let node_type = tscn.node_type(node_id);
+        let variation = tscn
+            .node_property(node_id, "theme_type_variation")
+            .and_then(VarType::string_name)
+            .map(str::to_string);
+
+        let mut class_types = ClassDefaults::builtin_ref().class_chain(node_type);
+        if let Some(control) = class_types.iter().position(|class| *class == "Control") {
+            class_types.truncate(control + 1);
+        }
+
+        let root_id = *tscn.nodes.keys().next()?;
+        let mut id = node_id;
+        loop {
+            let theme = tscn
+                .node_property(id, "theme")
+                .and_then(|theme| Theme::from_reference(tscn, theme).ok());
+
+            if let Some(theme) = theme {
+                let mut types = variation
+                    .as_ref()
+                    .map(|variation| theme.type_chain(variation))
+                    .unwrap_or_default();
+                types.extend(class_types.iter().map(|class| class.to_string()));
+
+                for theme_type in types {
+                    if let Some(item) = theme.get_item(data_type, name, &theme_type) {
+                        return Some(item.clone());
+                    }
+                }
+
+                match data_type {
+                    ThemeDataType::Font if theme.default_font.is_some() => {
+                        return theme.default_font.map(ThemeItem::Font);
+                    }
+                    ThemeDataType::FontSize if theme.default_font_size.is_some() => {
+                        return theme.default_font_size.map(ThemeItem::FontSize);
+                    }
+                    _ => {}
+                }
+            }
+
+            if id == root_id {
+                return None;
+            }
+
+            // Themes only propagate through Controls
+            id = tscn.nodes[&id].parent_id;
+            let parent = &tscn.nodes[&id];
+            if !Self::is_control(tscn.node_type(id), &parent.properties) {
+                return None;
+            }
+        }
+    }
+}
+
+impl Container {
+    fn from_properties(rtype: &str, properties: &PropertyMap) -> Self {
+        // Theme overrides, falling back to the default theme values
+        let constant = |name: &str, default: f32| {
+            ["theme_override_constants", "custom_constants"]
+                .iter()
+                .find_map(|group| properties.get_as::<isize>(&format!("{}/{}", group, name)))
+                .map_or(default, |constant| constant as f32)
+        };
+
+        match rtype {
+            "VBoxContainer" | "HBoxContainer" | "BoxContainer" => Container::Box {
+                vertical: rtype == "VBoxContainer"
+                    || properties.get_as("vertical").unwrap_or(false),
+                separation: constant("separation", 4.0),
+                alignment: properties.get_as("alignment").unwrap_or(0),
+            },
+            "MarginContainer" => Container::Margin(
+                ["left", "top", "right", "bottom"]
+                    .map(|side| constant(&format!("margin_{}", side), 0.0)),
+            ),
+            "CenterContainer" => Container::Center,
+            "GridContainer" => Container::Grid {
+                columns: properties.get_as::<isize>("columns").unwrap_or(1).max(1) as usize,
+                separation: Vector2::new(
+                    constant("h_separation", constant("hseparation", 4.0)),
+                    constant("v_separation", constant("vseparation", 4.0)),
+                ),
+            },
+            "PanelContainer" | "ScrollContainer" | "TabContainer" => Container::Fill,
+            _ => Container::None,
+        }
+    }
+}
+
+impl ControlLayout {
+    /// Lays out all Control nodes of `tscn`, top level controls are anchored to the viewport
+    pub fn compute(tscn: &Tscn, viewport: Vector2<f32>) -> Self {
+        let mut builder = LayoutBuilder {
+            tscn,
+            controls: HashMap::new(),
+            min_sizes: HashMap::new(),
+            layout: ControlLayout::default(),
+        };
+
+        for (&id, node) in &tscn.nodes {
+            let properties = match tscn
+                .ext_resources
+                .get(&node.instance)
+                .and_then(|scene| scene.nodes.values().next())
+            {
+                Some(root) => {
+                    let mut properties = root.properties.clone();
+                    properties.extend(node.properties.clone());
+                    properties
+                }
+                None => node.properties.clone(),
+            };
+
+            let rtype = tscn.node_type(id);
+            if Control::is_control(rtype, &properties) {
+                builder.controls.insert(
+                    id,
+                    (
+                        Control::from_properties(&properties),
+                        Container::from_properties(rtype, &properties),
+                    ),
+                );
+            }
+        }
+
+        let root_id = tscn.nodes.keys().next().copied();
+        for &id in tscn.nodes.keys() {
+            if !builder.controls.contains_key(&id) {
+                continue;
+            }
+
+            if !builder.layout.rects.contains_key(&id) {
+                let parent_id = tscn.nodes[&id].parent_id;
+                let parent_rect = match builder.layout.rects.get(&parent_id) {
+                    Some(rect) if Some(id) != root_id => *rect,
+                    _ => [Vector2::zeros(), viewport],
+                };
+                let rect = builder.anchored_rect(id, parent_rect);
+                builder.layout.rects.insert(id, rect);
+            }
+
+            builder.sort_children(id);
+        }
+
+        builder
+            .layout
+            .rects
+            .sort_by(|a, _, b, _| tscn.nodes.get_index_of(a).cmp(&tscn.nodes.get_index_of(b)));
+        builder.layout
+    }
+
+    pub fn rect(&self, node_id: usize) -> Option<Rect> {
+        self.rects.get(&node_id).copied()
+    }
+
+    /// One `path: x y width height` line per control, for snapshot tests
+    pub fn to_snapshot(&self, tscn: &Tscn) -> String {
+        self.rects
+            .iter()
+            .filter_map(|(&id, [position, size])| {
+                Some(format!(
+                    "{}: {} {} {} {}\n",
+                    tscn.relative_path(id)?,
+                    position.x,
+                    position.y,
+                    size.x,
+                    size.y
+                ))
+            })
+            .collect()
+    }
+}
+
+struct LayoutBuilder<'a> {
+    tscn: &'a Tscn,
+    controls: HashMap<usize, (Control, Container)>,
+    min_sizes: HashMap<usize, Vector2<f32>>,
+    layout: ControlLayout,
+}
+
+impl<'a> LayoutBuilder<'a> {
+    /// Visible Control children which containers sort
+    fn sorted_children(&self, id: usize) -> Vec<usize> {
+        self.tscn.nodes[&id]
+            .childrens
+            .iter()
+            .copied()
+            .filter(|child| {
+                self.controls
+                    .get(child)
+                    .is_some_and(|(control, _)| control.visible)
+            })
+            .collect()
+    }
+
+    /// Custom minimum size combined with the size the container needs for its children
+    fn min_size(&mut self, id: usize) -> Vector2<f32> {
+        if let Some(size) = self.min_sizes.get(&id) {
+            return *size;
+        }
+
+        let (control, container) = self.controls[&id].clone();
+        let children = self.sorted_children(id);
+        let sizes: Vec<Vector2<f32>> = children.iter().map(|&c| self.min_size(c)).collect();
+        let largest = sizes
+            .iter()
+            .fold(Vector2::zeros(), |max: Vector2<f32>, size| max.sup(size));
+
+        let own = match container {
+            Container::None => Vector2::zeros(),
+            Container::Box {
+                vertical,
+                separation,
+                ..
+            } => {
+                let (along, across) = if vertical { (1, 0) } else { (0, 1) };
+                let mut size = Vector2::zeros();
+                size[along] = sizes.iter().map(|size| size[along]).sum::<f32>()
+                    + separation * sizes.len().saturating_sub(1) as f32;
+                size[across] = largest[across];
+                size
+            }
+            Container::Margin([left, top, right, bottom]) => {
+                largest + Vector2::new(left + right, top + bottom)
+            }
+            Container::Center | Container::Fill => largest,
+            Container::Grid {
+                columns,
+                separation,
+            } => {
+                let (widths, heights) = grid_min_sizes(&sizes, columns);
+                Vector2::new(
+                    widths.iter().sum::<f32>()
+                        + separation.x * widths.len().saturating_sub(1) as f32,
+                    heights.iter().sum::<f32>()
+                        + separation.y * heights.len().saturating_sub(1) as f32,
+                )
+            }
+        };
+
+        let size = own.sup(&control.min_size);
+        self.min_sizes.insert(id, size);
+        size
+    }
+
+    /// Rect from anchors and offsets within the parent rect, grown to the minimum size
+    fn anchored_rect(&mut self, id: usize, parent: Rect) -> Rect {
+        let control = self.controls[&id].0.clone();
+        let [parent_position, parent_size] = parent;
+        let edge = |i: usize| control.offsets[i] + control.anchors[i] * parent_size[i % 2];
+
+        let mut position = Vector2::new(edge(0), edge(1));
+        let mut size = Vector2::new(edge(2), edge(3)) - position;
+        let min_size = self.min_size(id);
+
+        for axis in 0..2 {
+            if min_size[axis] > size[axis] {
+                position[axis] += match control.grow[axis] {
+                    GrowDirection::Begin => size[axis] - min_size[axis],
+                    GrowDirection::End => 0.0,
+                    GrowDirection::Both => 0.5 * (size[axis] - min_size[axis]),
+                };
+                size[axis] = min_size[axis];
+            }
+        }
+
+        [parent_position + position, size]
+    }
+
+    /// Places the container's children within its rect
+    fn sort_children(&mut self, id: usize) {
+        let container = self.controls[&id].1.clone();
+        let children = self.sorted_children(id);
+        let [_, size] = self.layout.rects[&id];
+
+        match container {
+            Container::None => {}
+            Container::Box {
+                vertical,
+                separation,
+                alignment,
+            } => self.sort_box(id, &children, vertical, separation, alignment),
+            Container::Margin([left, top, right, bottom]) => {
+                for child in children {
+                    self.fit_child(
+                        id,
+                        child,
+                        [
+                            Vector2::new(left, top),
+                            size - Vector2::new(left + right, top + bottom),
+                        ],
+                    );
+                }
+            }
+            Container::Center => {
+                for child in children {
+                    let min_size = self.min_size(child);
+                    let offset = ((size - min_size) / 2.0).map(f32::floor);
+                    self.fit_child(id, child, [offset, min_size]);
+                }
+            }
+            Container::Grid {
+                columns,
+                separation,
+            } => self.sort_grid(id, &children, columns, separation),
+            Container::Fill => {
+                for child in children {
+                    self.fit_child(id, child, [Vector2::zeros(), size]);
+                }
+            }
+        }
+    }
+
+    /// Mirrors BoxContainer's sorting: expanding children share the space left after the
+    /// minimum sizes by stretch ratio, unless their share would be below their minimum
+    fn sort_box(
+        &mut self,
+        id: usize,
+        children: &[usize],
+        vertical: bool,
+        separation: f32,
+        alignment: isize,
+    ) {
+        let [_, size] = self.layout.rects[&id];
+        let axis = if vertical { 1 } else { 0 };
+
+        let mut stretch_min = 0.0;
+        let mut stretch_avail = 0.0;
+        let mut stretch_ratio_total = 0.0;
+        // (min size, will stretch, final size)
+        let mut cache: Vec<(f32, bool, f32)> = Vec::new();
+        for &child in children {
+            let min_size = self.min_size(child)[axis].floor();
+            let control = &self.controls[&child].0;
+            let will_stretch = control.size_flags[axis].expand;
+
+            stretch_min += min_size;
+            if will_stretch {
+                stretch_avail += min_size;
+                stretch_ratio_total += control.stretch_ratio;
+            }
+            cache.push((min_size, will_stretch, min_size));
+        }
+
+        let stretch_max = size[axis] - separation * children.len().saturating_sub(1) as f32;
+        let stretch_diff = (stretch_max - stretch_min).max(0.0);
+        stretch_avail += stretch_diff;
+
+        let mut has_stretched = false;
+        while stretch_ratio_total > 0.0 {
+            has_stretched = true;
+            let mut refit_successful = true;
+
+            for (i, &child) in children.iter().enumerate() {
+                let (min_size, will_stretch, _) = cache[i];
+                if !will_stretch {
+                    continue;
+                }
+
+                let ratio = self.controls[&child].0.stretch_ratio;
+                let final_size = (stretch_avail * ratio / stretch_ratio_total).floor();
+                if final_size < min_size {
+                    cache[i] = (min_size, false, min_size);
+                    stretch_ratio_total -= ratio;
+                    stretch_avail -= min_size;
+                    refit_successful = false;
+                    break;
+                }
+
+                cache[i].2 = final_size;
+            }
+
+            if refit_successful {
+                break;
+            }
+        }
+
+        let mut offset = match alignment {
+            _ if has_stretched => 0.0,
+            1 => (stretch_diff / 2.0).floor(),
+            2 => stretch_diff,
+            _ => 0.0,
+        };
+
+        for (i, &child) in children.iter().enumerate() {
+            if i > 0 {
+                offset += separation;
+            }
+
+            let (_, will_stretch, final_size) = cache[i];
+            let from = offset;
+            // The last expanding child always fills up to the end
+            let to = if will_stretch && i == children.len() - 1 {
+                size[axis]
+            } else {
+                offset + final_size
+            };
+
+            let mut position = Vector2::zeros();
+            let mut child_size = size;
+            position[axis] = from;
+            child_size[axis] = to - from;
+            self.fit_child(id, child, [position, child_size]);
+
+            offset = to;
+        }
+    }
+
+    /// Mirrors GridContainer's sorting: columns and rows with expanding children share the
+    /// remaining space evenly
+    fn sort_grid(
+        &mut self,
+        id: usize,
+        children: &[usize],
+        columns: usize,
+        separation: Vector2<f32>,
+    ) {
+        let [_, size] = self.layout.rects[&id];
+        let sizes: Vec<Vector2<f32>> = children.iter().map(|&c| self.min_size(c)).collect();
+        let min_sizes = grid_min_sizes(&sizes, columns);
+
+        // Expanded columns and rows
+        let mut expanded = [Vec::new(), Vec::new()];
+        for (i, child) in children.iter().enumerate() {
+            let flags = self.controls[child].0.size_flags;
+            let cell = [i % columns, i / columns];
+            for axis in 0..2 {
+                if flags[axis].expand && !expanded[axis].contains(&cell[axis]) {
+                    expanded[axis].push(cell[axis]);
+                }
+            }
+        }
+
+        let mut expand = [0.0; 2];
+        for axis in 0..2 {
+            let lines = if axis == 0 {
+                &min_sizes.0
+            } else {
+                &min_sizes.1
+            };
+            let expanded = &mut expanded[axis];
+
+            let mut remaining = size[axis]
+                - separation[axis] * lines.len().saturating_sub(1) as f32
+                - (0..lines.len())
+                    .filter(|line| !expanded.contains(line))
+                    .map(|line| lines[line])
+                    .sum::<f32>();
+
+            // Lines whose minimum exceeds the even share stop expanding, largest first
+            while let Some(&largest) = expanded
+                .iter()
+                .max_by(|&&a, &&b| lines[a].total_cmp(&lines[b]))
+            {
+                if remaining / expanded.len() as f32 >= lines[largest] {
+                    break;
+                }
+
+                expanded.retain(|&line| line != largest);
+                remaining -= lines[largest];
+            }
+
+            if !expanded.is_empty() {
+                expand[axis] = (remaining / expanded.len() as f32).floor();
+            }
+        }
+
+        let line_size = |axis: usize, line: usize| {
+            if expanded[axis].contains(&line) {
+                expand[axis]
+            } else if axis == 0 {
+                min_sizes.0[line]
+            } else {
+                min_sizes.1[line]
+            }
+        };
+
+        let mut offset: Vector2<f32> = Vector2::zeros();
+        for (i, &child) in children.iter().enumerate() {
+            let (column, row) = (i % columns, i / columns);
+            if column == 0 {
+                offset.x = 0.0;
+                if row > 0 {
+                    offset.y += line_size(1, row - 1) + separation.y;
+                }
+            }
+
+            let cell = Vector2::new(line_size(0, column), line_size(1, row));
+            self.fit_child(id, child, [offset, cell]);
+            offset.x += cell.x + separation.x;
+        }
+    }
+
+    /// Places the child into the rect (relative to the container) by its size flags
+    fn fit_child(&mut self, id: usize, child: usize, rect: Rect) {
+        let [container_position, _] = self.layout.rects[&id];
+        let flags = self.controls[&child].0.size_flags;
+        let min_size = self.min_size(child);
+        let [mut position, mut size] = rect;
+
+        for axis in 0..2 {
+            if flags[axis].fill {
+                continue;
+            }
+
+            size[axis] = min_size[axis];
+            if flags[axis].shrink_end {
+                position[axis] += rect[1][axis] - min_size[axis];
+            } else if flags[axis].shrink_center {
+                position[axis] += ((rect[1][axis] - min_size[axis]) / 2.0).floor();
+            }
+        }
+
+        self.layout
+            .rects
+            .insert(child, [container_position + position, size]);
+    }
+}
+
+/// Column widths and row heights of the grid cells' minimum sizes
+fn grid_min_sizes(sizes: &[Vector2<f32>], columns: usize) -> (Vec<f32>, Vec<f32>) {
+    let mut widths = vec![0.0f32; columns.min(sizes.len())];
+    let mut heights = vec![0.0f32; sizes.len().div_ceil(columns)];
+
+    for (i, size) in sizes.iter().enumerate() {
+        widths[i % columns] = widths[i % columns].max(size.x);
+        heights[i / columns] = heights[i / columns].max(size.y);
+    }
+
+    (widths, heights)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::nodes::control::*;
+    use crate::Loader;
+
+    const SCENE: &str = r#"[gd_scene format=2]
+
+[node name="Menu" type="Control"]
+anchor_right = 1.0
+anchor_bottom = 1.0
+
+[node name="Panel" type="MarginContainer" parent="."]
+anchor_left = 0.5
+anchor_top = 0.5
+anchor_right = 0.5
+anchor_bottom = 0.5
+margin_left = -100.0
+margin_top = -50.0
+margin_right = 100.0
+margin_bottom = 50.0
+grow_horizontal = 2
+grow_vertical = 2
+custom_constants/margin_left = 10
+custom_constants/margin_top = 10
+custom_constants/margin_right = 10
+custom_constants/margin_bottom = 10
+
+[node name="Items" type="VBoxContainer" parent="Panel"]
+margin_right = 180.0
+margin_bottom = 80.0
+
+[node name="Title" type="Label" parent="Panel/Items"]
+rect_min_size = Vector2( 0, 20 )
+size_flags_horizontal = 4
+
+[node name="Play" type="Button" parent="Panel/Items"]
+rect_min_size = Vector2( 60, 10 )
+size_flags_vertical = 3
+
+[node name="Quit" type="Button" parent="Panel/Items"]
+rect_min_size = Vector2( 60, 30 )
+size_flags_vertical = 3
+size_flags_stretch_ratio = 2.0
+
+[node name="Hint" type="Label" parent="."]
+anchor_top = 1.0
+anchor_bottom = 1.0
+margin_top = -20.0
+rect_min_size = Vector2( 300, 0 )
+grow_vertical = 0
+"#;
+
+    #[test]
+    fn test_control_layout() {
+        let tscn = Loader::new().parse_tscn(SCENE);
+        let layout = ControlLayout::compute(&tscn, Vector2::new(640.0, 480.0));
+
+        let rect = |x: f32, y: f32, w: f32, h: f32| Some([Vector2::new(x, y), Vector2::new(w, h)]);
+        assert_eq!(layout.rect(0), rect(0.0, 0.0, 640.0, 480.0));
+        assert_eq!(layout.rect(1), rect(220.0, 190.0, 200.0, 100.0));
+        assert_eq!(layout.rect(2), rect(230.0, 200.0, 180.0, 80.0));
+        // Not filling, so shrunk to its (zero) width and centered
+        assert_eq!(layout.rect(3), rect(320.0, 200.0, 0.0, 20.0));
+        // 52 pixels shared 1:2 between both buttons, Play is 17 and Quit takes the rest
+        assert_eq!(layout.rect(4), rect(230.0, 224.0, 180.0, 17.0));
+        assert_eq!(layout.rect(5), rect(230.0, 245.0, 180.0, 35.0));
+        assert_eq!(layout.rect(6), rect(0.0, 460.0, 300.0, 20.0));
+
+        assert!(layout
+            .to_snapshot(&tscn)
+            .contains("Panel/Items/Quit: 230 245 180 35\n"));
+    }
+
+    const SCENE_GODOT4: &str = r#"[gd_scene format=3 uid="uid://bq8m3x0w5k2tn"]
+
+[node name="Menu" type="Control"]
+layout_mode = 3
+anchors_preset = 15
+anchor_right = 1.0
+anchor_bottom = 1.0
+
+[node name="Panel" type="MarginContainer" parent="."]
+layout_mode = 1
+anchors_preset = 8
+anchor_left = 0.5
+anchor_top = 0.5
+anchor_right = 0.5
+anchor_bottom = 0.5
+offset_left = -100.0
+offset_top = -50.0
+offset_right = 100.0
+offset_bottom = 50.0
+grow_horizontal = 2
+grow_vertical = 2
+theme_override_constants/margin_left = 10
+theme_override_constants/margin_top = 10
+theme_override_constants/margin_right = 10
+theme_override_constants/margin_bottom = 10
+
+[node name="Items" type="VBoxContainer" parent="Panel"]
+layout_mode = 2
+
+[node name="Title" type="Label" parent="Panel/Items"]
+custom_minimum_size = Vector2(0, 20)
+layout_mode = 2
+size_flags_horizontal = 4
+
+[node name="Play" type="Button" parent="Panel/Items"]
+custom_minimum_size = Vector2(60, 10)
+layout_mode = 2
+size_flags_vertical = 3
+
+[node name="Quit" type="Button" parent="Panel/Items"]
+custom_minimum_size = Vector2(60, 30)
+layout_mode = 2
+size_flags_vertical = 3
+size_flags_stretch_ratio = 2.0
+
+[node name="Slider" type="HSlider" parent="."]
+"#;
+
+    #[test]
+    fn test_control_layout_godot4() {
+        let tscn = Loader::new().parse_tscn(SCENE_GODOT4);
+        let layout = ControlLayout::compute(&tscn, Vector2::new(640.0, 480.0));
+
+        let rect = |x: f32, y: f32, w: f32, h: f32| Some([Vector2::new(x, y), Vector2::new(w, h)]);
+        assert_eq!(layout.rect(1), rect(220.0, 190.0, 200.0, 100.0));
+        assert_eq!(layout.rect(2), rect(230.0, 200.0, 180.0, 80.0));
+        assert_eq!(layout.rect(3), rect(320.0, 200.0, 0.0, 20.0));
+        assert_eq!(layout.rect(4), rect(230.0, 224.0, 180.0, 17.0));
+        assert_eq!(layout.rect(5), rect(230.0, 245.0, 180.0, 35.0));
+        // Range without layout properties is still a Control
+        assert_eq!(layout.rect(6), rect(0.0, 0.0, 0.0, 0.0));
+
+        let empty = PropertyMap::new();
+        assert!(Control::is_control("WindowDialog", &empty));
+        assert!(Control::is_control("TextureProgressBar", &empty));
+        assert!(!Control::is_control("Camera2D", &empty));
+        assert!(Control::is_control("Inventory", &tscn.nodes[&1].properties));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_control_layout_serde() {
+        let tscn = Loader::new().parse_tscn(SCENE);
+        let layout = ControlLayout::compute(&tscn, Vector2::new(640.0, 480.0));
+        let encoded = serde_json::to_value(&layout).unwrap();
+        assert_eq!(
+            encoded["rects"]["1"],
+            serde_json::json!({ "position": [220.0, 190.0], "size": [200.0, 100.0] })
+        );
+        assert_eq!(
+            serde_json::from_value::<ControlLayout>(encoded).unwrap(),
+            layout
+        );
+
+        let control = Control::from_node(&tscn.nodes[&3]).unwrap();
+        let encoded = serde_json::to_value(&control).unwrap();
+        assert_eq!(encoded["min_size"], serde_json::json!([0.0, 20.0]));
+        assert_eq!(serde_json::from_value::<Control>(encoded).unwrap(), control);
+    }
+}
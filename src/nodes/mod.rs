@@ -0,0 +1,11 @@
+pub mod animated_sprite;
+pub mod animation_player;
+pub mod animation_tree;
+pub mod control;
+pub mod tilemap;
+
+pub use animated_sprite::AnimatedSprite;
+pub use animation_player::{AnimationPlayer, BlendTime};
+pub use animation_tree::AnimationTree;
+pub use control::{Control, ControlLayout, GrowDirection, SizeFlags};
+pub use tilemap::{Cell, TileDataFormat, TileMap};
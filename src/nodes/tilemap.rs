@@ -0,0 +1,461 @@
+use crate::types::{TileCoord, VarType};
+use crate::{NodeEntry, PropertyMap, PropertyTrait};
+
+use std::collections::HashMap;
+
+const FLIP_H: i64 = 1 << 29;
+const FLIP_V: i64 = 1 << 30;
+const TRANSPOSE: i64 = 1 << 31;
+const TILE_ID_MASK: i64 = (1 << 29) - 1;
+
+// Godot 4 keeps the flags in the alternative tile id
+const ALTERNATIVE_FLIP_H: usize = 1 << 12;
+const ALTERNATIVE_FLIP_V: usize = 1 << 13;
+const ALTERNATIVE_TRANSPOSE: usize = 1 << 14;
+const ALTERNATIVE_MASK: usize = ALTERNATIVE_FLIP_H - 1;
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Cell {
+    /// Tile id (Godot 3) or atlas source id (Godot 4)
+    pub tile_id: usize,
+    pub flip_h: bool,
+    pub flip_v: bool,
+    pub transpose: bool,
+    /// Autotile (Godot 3) or atlas (Godot 4) coordinate
+    pub autotile_coord: TileCoord,
+    /// Alternative tile id, Godot 4 only
+    pub alternative: usize,
+}
+
+/// Layout of the stored cells
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TileDataFormat {
+    /// Godot 3 `tile_data` without autotile coordinates (`format = 0`)
+    Pairs,
+    /// Godot 3 `tile_data` (`format = 1`)
+    Triplets,
+    /// Godot 4 TileMap `layer_N/tile_data`
+    Layer,
+    /// Godot 4 TileMapLayer `tile_map_data`
+    LayerBytes,
+}
+
+/// Decoded cells of TileMap (or TileMapLayer) node
+#[derive(Debug, Clone, PartialEq)]
+pub struct TileMap {
+    /// Property holding the cells (i.e. `tile_data`, `layer_0/tile_data`)
+    pub property: String,
+    pub format: TileDataFormat,
+    cells: HashMap<TileCoord, Cell>,
+}
+
+impl TileMap {
+    /// Empty cell set, stored into `property` using `format`
+    pub fn new(property: &str, format: TileDataFormat) -> Self {
+        TileMap {
+            property: property.to_string(),
+            format,
+            cells: HashMap::new(),
+        }
+    }
+
+    /// Decodes cells of the node, first layer for Godot 4 TileMap
+    pub fn from_node(node: &NodeEntry) -> Result<Self, String> {
+        let properties = &node.properties;
+
+        if properties.contains_key("tile_map_data") {
+            Self::decode(properties, "tile_map_data", TileDataFormat::LayerBytes)
+        } else if properties.contains_key("layer_0") {
+            Self::from_layer(node, 0)
+        } else if properties.get_as::<isize>("format").unwrap_or(0) >= 1 {
+            Self::decode(properties, "tile_data", TileDataFormat::Triplets)
+        } else {
+            Self::decode(properties, "tile_data", TileDataFormat::Pairs)
+        }
+    }
+
+    /// Decodes cells of Godot 4 TileMap layer
+    pub fn from_layer(node: &NodeEntry, layer: usize) -> Result<Self, String> {
+        let property = format!("layer_{}/tile_data", layer);
+
+        Self::decode(&node.properties, &property, TileDataFormat::Layer)
+    }
+
+    pub fn get_cell(&self, x: i32, y: i32) -> Option<&Cell> {
+        self.cells.get(&(x, y))
+    }
+
+    pub fn set_cell(&mut self, x: i32, y: i32, cell: Cell) {
+        self.cells.insert((x, y), cell);
+    }
+
+    pub fn remove_cell(&mut self, x: i32, y: i32) -> Option<Cell> {
+        self.cells.remove(&(x, y))
+    }
+
+    /// Used cell coordinates, sorted the way Godot stores them (row by row)
+    pub fn used_cells(&self) -> Vec<TileCoord> {
+        let mut used: Vec<TileCoord> = self.cells.keys().copied().collect();
+        used.sort_by_key(|(x, y)| (*y, *x));
+
+        used
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    /// Encodes cells back into the property value
+    pub fn encode(&self) -> VarType {
+        let cells = self
+            .used_cells()
+            .into_iter()
+            .map(|pos| (pos, self.cells[&pos]));
+
+        match self.format {
+            TileDataFormat::Pairs | TileDataFormat::Triplets => {
+                let mut data: Vec<isize> = Vec::new();
+                for (pos, cell) in cells {
+                    let mut tile = cell.tile_id as i64 & TILE_ID_MASK;
+                    for (flag, set) in &[
+                        (FLIP_H, cell.flip_h),
+                        (FLIP_V, cell.flip_v),
+                        (TRANSPOSE, cell.transpose),
+                    ] {
+                        if *set {
+                            tile |= flag;
+                        }
+                    }
+
+                    data.push(Self::pack(pos) as isize);
+                    data.push(tile as u32 as i32 as isize);
+                    if self.format == TileDataFormat::Triplets {
+                        data.push(Self::pack(cell.autotile_coord) as isize);
+                    }
+                }
+
+                VarType::IntArr(data)
+            }
+            TileDataFormat::Layer => {
+                let mut data: Vec<String> = Vec::new();
+                for (pos, cell) in cells {
+                    let alternative = Self::alternative(&cell) as i64;
+
+                    data.push(Self::pack(pos).to_string());
+                    data.push(
+                        ((cell.tile_id as i64 & 0xFFFF) | (cell.autotile_coord.0 as i64) << 16)
+                            .to_string(),
+                    );
+                    data.push(
+                        ((cell.autotile_coord.1 as i64 & 0xFFFF) | alternative << 16).to_string(),
+                    );
+                }
+
+                VarType::None(format!("PackedInt32Array({})", data.join(", ")))
+            }
+            TileDataFormat::LayerBytes => {
+                let mut data: Vec<u8> = vec![0, 0];
+                for (pos, cell) in cells {
+                    for value in &[
+                        pos.0 as u16,
+                        pos.1 as u16,
+                        cell.tile_id as u16,
+                        cell.autotile_coord.0 as u16,
+                        cell.autotile_coord.1 as u16,
+                        Self::alternative(&cell) as u16,
+                    ] {
+                        data.extend_from_slice(&value.to_le_bytes());
+                    }
+                }
+
+                VarType::None(format!(
+                    "PackedByteArray(\"{}\")",
+                    Self::base64_encode(&data)
+                ))
+            }
+        }
+    }
+
+    /// Writes cells back into the node
+    pub fn apply(&self, node: &mut NodeEntry) {
+        node.properties
+            .insert_to(self.property.clone(), self.encode());
+    }
+
+    fn decode(
+        properties: &PropertyMap,
+        property: &str,
+        format: TileDataFormat,
+    ) -> Result<Self, String> {
+        let data: Vec<i64> = match (properties.get_from(property), format) {
+            (None, _) => Vec::new(),
+            (Some(VarType::IntArr(arr)), TileDataFormat::Pairs)
+            | (Some(VarType::IntArr(arr)), TileDataFormat::Triplets) => {
+                arr.iter().map(|n| *n as i64).collect()
+            }
+            (Some(VarType::None(raw)), TileDataFormat::Layer) => {
+                Self::parse_packed(raw, "PackedInt32Array")?
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|n| !n.is_empty())
+                    .map(|n| n.parse::<i64>().map_err(|e| e.to_string()))
+                    .collect::<Result<Vec<i64>, String>>()?
+            }
+            (Some(VarType::None(raw)), TileDataFormat::LayerBytes) => {
+                let encoded = Self::parse_packed(raw, "PackedByteArray")?.trim_matches('"');
+                let bytes = Self::base64_decode(encoded)?;
+
+                bytes
+                    .get(2..)
+                    .unwrap_or_default()
+                    .chunks(2)
+                    .map(|b| i64::from(u16::from_le_bytes([b[0], *b.get(1).unwrap_or(&0)])))
+                    .collect()
+            }
+            (Some(value), _) => return Err(format!("Unexpected tile data {:?}", value)),
+        };
+
+        let stride = match format {
+            TileDataFormat::Pairs => 2,
+            TileDataFormat::LayerBytes => 6,
+            _ => 3,
+        };
+        if !data.len().is_multiple_of(stride) {
+            return Err(format!(
+                "Tile data of {} values is not made of whole cells",
+                data.len()
+            ));
+        }
+
+        let mut cells: HashMap<TileCoord, Cell> = HashMap::new();
+        for chunk in data.chunks(stride) {
+            let (pos, cell) = match format {
+                TileDataFormat::Pairs | TileDataFormat::Triplets => (
+                    Self::unpack(chunk[0]),
+                    Cell {
+                        tile_id: (chunk[1] & TILE_ID_MASK) as usize,
+                        flip_h: chunk[1] & FLIP_H != 0,
+                        flip_v: chunk[1] & FLIP_V != 0,
+                        transpose: chunk[1] & TRANSPOSE != 0,
+                        autotile_coord: chunk.get(2).map(|c| Self::unpack(*c)).unwrap_or((0, 0)),
+                        alternative: 0,
+                    },
+                ),
+                TileDataFormat::Layer => (
+                    Self::unpack(chunk[0]),
+                    Self::godot4_cell(
+                        chunk[1] & 0xFFFF,
+                        (chunk[1] >> 16) & 0xFFFF,
+                        chunk[2] & 0xFFFF,
+                        (chunk[2] >> 16) & 0xFFFF,
+                    ),
+                ),
+                TileDataFormat::LayerBytes => (
+                    (chunk[0] as u16 as i16 as i32, chunk[1] as u16 as i16 as i32),
+                    Self::godot4_cell(chunk[2], chunk[3], chunk[4], chunk[5]),
+                ),
+            };
+
+            cells.insert(pos, cell);
+        }
+
+        Ok(TileMap {
+            property: property.to_string(),
+            format,
+            cells,
+        })
+    }
+
+    fn godot4_cell(source_id: i64, x: i64, y: i64, alternative: i64) -> Cell {
+        let alternative = alternative as usize;
+
+        Cell {
+            tile_id: source_id as usize,
+            flip_h: alternative & ALTERNATIVE_FLIP_H != 0,
+            flip_v: alternative & ALTERNATIVE_FLIP_V != 0,
+            transpose: alternative & ALTERNATIVE_TRANSPOSE != 0,
+            autotile_coord: (x as u16 as i16 as i32, y as u16 as i16 as i32),
+            alternative: alternative & ALTERNATIVE_MASK,
+        }
+    }
+
+    fn alternative(cell: &Cell) -> usize {
+        let mut alternative = cell.alternative & ALTERNATIVE_MASK;
+        for (flag, set) in &[
+            (ALTERNATIVE_FLIP_H, cell.flip_h),
+            (ALTERNATIVE_FLIP_V, cell.flip_v),
+            (ALTERNATIVE_TRANSPOSE, cell.transpose),
+        ] {
+            if *set {
+                alternative |= flag;
+            }
+        }
+
+        alternative
+    }
+
+    /// Packs coordinate as `(y << 16) | (x & 0xFFFF)` into a signed 32 bit integer
+    fn pack(pos: TileCoord) -> i32 {
+        ((pos.1 as u32) << 16 | (pos.0 as u32 & 0xFFFF)) as i32
+    }
+
+    fn unpack(packed: i64) -> TileCoord {
+        let packed = packed as u32;
+
+        (
+            (packed & 0xFFFF) as u16 as i16 as i32,
+            (packed >> 16) as u16 as i16 as i32,
+        )
+    }
+
+    fn parse_packed<'a>(raw: &'a str, constructor: &str) -> Result<&'a str, String> {
+        raw.trim()
+            .strip_prefix(constructor)
+            .and_then(|args| args.strip_prefix('('))
+            .and_then(|args| args.strip_suffix(')'))
+            .ok_or(format!("Expected {}, got `{}`", constructor, raw))
+    }
+
+    fn base64_encode(data: &[u8]) -> String {
+        let mut encoded = String::new();
+        for chunk in data.chunks(3) {
+            let bytes = [
+                chunk[0],
+                *chunk.get(1).unwrap_or(&0),
+                *chunk.get(2).unwrap_or(&0),
+            ];
+            let triple = u32::from(bytes[0]) << 16 | u32::from(bytes[1]) << 8 | u32::from(bytes[2]);
+
+            for i in 0..4 {
+                if i <= chunk.len() {
+                    encoded.push(BASE64[(triple >> (18 - i * 6) & 0x3F) as usize] as char);
+                } else {
+                    encoded.push('=');
+                }
+            }
+        }
+
+        encoded
+    }
+
+    fn base64_decode(encoded: &str) -> Result<Vec<u8>, String> {
+        let mut data = Vec::new();
+        let mut buffer: u32 = 0;
+        let mut bits = 0;
+
+        for ch in encoded.bytes().filter(|ch| *ch != b'=') {
+            let value = BASE64
+                .iter()
+                .position(|b| *b == ch)
+                .ok_or(format!("Invalid base64 character `{}`", ch as char))?;
+
+            buffer = buffer << 6 | value as u32;
+            bits += 6;
+            if bits >= 8 {
+                bits -= 8;
+                data.push((buffer >> bits) as u8);
+            }
+        }
+
+        Ok(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::nodes::tilemap::*;
+    use crate::Loader;
+
+    const SCENE: &str = r#"[gd_scene format=2]
+
+[node name="Level" type="Node2D"]
+
+[node name="TileMap" type="TileMap" parent="."]
+format = 1
+tile_data = PoolIntArray( -131072, 0, 1, 0, 2, 0, 131071, 1610612737, 65536 )
+"#;
+
+    const SCENE_GODOT4: &str = r#"[gd_scene format=3]
+
+[node name="Level" type="Node2D"]
+
+[node name="TileMap" type="TileMap" parent="."]
+format = 2
+layer_0/tile_data = PackedInt32Array(65535, 65536, 805306368, 0, 131073, 0)
+
+[node name="Layer" type="TileMapLayer" parent="."]
+tile_map_data = PackedByteArray("AAAAAP//AQABAAIAAGAAAAEAAAACAAMAAAA=")
+"#;
+
+    #[test]
+    fn test_tilemap() {
+        let mut tscn = Loader::new().parse_tscn(SCENE);
+        let node = tscn.nodes.get_mut(&1).unwrap();
+        let original = node.properties["tile_data"].clone();
+
+        let mut tilemap = TileMap::from_node(node).unwrap();
+        assert_eq!(tilemap.format, TileDataFormat::Triplets);
+        assert_eq!(tilemap.used_cells(), vec![(0, -2), (0, 0), (-1, 1)]);
+        assert_eq!(
+            tilemap.get_cell(-1, 1),
+            Some(&Cell {
+                tile_id: 1,
+                flip_h: true,
+                flip_v: true,
+                autotile_coord: (0, 1),
+                ..Cell::default()
+            })
+        );
+        assert_eq!(tilemap.encode(), original);
+
+        tilemap.remove_cell(0, -2);
+        tilemap.set_cell(
+            3,
+            0,
+            Cell {
+                tile_id: 4,
+                transpose: true,
+                ..Cell::default()
+            },
+        );
+        tilemap.apply(node);
+        assert_eq!(
+            node.properties["tile_data"],
+            VarType::IntArr(vec![0, 2, 0, 3, -2147483644, 0, 131071, 1610612737, 65536])
+        );
+    }
+
+    #[test]
+    fn test_tilemap_godot4() {
+        let tscn = Loader::new().parse_tscn(SCENE_GODOT4);
+
+        let layer = TileMap::from_node(&tscn.nodes[&1]).unwrap();
+        assert_eq!(layer.property, "layer_0/tile_data");
+        let cell = layer.get_cell(-1, 0).unwrap();
+        assert_eq!((cell.tile_id, cell.autotile_coord), (0, (1, 0)));
+        assert!(cell.flip_h && cell.flip_v && !cell.transpose);
+        assert_eq!(layer.get_cell(0, 0).unwrap().autotile_coord, (2, 0));
+        assert_eq!(
+            layer.encode(),
+            tscn.nodes[&1]
+                .properties
+                .get_from("layer_0/tile_data")
+                .cloned()
+                .unwrap()
+        );
+
+        let bytes = TileMap::from_node(&tscn.nodes[&2]).unwrap();
+        assert_eq!(bytes.format, TileDataFormat::LayerBytes);
+        let cell = bytes.get_cell(0, -1).unwrap();
+        assert_eq!((cell.tile_id, cell.autotile_coord), (1, (1, 2)));
+        assert!(cell.flip_v && cell.transpose);
+        assert_eq!(bytes.get_cell(0, 1).unwrap().autotile_coord, (2, 3));
+        assert_eq!(bytes.encode(), tscn.nodes[&2].properties["tile_map_data"]);
+    }
+}
@@ -0,0 +1,380 @@
+//! Stable serde encodings for nalgebra values
+//!
+//! Vectors and points are written as `[x, y]` and rectangles as
+//! `{ "position": [x, y], "size": [w, h] }`, independently of nalgebra's own format.
+//! Transforms are written as reals in Godot's order.
+
+use crate::types::{Transform2D, Transform2DTrait, Transform3D, Transform3DTrait};
+
+use indexmap::IndexMap;
+use nalgebra::{Matrix3, Point2, Quaternion, UnitQuaternion, Vector2, Vector3};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::{BTreeMap, HashMap};
+
+pub mod vector2 {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(vector: &Vector2<f32>, s: S) -> Result<S::Ok, S::Error> {
+        [vector.x, vector.y].serialize(s)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Vector2<f32>, D::Error> {
+        let [x, y] = <[f32; 2]>::deserialize(d)?;
+
+        Ok(Vector2::new(x, y))
+    }
+}
+
+pub mod point2 {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(point: &Point2<f32>, s: S) -> Result<S::Ok, S::Error> {
+        [point.x, point.y].serialize(s)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Point2<f32>, D::Error> {
+        let [x, y] = <[f32; 2]>::deserialize(d)?;
+
+        Ok(Point2::new(x, y))
+    }
+}
+
+pub mod vector3 {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(vector: &Vector3<f32>, s: S) -> Result<S::Ok, S::Error> {
+        [vector.x, vector.y, vector.z].serialize(s)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Vector3<f32>, D::Error> {
+        let [x, y, z] = <[f32; 3]>::deserialize(d)?;
+
+        Ok(Vector3::new(x, y, z))
+    }
+}
+
+/// Written as `[x, y, z, w]`, like Godot's `Quat`
+pub mod unit_quaternion {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        quaternion: &UnitQuaternion<f32>,
+        s: S,
+    ) -> Result<S::Ok, S::Error> {
+        let q = quaternion.quaternion();
+
+        [q.i, q.j, q.k, q.w].serialize(s)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<UnitQuaternion<f32>, D::Error> {
+        let [x, y, z, w] = <[f32; 4]>::deserialize(d)?;
+
+        Ok(UnitQuaternion::new_unchecked(Quaternion::new(w, x, y, z)))
+    }
+}
+
+pub mod vector2_vec {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(vectors: &[Vector2<f32>], s: S) -> Result<S::Ok, S::Error> {
+        let arr: Vec<[f32; 2]> = vectors.iter().map(|v| [v.x, v.y]).collect();
+
+        arr.serialize(s)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<Vector2<f32>>, D::Error> {
+        let arr = Vec::<[f32; 2]>::deserialize(d)?;
+
+        Ok(arr.into_iter().map(|[x, y]| Vector2::new(x, y)).collect())
+    }
+}
+
+pub mod vector3_vec {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(vectors: &[Vector3<f32>], s: S) -> Result<S::Ok, S::Error> {
+        let arr: Vec<[f32; 3]> = vectors.iter().map(|v| [v.x, v.y, v.z]).collect();
+
+        arr.serialize(s)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<Vector3<f32>>, D::Error> {
+        let arr = Vec::<[f32; 3]>::deserialize(d)?;
+
+        Ok(arr
+            .into_iter()
+            .map(|[x, y, z]| Vector3::new(x, y, z))
+            .collect())
+    }
+}
+
+/// Segments as `[[x, y], [x, y]]`
+pub mod segment2_vec {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        segments: &[[Vector2<f32>; 2]],
+        s: S,
+    ) -> Result<S::Ok, S::Error> {
+        let arr: Vec<[[f32; 2]; 2]> = segments
+            .iter()
+            .map(|segment| segment.map(|v| [v.x, v.y]))
+            .collect();
+
+        arr.serialize(s)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        d: D,
+    ) -> Result<Vec<[Vector2<f32>; 2]>, D::Error> {
+        let arr = Vec::<[[f32; 2]; 2]>::deserialize(d)?;
+
+        Ok(arr
+            .into_iter()
+            .map(|segment| segment.map(|[x, y]| Vector2::new(x, y)))
+            .collect())
+    }
+}
+
+/// Triangles as `[[x, y, z], [x, y, z], [x, y, z]]`
+pub mod triangle3_vec {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        triangles: &[[Vector3<f32>; 3]],
+        s: S,
+    ) -> Result<S::Ok, S::Error> {
+        let arr: Vec<[[f32; 3]; 3]> = triangles
+            .iter()
+            .map(|triangle| triangle.map(|v| [v.x, v.y, v.z]))
+            .collect();
+
+        arr.serialize(s)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        d: D,
+    ) -> Result<Vec<[Vector3<f32>; 3]>, D::Error> {
+        let arr = Vec::<[[f32; 3]; 3]>::deserialize(d)?;
+
+        Ok(arr
+            .into_iter()
+            .map(|triangle| triangle.map(|[x, y, z]| Vector3::new(x, y, z)))
+            .collect())
+    }
+}
+
+pub mod rect2 {
+    use super::*;
+
+    #[derive(Serialize, Deserialize)]
+    pub(super) struct Rect2 {
+        position: [f32; 2],
+        size: [f32; 2],
+    }
+
+    impl From<&[Vector2<f32>; 2]> for Rect2 {
+        fn from(rect: &[Vector2<f32>; 2]) -> Self {
+            Rect2 {
+                position: [rect[0].x, rect[0].y],
+                size: [rect[1].x, rect[1].y],
+            }
+        }
+    }
+
+    impl From<Rect2> for [Vector2<f32>; 2] {
+        fn from(rect: Rect2) -> Self {
+            [
+                Vector2::new(rect.position[0], rect.position[1]),
+                Vector2::new(rect.size[0], rect.size[1]),
+            ]
+        }
+    }
+
+    pub fn serialize<S: Serializer>(rect: &[Vector2<f32>; 2], s: S) -> Result<S::Ok, S::Error> {
+        Rect2::from(rect).serialize(s)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<[Vector2<f32>; 2], D::Error> {
+        Ok(Rect2::deserialize(d)?.into())
+    }
+}
+
+pub mod rect2_option {
+    use super::rect2::Rect2;
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        rect: &Option<[Vector2<f32>; 2]>,
+        s: S,
+    ) -> Result<S::Ok, S::Error> {
+        rect.as_ref().map(Rect2::from).serialize(s)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        d: D,
+    ) -> Result<Option<[Vector2<f32>; 2]>, D::Error> {
+        Ok(Option::<Rect2>::deserialize(d)?.map(Into::into))
+    }
+}
+
+/// Rects keyed by node id
+pub mod rect2_map {
+    use super::rect2::Rect2;
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        rects: &IndexMap<usize, [Vector2<f32>; 2]>,
+        s: S,
+    ) -> Result<S::Ok, S::Error> {
+        let rects: IndexMap<usize, Rect2> = rects
+            .iter()
+            .map(|(id, rect)| (*id, Rect2::from(rect)))
+            .collect();
+
+        rects.serialize(s)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        d: D,
+    ) -> Result<IndexMap<usize, [Vector2<f32>; 2]>, D::Error> {
+        let rects = IndexMap::<usize, Rect2>::deserialize(d)?;
+
+        Ok(rects
+            .into_iter()
+            .map(|(id, rect)| (id, rect.into()))
+            .collect())
+    }
+}
+
+pub mod transform2d {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(transform: &Transform2D, s: S) -> Result<S::Ok, S::Error> {
+        transform.to_reals().serialize(s)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Transform2D, D::Error> {
+        Ok(Transform2D::from_reals(<[f32; 6]>::deserialize(d)?))
+    }
+}
+
+pub mod basis {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(basis: &Matrix3<f32>, s: S) -> Result<S::Ok, S::Error> {
+        let reals: Vec<f32> = basis.transpose().iter().copied().collect();
+
+        reals.serialize(s)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Matrix3<f32>, D::Error> {
+        let reals = <[f32; 9]>::deserialize(d)?;
+
+        Ok(Matrix3::from_row_slice(&reals))
+    }
+}
+
+pub mod transform3d {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(transform: &Transform3D, s: S) -> Result<S::Ok, S::Error> {
+        transform.to_reals().serialize(s)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Transform3D, D::Error> {
+        Ok(Transform3D::from_reals(<[f32; 12]>::deserialize(d)?))
+    }
+}
+
+/// Maps keyed by tile coordinate, as `[[x, y], value]` pairs sorted by coordinate
+pub mod coord_map {
+    use super::*;
+
+    pub fn serialize<S: Serializer, T: Serialize>(
+        map: &HashMap<(i32, i32), T>,
+        s: S,
+    ) -> Result<S::Ok, S::Error> {
+        let mut pairs: Vec<([i32; 2], &T)> = map.iter().map(|(k, v)| ([k.0, k.1], v)).collect();
+        pairs.sort_by_key(|([x, y], _)| (*y, *x));
+
+        pairs.serialize(s)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>, T: Deserialize<'de>>(
+        d: D,
+    ) -> Result<HashMap<(i32, i32), T>, D::Error> {
+        let pairs = Vec::<([i32; 2], T)>::deserialize(d)?;
+
+        Ok(pairs.into_iter().map(|([x, y], v)| ((x, y), v)).collect())
+    }
+}
+
+/// Maps with compound keys (which JSON objects can't hold), as `[key, value]` pairs
+pub mod map_entries {
+    use super::*;
+
+    pub fn serialize<S: Serializer, K: Serialize, V: Serialize>(
+        map: &BTreeMap<K, V>,
+        s: S,
+    ) -> Result<S::Ok, S::Error> {
+        map.iter().collect::<Vec<_>>().serialize(s)
+    }
+
+    pub fn deserialize<'de, D, K, V>(d: D) -> Result<BTreeMap<K, V>, D::Error>
+    where
+        D: Deserializer<'de>,
+        K: Deserialize<'de> + Ord,
+        V: Deserialize<'de>,
+    {
+        Ok(Vec::<(K, V)>::deserialize(d)?.into_iter().collect())
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use crate::types::{ControlPoint, Transform2D, Transform2DTrait, VarType};
+    use crate::uid::Uid;
+
+    use nalgebra::Vector2;
+    use serde_json::json;
+
+    fn assert_round_trip(value: VarType, expected: serde_json::Value) {
+        let encoded = serde_json::to_value(&value).unwrap();
+        assert_eq!(encoded, expected);
+        assert_eq!(serde_json::from_value::<VarType>(encoded).unwrap(), value);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        assert_round_trip(
+            VarType::Vector(Vector2::new(1.0, 2.0)),
+            json!({ "Vector": [1.0, 2.0] }),
+        );
+        assert_round_trip(
+            VarType::VectorArr(vec![Vector2::new(1.0, 2.0), Vector2::new(3.0, 4.0)]),
+            json!({ "VectorArr": [[1.0, 2.0], [3.0, 4.0]] }),
+        );
+        assert_round_trip(
+            VarType::Rect2([Vector2::new(1.0, 2.0), Vector2::new(3.0, 4.0)]),
+            json!({ "Rect2": { "position": [1.0, 2.0], "size": [3.0, 4.0] } }),
+        );
+        assert_round_trip(
+            VarType::Transform2D(Transform2D::from_reals([1.0, 0.0, 0.0, 2.0, 5.0, 6.0])),
+            json!({ "Transform2D": [1.0, 0.0, 0.0, 2.0, 5.0, 6.0] }),
+        );
+
+        let point = ControlPoint::new_point(0.5, 1.0, 0.0, 0.0);
+        let encoded = serde_json::to_value(point).unwrap();
+        assert_eq!(encoded["pos"], json!([0.5, 1.0]));
+        assert_eq!(
+            serde_json::from_value::<ControlPoint>(encoded).unwrap(),
+            point
+        );
+
+        let uid = Uid::from_text("uid://cecaux1sm7mo0").unwrap();
+        let encoded = serde_json::to_value(uid).unwrap();
+        assert_eq!(encoded, json!("uid://cecaux1sm7mo0"));
+        assert_eq!(serde_json::from_value::<Uid>(encoded).unwrap(), uid);
+    }
+}
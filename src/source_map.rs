@@ -0,0 +1,58 @@
+use std::collections::HashMap;
+
+/// Section of text scene or resource
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Section {
+    /// `[gd_scene]` or `[gd_resource]` header
+    Header,
+    /// Ext resource by id
+    ExtResource(usize),
+    /// Node by id
+    Node(usize),
+    /// Sub resource by id
+    SubResource(usize),
+    /// `[resource]` of `.tres`
+    Resource,
+    /// Connection by index
+    Connection(usize),
+}
+
+/// 1-based lines of sections and properties, recorded while parsing text
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SourceMap {
+    sections: HashMap<Section, usize>,
+    properties: HashMap<(Section, String), usize>,
+    load_steps: Option<usize>,
+}
+
+impl SourceMap {
+    pub fn insert_section(&mut self, section: Section, line: usize) {
+        self.sections.insert(section, line);
+    }
+
+    pub fn insert_property(&mut self, section: Section, key: String, line: usize) {
+        self.properties.insert((section, key), line);
+    }
+
+    /// Records `load_steps` of the file header, `0` when it's omitted
+    pub fn set_load_steps(&mut self, load_steps: usize) {
+        self.load_steps = Some(load_steps).filter(|steps| *steps > 0);
+    }
+
+    /// `load_steps` declared in the file header, it isn't kept in `Tscn` as it's
+    /// recomputed when writing
+    pub fn load_steps(&self) -> Option<usize> {
+        self.load_steps
+    }
+
+    /// Line of the section header
+    pub fn section_line(&self, section: Section) -> Option<usize> {
+        self.sections.get(&section).copied()
+    }
+
+    /// Line where the property (i.e. `tracks/0/type`) starts
+    pub fn property_line(&self, section: Section, key: &str) -> Option<usize> {
+        self.properties.get(&(section, key.to_string())).copied()
+    }
+}
@@ -1,5 +1,9 @@
 pub trait StrHelper {
     fn check_borders(&self, start_char: char, end_char: char) -> bool;
+    /// Resolves escapes of text resource string (`\"`, `\\`, `\n`, ...)
+    fn unescape(&self) -> String;
+    /// Escapes `"` and `\` the way text resources store strings
+    fn escape(&self) -> String;
 }
 
 impl StrHelper for str {
@@ -16,4 +20,48 @@ impl StrHelper for str {
 
         first.unwrap() == start_char && last.unwrap() == end_char
     }
+
+    fn unescape(&self) -> String {
+        let mut result = String::with_capacity(self.len());
+        let mut chars = self.chars();
+
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                result.push(c);
+                continue;
+            }
+
+            match chars.next() {
+                Some('n') => result.push('\n'),
+                Some('t') => result.push('\t'),
+                Some('r') => result.push('\r'),
+                Some(escaped) => result.push(escaped),
+                None => result.push('\\'),
+            }
+        }
+
+        result
+    }
+
+    fn escape(&self) -> String {
+        self.replace('\\', "\\\\").replace('"', "\\\"")
+    }
+}
+
+pub trait ConstructorHelper {
+    /// Reals of constructor text (i.e. `Color( 1, 0.5, 0, 1 )`, `PackedFloat32Array(0, 1)`)
+    fn constructor_reals(&self) -> Vec<f32>;
+}
+
+impl ConstructorHelper for str {
+    fn constructor_reals(&self) -> Vec<f32> {
+        let args = match (self.find('('), self.rfind(')')) {
+            (Some(start), Some(end)) if start < end => &self[start + 1..end],
+            _ => return Vec::new(),
+        };
+
+        args.split(',')
+            .filter_map(|real| real.trim().parse().ok())
+            .collect()
+    }
 }
@@ -0,0 +1,531 @@
+//! Tiled (`.tmx` / `.tsx`) export of TileMap nodes and their TileSet
+//!
+//! Every tile, and every subtile of autotiles and atlases, becomes a tile of an image
+//! collection tileset, cut out of its texture with `x`, `y`, `width` and `height` (Tiled 1.9+).
+//! Godot ids are kept in the `godot_tile_id` and `godot_autotile_coord` tile properties and
+//! the map origin in the `godot_offset_x` / `godot_offset_y` map properties, which is what
+//! `import_map` relies on to convert back. Only Godot 3 tile sets are supported.
+
+use crate::nodes::{Cell, TileDataFormat, TileMap};
+use crate::types::{Tile, TileCoord, TileSet, Transform2DTrait, VarType};
+use crate::{PropertyTrait, Tscn};
+
+use nalgebra::Vector2;
+use std::collections::HashMap;
+use xml::attribute::OwnedAttribute;
+use xml::reader::{EventReader, XmlEvent};
+
+const FLIPPED_HORIZONTALLY: u32 = 0x8000_0000;
+const FLIPPED_VERTICALLY: u32 = 0x4000_0000;
+const FLIPPED_DIAGONALLY: u32 = 0x2000_0000;
+const GID_MASK: u32 = 0x1FFF_FFFF;
+
+const HEADER: &str = r#"<?xml version="1.0" encoding="UTF-8"?>"#;
+const VERSION: &str = r#"version="1.10" tiledversion="1.10.2""#;
+
+/// Tiled tile made out of Godot tile (or its subtile)
+struct TiledTile<'a> {
+    tile: &'a Tile,
+    coord: TileCoord,
+    region: [Vector2<f32>; 2],
+}
+
+/// Writes `.tsx` tileset, shape sub-resources and textures are looked up in `tileset_tscn`
+pub fn export_tileset(tileset_tscn: &Tscn, tileset: &TileSet, name: &str) -> String {
+    let tiles = tiled_tiles(tileset);
+    let tile_size = tiles
+        .iter()
+        .fold(Vector2::zeros(), |size, tiled| size.sup(&tiled.region[1]));
+
+    let mut lines = vec![
+        HEADER.to_string(),
+        format!(
+            r#"<tileset {} name="{}" tilewidth="{}" tileheight="{}" tilecount="{}" columns="0">"#,
+            VERSION,
+            escape(name),
+            tile_size.x,
+            tile_size.y,
+            tiles.len()
+        ),
+        r#" <grid orientation="orthogonal" width="1" height="1"/>"#.to_string(),
+    ];
+
+    for (id, tiled) in tiles.iter().enumerate() {
+        let [position, size] = tiled.region;
+        lines.push(format!(
+            r#" <tile id="{}" x="{}" y="{}" width="{}" height="{}">"#,
+            id, position.x, position.y, size.x, size.y
+        ));
+        lines.push("  <properties>".to_string());
+        lines.push(format!(
+            r#"   <property name="godot_autotile_coord" value="{},{}"/>"#,
+            tiled.coord.0, tiled.coord.1
+        ));
+        lines.push(format!(
+            r#"   <property name="godot_tile_id" type="int" value="{}"/>"#,
+            tiled.tile.id
+        ));
+        lines.push("  </properties>".to_string());
+
+        if let Some(path) = tiled.tile.texture_path(tileset_tscn) {
+            lines.push(format!(
+                r#"  <image source="{}"/>"#,
+                escape(path.trim_start_matches("res://"))
+            ));
+        }
+
+        let objects = collision_objects(tileset_tscn, tiled);
+        if !objects.is_empty() {
+            lines.push(r#"  <objectgroup draworder="index" id="2">"#.to_string());
+            lines.extend(objects);
+            lines.push("  </objectgroup>".to_string());
+        }
+
+        lines.push(" </tile>".to_string());
+    }
+
+    lines.push("</tileset>".to_string());
+    format!("{}\n", lines.join("\n"))
+}
+
+/// Writes `.tmx` map with a layer for each of the TileMap nodes of `scene`
+pub fn export_map(
+    scene: &Tscn,
+    tilemaps: &[usize],
+    tileset: &TileSet,
+    tsx_source: &str,
+) -> Result<String, String> {
+    let ids: HashMap<(usize, TileCoord), usize> = tiled_tiles(tileset)
+        .iter()
+        .enumerate()
+        .map(|(id, tiled)| ((tiled.tile.id, tiled.coord), id))
+        .collect();
+
+    let mut layers: Vec<(&str, TileMap)> = Vec::new();
+    for id in tilemaps {
+        let node = scene.nodes.get(id).ok_or(format!("Missing node {}", id))?;
+        layers.push((&node.name, TileMap::from_node(node)?));
+    }
+
+    let cell_size: Vector2<f32> = tilemaps
+        .first()
+        .and_then(|id| scene.nodes[id].properties.get_as("cell_size"))
+        .unwrap_or_else(|| Vector2::new(64.0, 64.0));
+
+    let used: Vec<TileCoord> = layers
+        .iter()
+        .flat_map(|(_, tilemap)| tilemap.used_cells())
+        .collect();
+    let min = (
+        used.iter().map(|pos| pos.0).min().unwrap_or(0),
+        used.iter().map(|pos| pos.1).min().unwrap_or(0),
+    );
+    let max = (
+        used.iter().map(|pos| pos.0).max().unwrap_or(0),
+        used.iter().map(|pos| pos.1).max().unwrap_or(0),
+    );
+    let (width, height) = (max.0 - min.0 + 1, max.1 - min.1 + 1);
+
+    let mut lines = vec![
+        HEADER.to_string(),
+        format!(
+            r#"<map {} orientation="orthogonal" renderorder="right-down" width="{}" height="{}" tilewidth="{}" tileheight="{}" infinite="0" nextlayerid="{}" nextobjectid="1">"#,
+            VERSION,
+            width,
+            height,
+            cell_size.x,
+            cell_size.y,
+            layers.len() + 1
+        ),
+        " <properties>".to_string(),
+        format!(
+            r#"  <property name="godot_offset_x" type="int" value="{}"/>"#,
+            min.0
+        ),
+        format!(
+            r#"  <property name="godot_offset_y" type="int" value="{}"/>"#,
+            min.1
+        ),
+        " </properties>".to_string(),
+        format!(
+            r#" <tileset firstgid="1" source="{}"/>"#,
+            escape(tsx_source)
+        ),
+    ];
+
+    for (layer_id, (name, tilemap)) in layers.iter().enumerate() {
+        let mut rows: Vec<String> = Vec::new();
+        for y in min.1..=max.1 {
+            let mut row: Vec<String> = Vec::new();
+            for x in min.0..=max.0 {
+                let gid = match tilemap.get_cell(x, y) {
+                    Some(cell) => gid(&ids, cell)?,
+                    None => 0,
+                };
+                row.push(gid.to_string());
+            }
+            rows.push(row.join(","));
+        }
+
+        lines.push(format!(
+            r#" <layer id="{}" name="{}" width="{}" height="{}">"#,
+            layer_id + 1,
+            escape(name),
+            width,
+            height
+        ));
+        lines.push(r#"  <data encoding="csv">"#.to_string());
+        lines.push(rows.join(",\n"));
+        lines.push("</data>".to_string());
+        lines.push(" </layer>".to_string());
+    }
+
+    lines.push("</map>".to_string());
+    Ok(format!("{}\n", lines.join("\n")))
+}
+
+/// Reads layers of `.tmx` map back into Godot 3 `tile_data` cells, by layer name
+pub fn import_map(tmx: &str, tsx: &str) -> Result<Vec<(String, TileMap)>, String> {
+    // Tiled tile id -> (Godot tile id, autotile coordinate)
+    let mut godot_ids: HashMap<u32, (usize, TileCoord)> = HashMap::new();
+    let mut tile: Option<(u32, HashMap<String, String>)> = None;
+
+    for event in EventReader::from_str(tsx) {
+        match event.map_err(|e| e.to_string())? {
+            XmlEvent::StartElement {
+                name, attributes, ..
+            } => match name.local_name.as_str() {
+                "tile" => {
+                    let id = parse_attribute(&attributes, "id")?;
+                    tile = Some((id, HashMap::new()));
+                }
+                "property" => {
+                    if let Some((_, properties)) = tile.as_mut() {
+                        properties.insert(
+                            attribute(&attributes, "name")?.to_string(),
+                            attribute(&attributes, "value")?.to_string(),
+                        );
+                    }
+                }
+                _ => (),
+            },
+            XmlEvent::EndElement { name } if name.local_name == "tile" => {
+                if let Some((id, properties)) = tile.take() {
+                    let tile_id = properties
+                        .get("godot_tile_id")
+                        .and_then(|id| id.parse().ok())
+                        .unwrap_or(id as usize);
+                    let coord = properties
+                        .get("godot_autotile_coord")
+                        .and_then(|coord| coord.split_once(','))
+                        .and_then(|(x, y)| Some((x.parse().ok()?, y.parse().ok()?)))
+                        .unwrap_or((0, 0));
+
+                    godot_ids.insert(id, (tile_id, coord));
+                }
+            }
+            _ => (),
+        }
+    }
+
+    let mut layers: Vec<(String, TileMap)> = Vec::new();
+    let mut offset: TileCoord = (0, 0);
+    let mut firstgid: u32 = 1;
+    let mut layer: Option<(String, i32)> = None;
+    let mut data = String::new();
+
+    for event in EventReader::from_str(tmx) {
+        match event.map_err(|e| e.to_string())? {
+            XmlEvent::StartElement {
+                name, attributes, ..
+            } => match name.local_name.as_str() {
+                "property" if layer.is_none() => match attribute(&attributes, "name")? {
+                    "godot_offset_x" => offset.0 = parse_attribute(&attributes, "value")?,
+                    "godot_offset_y" => offset.1 = parse_attribute(&attributes, "value")?,
+                    _ => (),
+                },
+                "tileset" => firstgid = parse_attribute(&attributes, "firstgid")?,
+                "layer" => {
+                    let name = attribute(&attributes, "name")?.to_string();
+                    layer = Some((name, parse_attribute(&attributes, "width")?));
+                    data.clear();
+                }
+                "data" => {
+                    let encoding = attribute(&attributes, "encoding").unwrap_or("xml");
+                    if encoding != "csv" {
+                        return Err(format!("Unsupported layer data encoding `{}`", encoding));
+                    }
+                }
+                _ => (),
+            },
+            XmlEvent::Characters(characters) => data.push_str(&characters),
+            XmlEvent::EndElement { name } if name.local_name == "layer" => {
+                if let Some((name, width)) = layer.take() {
+                    let mut tilemap = TileMap::new("tile_data", TileDataFormat::Triplets);
+
+                    for (i, gid) in data.split(',').map(str::trim).enumerate() {
+                        let gid = gid.parse::<u32>().map_err(|e| e.to_string())?;
+                        if gid == 0 {
+                            continue;
+                        }
+
+                        let local_id = (gid & GID_MASK)
+                            .checked_sub(firstgid)
+                            .ok_or(format!("Invalid tile gid {}", gid))?;
+                        let (tile_id, autotile_coord) = godot_ids
+                            .get(&local_id)
+                            .copied()
+                            .unwrap_or((local_id as usize, (0, 0)));
+
+                        let cell = Cell {
+                            tile_id,
+                            flip_h: gid & FLIPPED_HORIZONTALLY != 0,
+                            flip_v: gid & FLIPPED_VERTICALLY != 0,
+                            transpose: gid & FLIPPED_DIAGONALLY != 0,
+                            autotile_coord,
+                            alternative: 0,
+                        };
+
+                        let i = i as i32;
+                        tilemap.set_cell(i % width + offset.0, i / width + offset.1, cell);
+                    }
+
+                    layers.push((name, tilemap));
+                }
+            }
+            _ => (),
+        }
+    }
+
+    Ok(layers)
+}
+
+fn tiled_tiles(tileset: &TileSet) -> Vec<TiledTile<'_>> {
+    let mut tiles = Vec::new();
+
+    for tile in tileset.tiles.values() {
+        match &tile.autotile {
+            Some(autotile) if autotile.tile_size.x > 0.0 && autotile.tile_size.y > 0.0 => {
+                let step = autotile.tile_size.add_scalar(autotile.spacing as f32);
+                let size = tile.region[1].add_scalar(autotile.spacing as f32);
+                let (columns, rows) = ((size.x / step.x) as i32, (size.y / step.y) as i32);
+
+                for y in 0..rows {
+                    for x in 0..columns {
+                        tiles.push(TiledTile {
+                            tile,
+                            coord: (x, y),
+                            region: autotile.subtile_region(&tile.region, (x, y)),
+                        });
+                    }
+                }
+            }
+            _ => tiles.push(TiledTile {
+                tile,
+                coord: (0, 0),
+                region: tile.region,
+            }),
+        }
+    }
+
+    tiles
+}
+
+fn collision_objects(tileset_tscn: &Tscn, tiled: &TiledTile) -> Vec<String> {
+    let mut objects = Vec::new();
+
+    for shape in tiled.tile.shapes_at(tiled.coord) {
+        let resource = match &shape.shape {
+            VarType::SubResource(id) => tileset_tscn.sub_resources.get(id),
+            _ => None,
+        };
+        let resource = match resource {
+            Some(resource) => resource,
+            None => continue,
+        };
+
+        let placement = tiled.tile.shape_placement(shape);
+        let origin = placement.get_origin();
+        let properties = &resource.properties;
+        let id = objects.len() + 1;
+
+        let mut children: Vec<String> = Vec::new();
+        if shape.one_way {
+            children.push(
+                r#"<properties><property name="one_way" type="bool" value="true"/></properties>"#
+                    .to_string(),
+            );
+        }
+
+        let attributes = match resource.rtype.as_str() {
+            "RectangleShape2D" => {
+                let extents: Vector2<f32> = match properties.get_as("extents") {
+                    Some(extents) => extents,
+                    None => continue,
+                };
+                let position = origin - extents;
+
+                format!(
+                    r#"x="{}" y="{}" width="{}" height="{}""#,
+                    position.x,
+                    position.y,
+                    extents.x * 2.0,
+                    extents.y * 2.0
+                )
+            }
+            "CircleShape2D" => {
+                let radius: f32 = match properties.get_as("radius") {
+                    Some(radius) => radius,
+                    None => continue,
+                };
+                children.push("<ellipse/>".to_string());
+
+                format!(
+                    r#"x="{}" y="{}" width="{}" height="{}""#,
+                    origin.x - radius,
+                    origin.y - radius,
+                    radius * 2.0,
+                    radius * 2.0
+                )
+            }
+            "ConvexPolygonShape2D" => {
+                let points = match properties.get_from("points") {
+                    Some(VarType::VectorArr(points)) => points,
+                    _ => continue,
+                };
+                let points: Vec<String> = points
+                    .iter()
+                    .map(|point| {
+                        let point = placement.xform(*point);
+                        format!("{},{}", point.x, point.y)
+                    })
+                    .collect();
+                children.push(format!(r#"<polygon points="{}"/>"#, points.join(" ")));
+
+                r#"x="0" y="0""#.to_string()
+            }
+            _ => continue,
+        };
+
+        if children.is_empty() {
+            objects.push(format!(r#"   <object id="{}" {}/>"#, id, attributes));
+        } else {
+            objects.push(format!(
+                r#"   <object id="{}" {}>{}</object>"#,
+                id,
+                attributes,
+                children.join("")
+            ));
+        }
+    }
+
+    objects
+}
+
+fn gid(ids: &HashMap<(usize, TileCoord), usize>, cell: &Cell) -> Result<u32, String> {
+    let id = ids
+        .get(&(cell.tile_id, cell.autotile_coord))
+        .or_else(|| ids.get(&(cell.tile_id, (0, 0))))
+        .ok_or(format!("Tile {} is missing in the tile set", cell.tile_id))?;
+
+    let mut gid = *id as u32 + 1;
+    for (flag, set) in &[
+        (FLIPPED_HORIZONTALLY, cell.flip_h),
+        (FLIPPED_VERTICALLY, cell.flip_v),
+        (FLIPPED_DIAGONALLY, cell.transpose),
+    ] {
+        if *set {
+            gid |= flag;
+        }
+    }
+
+    Ok(gid)
+}
+
+fn attribute<'a>(attributes: &'a [OwnedAttribute], name: &str) -> Result<&'a str, String> {
+    attributes
+        .iter()
+        .find(|attribute| attribute.name.local_name == name)
+        .map(|attribute| attribute.value.as_str())
+        .ok_or(format!("Missing `{}` attribute", name))
+}
+
+fn parse_attribute<T: std::str::FromStr>(
+    attributes: &[OwnedAttribute],
+    name: &str,
+) -> Result<T, String> {
+    attribute(attributes, name)?
+        .parse::<T>()
+        .map_err(|_| format!("Invalid `{}` attribute", name))
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tiled::*;
+    use crate::Loader;
+    use std::convert::TryFrom;
+
+    const TILESET: &str = r#"[gd_resource type="TileSet" load_steps=3 format=2]
+
+[ext_resource path="res://tiles.png" type="Texture" id=1]
+
+[sub_resource type="RectangleShape2D" id=1]
+extents = Vector2( 8, 8 )
+
+[resource]
+0/name = "wall"
+0/texture = ExtResource( 1 )
+0/region = Rect2( 0, 0, 32, 16 )
+0/tile_mode = 2
+0/autotile/tile_size = Vector2( 16, 16 )
+0/autotile/spacing = 0
+0/shapes = [ {
+"autotile_coord": Vector2( 1, 0 ),
+"one_way": false,
+"one_way_margin": 1.0,
+"shape": SubResource( 1 ),
+"shape_transform": Transform2D( 1, 0, 0, 1, 8, 8 )
+} ]
+1/name = "floor"
+1/texture = ExtResource( 1 )
+1/region = Rect2( 32, 0, 16, 16 )
+1/tile_mode = 0
+"#;
+
+    const SCENE: &str = r#"[gd_scene format=2]
+
+[node name="Level" type="Node2D"]
+
+[node name="Ground" type="TileMap" parent="."]
+cell_size = Vector2( 16, 16 )
+format = 1
+tile_data = PoolIntArray( -65536, 1, 0, -65535, 1, 0, 0, 536870912, 1, 1, 1610612737, 0 )
+"#;
+
+    #[test]
+    fn test_tiled_round_trip() {
+        let tileset_tscn = Loader::new().parse_tscn(TILESET);
+        let tileset = TileSet::try_from(&tileset_tscn).unwrap();
+        let scene = Loader::new().parse_tscn(SCENE);
+
+        let tsx = export_tileset(&tileset_tscn, &tileset, "tiles");
+        assert!(tsx.contains(r#"<tile id="1" x="16" y="0" width="16" height="16">"#));
+        assert!(tsx.contains(r#"<image source="tiles.png"/>"#));
+        assert!(tsx.contains(r#"<object id="1" x="0" y="0" width="16" height="16"/>"#));
+
+        let tmx = export_map(&scene, &[1], &tileset, "tiles.tsx").unwrap();
+        assert!(tmx.contains("3,3,\n2147483650,3221225475"));
+
+        let layers = import_map(&tmx, &tsx).unwrap();
+        assert_eq!(layers.len(), 1);
+        assert_eq!(layers[0].0, "Ground");
+        assert_eq!(layers[0].1, TileMap::from_node(&scene.nodes[&1]).unwrap());
+    }
+}
@@ -1,34 +1,48 @@
+use crate::str_helper::ConstructorHelper;
 use crate::str_helper::StrHelper;
-use crate::types::{ControlPoint, Curve, VarType};
+use crate::types::{Curve, Transform2D, Transform2DTrait, Transform3D, Transform3DTrait, VarType};
 use crate::NodeEntry;
 
 use std::collections::HashMap;
-use std::convert::TryInto;
+use std::convert::{TryFrom, TryInto};
 
 use indexmap::IndexMap;
 use regex::Regex;
 
-use nalgebra::Vector2;
+use nalgebra::{Matrix3, Vector2};
 
 lazy_static! {
-    static ref RE_VECTOR: Regex =
-        Regex::new(r"^Vector2\( (-?\d+(?:\.\d+)?), (-?\d+(?:\.\d+)?) \)$")
-            .expect("Failed to read regex pattern");
+    static ref RE_VECTOR: Regex = Regex::new(&format!(
+        r"^Vector2\(\s*({real}),\s*({real})\s*\)$",
+        real = r"-?(?:\d+(?:\.\d*)?|\.\d+)(?:[eE][-+]?\d+)?"
+    ))
+    .expect("Failed to read regex pattern");
     static ref RE_RECT: Regex =
-        Regex::new(r"^Rect2\( (.+) \)$").expect("Failed to read regex pattern");
+        Regex::new(r"^Rect2\(\s*(.+?)\s*\)$").expect("Failed to read regex pattern");
+    static ref RE_TRANSFORM2D: Regex =
+        Regex::new(r"^Transform2D\(\s*(.+?)\s*\)$").expect("Failed to read regex pattern");
+    static ref RE_BASIS: Regex =
+        Regex::new(r"^Basis\(\s*(.+?)\s*\)$").expect("Failed to read regex pattern");
+    static ref RE_TRANSFORM: Regex =
+        Regex::new(r"^Transform(?:3D)?\(\s*(.+?)\s*\)$").expect("Failed to read regex pattern");
     static ref RE_VECTOR_POOL: Regex =
-        Regex::new(r"^PoolVector2Array\( (.+) \)$").expect("Failed to read regex pattern");
+        Regex::new(r"^PoolVector2Array\(\s*(.*?)\s*\)$").expect("Failed to read regex pattern");
     static ref RE_INT_POOL: Regex =
-        Regex::new(r"^PoolIntArray\( (.+) \)$").expect("Failed to read regex pattern");
+        Regex::new(r"^PoolIntArray\(\s*(.*?)\s*\)$").expect("Failed to read regex pattern");
+    static ref RE_STR_POOL: Regex =
+        Regex::new(r"^PoolStringArray\(\s*(.*?)\s*\)$").expect("Failed to read regex pattern");
     static ref RE_REAL_POOL: Regex =
-        Regex::new(r"^PoolRealArray\( (.+) \)$").expect("Failed to read regex pattern");
+        Regex::new(r"^PoolRealArray\(\s*(.*?)\s*\)$").expect("Failed to read regex pattern");
     static ref RE_SUBRES: Regex =
         Regex::new(r"^SubResource\(\s?(\d+)\s?\)$").expect("Failed to read regex pattern");
     static ref RE_EXTRES: Regex =
         Regex::new(r"^ExtResource\(\s?(\d+)\s?\)$").expect("Failed to read regex pattern");
+    static ref RE_STRING_REF: Regex = Regex::new(r#"^(Sub|Ext)Resource\(\s?"([^"]*)"\s?\)$"#)
+        .expect("Failed to read regex pattern");
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum NodeType {
     Node,
     GdScene,
@@ -36,39 +50,62 @@ pub enum NodeType {
     GdResource,
     SubResource,
     ExtResource,
+    Connection,
+    Editable,
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Command {
     pub lhs: String,
     pub rhs: VarType,
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Node {
     pub id: usize,
+    /// Godot 4 string id (i.e. `"1_x5k2p"`), mapped to `id` by the loader
+    pub string_id: String,
     pub format: usize,
     pub load_steps: usize,
     pub path: String,
+    pub uid: String,
     pub name: String,
     pub rtype: String,
     pub parent: String,
     pub node_type: NodeType,
     pub instance_resource_id: usize,
+    pub instance_string_id: String,
+    pub signal: String,
+    pub from: String,
+    pub to: String,
+    pub method: String,
+    pub flags: usize,
+    pub groups: Vec<String>,
 }
 
 impl Default for Node {
     fn default() -> Self {
         Node {
             id: 0,
+            string_id: String::from(""),
             format: 0,
             load_steps: 0,
             path: String::from(""),
+            uid: String::from(""),
             name: String::from(""),
             rtype: String::from(""),
             parent: String::from(""),
             instance_resource_id: 0,
+            instance_string_id: String::from(""),
             node_type: NodeType::Node,
+            signal: String::from(""),
+            from: String::from(""),
+            to: String::from(""),
+            method: String::from(""),
+            flags: 0,
+            groups: Vec::new(),
         }
     }
 }
@@ -78,7 +115,10 @@ pub struct TscnHelper();
 impl TscnHelper {
     // (node_type: &str, attributes: Vec<&str>)
     pub fn parse_node(line: &str) -> (&str, &str) {
-        let contents = line.trim_start_matches('[').trim_end_matches(']');
+        // Only the section brackets, attributes may end with an array (`groups=[ "a" ]]`)
+        let contents = line.trim();
+        let contents = contents.strip_prefix('[').unwrap_or(contents);
+        let contents = contents.strip_suffix(']').unwrap_or(contents);
 
         let rtype: &str = contents.split_whitespace().next().unwrap();
 
@@ -87,44 +127,44 @@ impl TscnHelper {
         (rtype, params_str)
     }
 
-    fn parse_rhs<'a>(rhs_data: &'a str, rtype: &str) -> VarType {
+    fn parse_rhs(rhs_data: &str, rtype: &str) -> VarType {
         if rhs_data.check_borders('"', '"') {
-            return VarType::Str(String::from(rhs_data.trim_matches('"')));
+            return VarType::Str(rhs_data[1..rhs_data.len() - 1].unescape());
         }
 
         if rhs_data.check_borders('[', ']') {
-            let str_data = rhs_data.trim_start_matches('[').trim_end_matches(']');
+            let str_data = &rhs_data[1..rhs_data.len() - 1];
             let split = Self::get_splitted(str_data);
 
-            match rtype {
-                "Curve" => {
-                    let mut curve = Curve::default();
-
-                    for i in (0..split.len()).step_by(5) {
-                        if let VarType::Vector(vector) = &split[i] {
-                            if let VarType::Float(left_arm) = &split[i + 1] {
-                                if let VarType::Float(right_arm) = &split[i + 2] {
-                                    let control_point = ControlPoint::new_point(
-                                        vector.x, vector.y, *left_arm, *right_arm,
-                                    );
-                                    curve.add_point(control_point);
-                                }
-                            }
-                        }
-                    }
+            if rtype == "Curve" {
+                return VarType::Curve(Curve::from_data(&split));
+            }
 
-                    return VarType::Curve(curve);
-                }
-                _ => (),
+            if !split.is_empty() && split.iter().all(|v| matches!(v, VarType::Dict(_))) {
+                let maps = split
+                    .into_iter()
+                    .filter_map(|v| match v {
+                        VarType::Dict(map) => Some(map),
+                        _ => None,
+                    })
+                    .collect();
+
+                return VarType::ArrMap(maps);
             }
-        }
 
-        if rhs_data.split_whitespace().collect::<String>() == "[{" {
-            return VarType::ArrMap(vec![HashMap::default()]);
+            return VarType::Arr(split);
         }
 
-        if rhs_data.trim() == "{" {
-            return VarType::Map(HashMap::default());
+        if rhs_data.check_borders('{', '}') {
+            let mut dict = HashMap::default();
+
+            for entry in Self::split_top(&rhs_data[1..rhs_data.len() - 1], ',', usize::MAX) {
+                if let Some(command) = Self::parse_obj(entry.trim(), "") {
+                    dict.insert(command.lhs, command.rhs);
+                }
+            }
+
+            return VarType::Dict(dict);
         }
 
         if rhs_data == "true" || rhs_data == "false" {
@@ -145,12 +185,16 @@ impl TscnHelper {
 
         if RE_VECTOR_POOL.is_match(rhs_data) {
             let caps = RE_VECTOR_POOL.captures(rhs_data).unwrap();
-            let arr_str = caps.get(1).unwrap().as_str().split(", ");
+            let arr_str = caps
+                .get(1)
+                .unwrap()
+                .as_str()
+                .split(", ")
+                .filter(|s| !s.is_empty());
             let mut vec_arr: Vec<Vector2<f32>> = Vec::default();
             let mut previous: f32 = 0.0;
-            let mut i = 0;
 
-            for s in arr_str {
+            for (i, s) in arr_str.enumerate() {
                 let current = s.parse::<f32>().unwrap();
 
                 if i % 2 == 1 {
@@ -158,27 +202,53 @@ impl TscnHelper {
                 }
 
                 previous = current;
-                i += 1;
             }
 
             return VarType::VectorArr(vec_arr);
         }
 
         if RE_RECT.is_match(rhs_data) {
-            let caps = RE_RECT.captures(rhs_data).unwrap();
-            let s: Vec<&str> = caps.get(1).unwrap().as_str().split(", ").collect();
+            let reals = rhs_data.constructor_reals();
+
+            if reals.len() == 4 {
+                return VarType::Rect2([
+                    Vector2::new(reals[0], reals[1]),
+                    Vector2::new(reals[2], reals[3]),
+                ]);
+            }
+        }
 
-            let arr = [
-                Vector2::<f32>::new(s[0].parse::<f32>().unwrap(), s[1].parse::<f32>().unwrap()),
-                Vector2::<f32>::new(s[2].parse::<f32>().unwrap(), s[3].parse::<f32>().unwrap()),
-            ];
+        if RE_TRANSFORM2D.is_match(rhs_data) {
+            let reals = rhs_data.constructor_reals();
 
-            return VarType::Rect2(arr);
+            if reals.len() == 6 {
+                let mut array = [0.0; 6];
+                array.copy_from_slice(&reals);
+                return VarType::Transform2D(Transform2D::from_reals(array));
+            }
+        }
+
+        if RE_BASIS.is_match(rhs_data) || RE_TRANSFORM.is_match(rhs_data) {
+            let reals = rhs_data.constructor_reals();
+
+            if reals.len() == 9 {
+                return VarType::Basis(Matrix3::from_row_slice(&reals));
+            }
+            if reals.len() == 12 {
+                let mut array = [0.0; 12];
+                array.copy_from_slice(&reals);
+                return VarType::Transform3D(Transform3D::from_reals(array));
+            }
         }
 
         if RE_INT_POOL.is_match(rhs_data) {
             let caps = RE_INT_POOL.captures(rhs_data).unwrap();
-            let arr_str = caps.get(1).unwrap().as_str().split(", ");
+            let arr_str = caps
+                .get(1)
+                .unwrap()
+                .as_str()
+                .split(", ")
+                .filter(|s| !s.is_empty());
             let mut int_arr: Vec<isize> = Vec::default();
 
             for s in arr_str {
@@ -190,7 +260,12 @@ impl TscnHelper {
 
         if RE_REAL_POOL.is_match(rhs_data) {
             let caps = RE_REAL_POOL.captures(rhs_data).unwrap();
-            let arr_str = caps.get(1).unwrap().as_str().split(", ");
+            let arr_str = caps
+                .get(1)
+                .unwrap()
+                .as_str()
+                .split(", ")
+                .filter(|s| !s.is_empty());
             let mut real_arr: Vec<f32> = Vec::default();
 
             for s in arr_str {
@@ -200,6 +275,16 @@ impl TscnHelper {
             return VarType::FloatArr(real_arr);
         }
 
+        if RE_STR_POOL.is_match(rhs_data) {
+            let caps = RE_STR_POOL.captures(rhs_data).unwrap();
+            let str_arr: Vec<String> = Self::get_splitted(caps.get(1).unwrap().as_str())
+                .into_iter()
+                .map(|s| String::try_from(s).unwrap_or_default())
+                .collect();
+
+            return VarType::StrArr(str_arr);
+        }
+
         if RE_SUBRES.is_match(rhs_data) {
             let caps = RE_SUBRES.captures(rhs_data).unwrap();
             let id: usize = caps.get(1).unwrap().as_str().parse::<usize>().unwrap();
@@ -226,50 +311,67 @@ impl TscnHelper {
     }
 
     fn get_splitted(data: &str) -> Vec<VarType> {
-        let mut vars = Vec::default();
-
-        let mut isq_opened = false;
-        let mut isb_opened = false;
-        let mut cmd = String::from("");
+        Self::split_top(data, ',', usize::MAX)
+            .into_iter()
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .map(|item| Self::parse_rhs(item, ""))
+            .collect()
+    }
 
-        let formatted = data.trim();
+    /// Splits on `separator` outside of strings and brackets, into at most `limit` parts
+    fn split_top(data: &str, separator: char, limit: usize) -> Vec<&str> {
+        let mut parts = Vec::new();
+        let mut start = 0;
 
-        for (i, ch) in formatted.chars().enumerate() {
-            if ch == ' ' && !isq_opened && !isb_opened {
-                continue;
+        Self::scan(data, |i, ch, depth| {
+            if ch == separator && depth == 0 && parts.len() + 1 < limit {
+                parts.push(&data[start..i]);
+                start = i + ch.len_utf8();
             }
+        });
 
-            if ch == '"' {
-                isq_opened = !isq_opened;
-            }
-
-            if ch == '(' {
-                isb_opened = true;
-            }
+        parts.push(&data[start..]);
+        parts
+    }
 
-            if ch == ')' {
-                isb_opened = false;
-            }
+    /// Whether property text is complete, values spanning several lines are not until closed
+    pub fn is_complete(text: &str) -> bool {
+        let (depth, quoted) = Self::scan(text, |_, _, _| ());
 
-            if i == formatted.len() - 1 {
-                cmd.push(ch);
-                vars.push(Self::parse_rhs(&cmd, ""));
-                break;
-            }
+        !quoted && depth <= 0
+    }
 
-            if ch == ',' && !isq_opened && !isb_opened {
-                vars.push(Self::parse_rhs(&cmd, ""));
-                cmd = String::from("");
+    /// Visits characters outside of strings with their bracket depth, returns final depth
+    /// and whether a string is left open
+    fn scan(text: &str, mut visit: impl FnMut(usize, char, i32)) -> (i32, bool) {
+        let mut depth = 0;
+        let mut quoted = false;
+        let mut escaped = false;
+
+        for (i, ch) in text.char_indices() {
+            if quoted {
+                match ch {
+                    _ if escaped => escaped = false,
+                    '\\' => escaped = true,
+                    '"' => quoted = false,
+                    _ => (),
+                }
                 continue;
             }
 
-            cmd = format!("{}{}", cmd, ch);
+            match ch {
+                '"' => quoted = true,
+                '(' | '[' | '{' => depth += 1,
+                ')' | ']' | '}' => depth -= 1,
+                _ => visit(i, ch, depth),
+            }
         }
 
-        vars
+        (depth, quoted)
     }
 
-    fn parse_eq<'a>(cmd_data: [&'a str; 2], rtype: &str) -> Command {
+    fn parse_eq(cmd_data: [&str; 2], rtype: &str) -> Command {
         let lhs_data = cmd_data[0].trim();
         let rhs_data = cmd_data[1].trim();
 
@@ -281,24 +383,25 @@ impl TscnHelper {
         }
     }
 
-    pub fn parse_command<'a>(line: &'a str, rtype: &str) -> Option<Command> {
-        let cmd_data: Vec<&str> = line.split("=").collect();
+    pub fn parse_command(line: &str, rtype: &str) -> Option<Command> {
+        // Values may contain `=` as well (i.e. base64 in `PackedByteArray("AA==")`)
+        let cmd_data: Vec<&str> = line.splitn(2, '=').collect();
 
-        if cmd_data.len() != 2 {
+        if cmd_data.len() != 2 || cmd_data[0].contains('"') {
             return None;
         }
 
         Some(Self::parse_eq([cmd_data[0], cmd_data[1]], rtype))
     }
 
-    pub fn parse_obj<'a>(line: &'a str, rtype: &str) -> Option<Command> {
-        let cmd_data: Vec<&str> = line.split(":").collect();
+    pub fn parse_obj(line: &str, rtype: &str) -> Option<Command> {
+        let cmd_data: Vec<&str> = Self::split_top(line, ':', 2);
 
         if cmd_data.len() != 2 {
             return None;
         }
 
-        let lhs = cmd_data[0].trim_matches('"');
+        let lhs = cmd_data[0].trim().trim_matches('"');
 
         Some(Self::parse_eq(
             [lhs, cmd_data[1].trim().trim_end_matches(',')],
@@ -306,7 +409,19 @@ impl TscnHelper {
         ))
     }
 
-    pub fn get_node<'a>(node_type: &'a str, attributes_str: &'a str) -> Node {
+    /// Godot 4 reference with a string id (i.e. `SubResource("RectangleShape2D_abc12")`),
+    /// left unparsed as `VarType::None` by `parse_rhs`
+    pub fn string_reference(text: &str) -> Option<(NodeType, &str)> {
+        let caps = RE_STRING_REF.captures(text)?;
+        let node_type = match caps.get(1)?.as_str() {
+            "Sub" => NodeType::SubResource,
+            _ => NodeType::ExtResource,
+        };
+
+        Some((node_type, caps.get(2)?.as_str()))
+    }
+
+    pub fn get_node(node_type: &str, attributes_str: &str) -> Node {
         let mut node = Node::default();
 
         match node_type {
@@ -315,6 +430,8 @@ impl TscnHelper {
             "gd_resource" => node.node_type = NodeType::GdResource,
             "sub_resource" => node.node_type = NodeType::SubResource,
             "ext_resource" => node.node_type = NodeType::ExtResource,
+            "connection" => node.node_type = NodeType::Connection,
+            "editable" => node.node_type = NodeType::Editable,
             _ => (),
         };
 
@@ -323,44 +440,51 @@ impl TscnHelper {
             let attr_name = attribute.0.as_str();
 
             match attr_name {
-                "id" => {
-                    node.id = if let VarType::Num(id) = attribute.1 {
-                        id.try_into().unwrap()
-                    } else {
-                        0
-                    }
-                }
+                "id" => match attribute.1 {
+                    VarType::Num(id) => node.id = id.try_into().unwrap(),
+                    VarType::Str(id) => node.string_id = id,
+                    _ => (),
+                },
                 "name" => {
                     node.name = if let VarType::Str(n) = attribute.1 {
-                        String::from(n)
+                        n
                     } else {
                         "".to_string()
                     }
                 }
                 "type" => {
                     node.rtype = if let VarType::Str(rt) = attribute.1 {
-                        String::from(rt)
+                        rt
                     } else {
                         "".to_string()
                     }
                 }
                 "parent" => {
                     node.parent = if let VarType::Str(p) = attribute.1 {
-                        String::from(p)
+                        p
                     } else {
                         "".to_string()
                     }
                 }
-                "instance" => {
-                    node.instance_resource_id = if let VarType::ExtResource(r) = attribute.1 {
-                        r
-                    } else {
-                        0
+                "instance" => match attribute.1 {
+                    VarType::ExtResource(r) => node.instance_resource_id = r,
+                    VarType::None(text) => {
+                        if let Some((NodeType::ExtResource, id)) = Self::string_reference(&text) {
+                            node.instance_string_id = id.to_string();
+                        }
                     }
-                }
+                    _ => (),
+                },
                 "path" => {
                     node.path = if let VarType::Str(path) = attribute.1 {
-                        String::from(path)
+                        path
+                    } else {
+                        "".to_string()
+                    }
+                }
+                "uid" => {
+                    node.uid = if let VarType::Str(uid) = attribute.1 {
+                        uid
                     } else {
                         "".to_string()
                     }
@@ -372,6 +496,40 @@ impl TscnHelper {
                         0
                     }
                 }
+                "signal" | "from" | "to" | "method" => {
+                    let value = if let VarType::Str(s) = attribute.1 {
+                        s
+                    } else {
+                        "".to_string()
+                    };
+
+                    match attr_name {
+                        "signal" => node.signal = value,
+                        "from" => node.from = value,
+                        "to" => node.to = value,
+                        _ => node.method = value,
+                    }
+                }
+                "flags" => {
+                    node.flags = if let VarType::Num(f) = attribute.1 {
+                        f.try_into().unwrap()
+                    } else {
+                        0
+                    }
+                }
+                "groups" => {
+                    node.groups = match attribute.1 {
+                        VarType::Arr(groups) => groups
+                            .into_iter()
+                            .filter_map(|group| match group {
+                                VarType::Str(group) => Some(group),
+                                _ => None,
+                            })
+                            .collect(),
+                        VarType::StrArr(groups) => groups,
+                        _ => Vec::new(),
+                    }
+                }
                 "format" => {
                     node.format = if let VarType::Num(f) = attribute.1 {
                         f.try_into().unwrap()
@@ -390,7 +548,7 @@ impl TscnHelper {
         let mut path = "".to_string();
 
         for key in ctx.keys() {
-            let current = if key == &"." {
+            let current = if key == "." {
                 &nodes.get(ctx.get(key).unwrap()).unwrap().name
             } else {
                 key
@@ -402,7 +560,7 @@ impl TscnHelper {
         path
     }
 
-    fn split_attributes<'a>(cmd_line: &'a str) -> Vec<(String, VarType)> {
+    fn split_attributes(cmd_line: &str) -> Vec<(String, VarType)> {
         let mut commands: Vec<(String, VarType)> = Vec::new();
 
         let mut is_lhs = true;
@@ -445,9 +603,9 @@ impl TscnHelper {
 
             if previous == ' '
                 && ch.is_alphabetic()
-                && is_lhs == false
-                && lhs != ""
-                && rhs != ""
+                && !is_lhs
+                && !lhs.is_empty()
+                && !rhs.is_empty()
                 && !isq_opened
                 && !isb_opened
             {
@@ -517,6 +675,15 @@ mod tests {
             )
         );
         assert_eq!(TscnHelper::parse_node("[resource]"), ("resource", ""));
+        assert_eq!(
+            TscnHelper::parse_node(
+                "[connection signal=\"pressed\" from=\"Button\" to=\".\" method=\"_on_pressed\"]"
+            ),
+            (
+                "connection",
+                "signal=\"pressed\" from=\"Button\" to=\".\" method=\"_on_pressed\""
+            )
+        );
     }
 
     #[test]
@@ -553,6 +720,20 @@ mod tests {
                 ..Node::default()
             }
         );
+        assert_eq!(
+            TscnHelper::get_node(
+                "ext_resource",
+                "type=\"Texture2D\" uid=\"uid://cecaux1sm7mo0\" path=\"res://icon.svg\" id=\"1_x5k2p\""
+            ),
+            Node {
+                string_id: "1_x5k2p".to_string(),
+                rtype: "Texture2D".to_string(),
+                uid: "uid://cecaux1sm7mo0".to_string(),
+                path: "res://icon.svg".to_string(),
+                node_type: NodeType::ExtResource,
+                ..Node::default()
+            }
+        );
         assert_eq!(
             TscnHelper::get_node("sub_resource", "type=\"TileSet\" id=5"),
             Node {
@@ -586,6 +767,21 @@ mod tests {
                 ..Node::default()
             }
         );
+        assert_eq!(
+            TscnHelper::get_node(
+                "connection",
+                "signal=\"pressed\" from=\"Button\" to=\".\" method=\"_on_pressed\" flags=3"
+            ),
+            Node {
+                signal: "pressed".to_string(),
+                from: "Button".to_string(),
+                to: ".".to_string(),
+                method: "_on_pressed".to_string(),
+                flags: 3,
+                node_type: NodeType::Connection,
+                ..Node::default()
+            }
+        );
         assert_eq!(
             TscnHelper::get_node("resource", ""),
             Node {
@@ -594,4 +790,37 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn test_parse_godot4_constructors() {
+        let rect = TscnHelper::parse_command("region_rect = Rect2(0, 0, 16, 8.5)", "");
+        assert_eq!(
+            rect.unwrap().rhs,
+            VarType::Rect2([Vector2::new(0.0, 0.0), Vector2::new(16.0, 8.5)])
+        );
+
+        let transform = TscnHelper::parse_command("transform = Transform2D(1, 0, 0, 1, 5, -6)", "");
+        assert_eq!(
+            transform.unwrap().rhs,
+            VarType::Transform2D(Transform2D::from_reals([1.0, 0.0, 0.0, 1.0, 5.0, -6.0]))
+        );
+
+        let basis = TscnHelper::parse_command("basis = Basis(0, 0, 1, 0, 1, 0, -1, 0, 0)", "");
+        assert_eq!(
+            basis.unwrap().rhs,
+            VarType::Basis(Matrix3::new(0.0, 0.0, 1.0, 0.0, 1.0, 0.0, -1.0, 0.0, 0.0))
+        );
+
+        let transform = TscnHelper::parse_command(
+            "transform = Transform3D(1, 0, 0, 0, 1, 0, 0, 0, 1, 2, 3, 4)",
+            "",
+        );
+        assert_eq!(
+            transform.unwrap().rhs,
+            VarType::Transform3D(Transform3D::from_basis(
+                Matrix3::identity(),
+                nalgebra::Vector3::new(2.0, 3.0, 4.0)
+            ))
+        );
+    }
 }
@@ -0,0 +1,449 @@
+use crate::str_helper::StrHelper;
+use crate::types::{Curve, Transform2DTrait, Transform3DTrait, VarType};
+use crate::{
+    Connection, ExtResourceEntry, NodeEntry, PropertyMap, PropertyTrait, Tscn, TEXT_FORMAT,
+};
+
+use std::collections::HashMap;
+
+pub struct TscnWriter();
+
+impl TscnWriter {
+    /// Serializes scene (or resource) into `.tscn` (`.tres`) text
+    pub fn write(tscn: &Tscn) -> String {
+        let format = tscn.format;
+        let mut sections: Vec<String> = vec![Self::write_header(tscn)];
+
+        for (id, ext) in &tscn.ext_resource_entries {
+            sections.push(Self::write_ext_resource(*id, ext, format));
+        }
+
+        for (id, sub) in &tscn.sub_resources {
+            let header = format!(
+                "[sub_resource type=\"{}\" id={}]",
+                sub.rtype,
+                Self::write_id(*id, format)
+            );
+            sections.push(Self::write_section(header, &sub.properties, format));
+        }
+
+        if tscn.rtype == "Scene" {
+            for node in tscn.nodes.values() {
+                let header =
+                    Self::write_node_header(node, tscn.relative_path(node.parent_id), format);
+                sections.push(Self::write_section(header, &node.properties, format));
+            }
+        } else {
+            sections.push(Self::write_section(
+                "[resource]".to_string(),
+                &tscn.resource,
+                format,
+            ));
+        }
+
+        for connection in &tscn.connections {
+            sections.push(Self::write_connection(connection));
+        }
+
+        for path in &tscn.editable_instances {
+            sections.push(format!("[editable path=\"{}\"]", path));
+        }
+
+        format!("{}\n", sections.join("\n\n"))
+    }
+
+    /// Writes value the way it appears on the right hand side of a property
+    pub fn write_value(value: &VarType) -> String {
+        Self::write_value_for(value, TEXT_FORMAT)
+    }
+
+    /// Writes value in the spelling of the given text format
+    pub fn write_value_for(value: &VarType, format: usize) -> String {
+        match value {
+            VarType::Num(num) => num.to_string(),
+            VarType::Bool(boolean) => boolean.to_string(),
+            VarType::Float(fl) => Self::write_float(*fl),
+            VarType::Str(string) => format!("\"{}\"", string.escape()),
+            VarType::Curve(curve) => Self::write_curve(curve, format),
+            VarType::Rect2(rect) => Self::write_constructor(
+                "Rect2",
+                &[rect[0].x, rect[0].y, rect[1].x, rect[1].y],
+                format,
+            ),
+            VarType::Transform2D(transform) => {
+                Self::write_constructor("Transform2D", &transform.to_reals(), format)
+            }
+            VarType::Basis(basis) => {
+                Self::write_constructor("Basis", basis.transpose().as_slice(), format)
+            }
+            VarType::Transform3D(transform) => {
+                let name = if format >= 3 {
+                    "Transform3D"
+                } else {
+                    "Transform"
+                };
+                Self::write_constructor(name, &transform.to_reals(), format)
+            }
+            VarType::IntArr(arr) => Self::write_call(
+                "PoolIntArray",
+                arr.iter().map(|n| n.to_string()).collect(),
+                format,
+            ),
+            VarType::StrArr(arr) => Self::write_call(
+                "PoolStringArray",
+                arr.iter().map(|s| format!("\"{}\"", s.escape())).collect(),
+                format,
+            ),
+            VarType::FloatArr(arr) => Self::write_constructor("PoolRealArray", arr, format),
+            VarType::Vector(vector) => {
+                Self::write_constructor("Vector2", &[vector.x, vector.y], format)
+            }
+            VarType::VectorArr(arr) => {
+                let reals: Vec<f32> = arr.iter().flat_map(|v| vec![v.x, v.y]).collect();
+                Self::write_constructor("PoolVector2Array", &reals, format)
+            }
+            VarType::ArrMap(arr_map) => format!(
+                "[ {} ]",
+                arr_map
+                    .iter()
+                    .map(|map| Self::write_inline_map(map, format))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+            VarType::Map(map) | VarType::Dict(map) => Self::write_inline_map(map, format),
+            VarType::SubResource(id) => {
+                Self::write_call("SubResource", vec![Self::write_id(*id, format)], format)
+            }
+            VarType::ExtResource(id) => {
+                Self::write_call("ExtResource", vec![Self::write_id(*id, format)], format)
+            }
+            VarType::Arr(arr) => format!(
+                "[ {} ]",
+                arr.iter()
+                    .map(|value| Self::write_value_for(value, format))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+            VarType::None(raw) => raw.clone(),
+        }
+    }
+
+    /// Writes float property, whole numbers keep `.0` so they are not read back as integers
+    pub fn write_float(fl: f32) -> String {
+        if fl.is_finite() && fl.fract() == 0.0 {
+            format!("{:.1}", fl)
+        } else {
+            fl.to_string()
+        }
+    }
+
+    /// Godot 4 resource ids are strings
+    fn write_id(id: usize, format: usize) -> String {
+        if format >= 3 {
+            format!("\"{}\"", id)
+        } else {
+            id.to_string()
+        }
+    }
+
+    fn write_constructor(name: &str, reals: &[f32], format: usize) -> String {
+        Self::write_call(name, reals.iter().map(|r| r.to_string()).collect(), format)
+    }
+
+    /// Godot 3 pads the arguments with spaces, Godot 4 doesn't
+    fn write_call(name: &str, args: Vec<String>, format: usize) -> String {
+        if format >= 3 {
+            format!("{}({})", name, args.join(", "))
+        } else {
+            format!("{}( {} )", name, args.join(", "))
+        }
+    }
+
+    fn write_curve(curve: &Curve, format: usize) -> String {
+        let data: Vec<String> = curve
+            .get_points()
+            .iter()
+            .map(|point| {
+                format!(
+                    "{}, {}, {}, {}, {}",
+                    Self::write_constructor("Vector2", &[point.pos.x, point.pos.y], format),
+                    Self::write_float(point.left_tangent),
+                    Self::write_float(point.right_tangent),
+                    point.left_mode as isize,
+                    point.right_mode as isize
+                )
+            })
+            .collect();
+
+        format!("[ {} ]", data.join(", "))
+    }
+
+    fn write_inline_map(map: &HashMap<String, VarType>, format: usize) -> String {
+        format!("{{ {} }}", Self::write_map_entries(map, format).join(", "))
+    }
+
+    fn write_map_entries(map: &HashMap<String, VarType>, format: usize) -> Vec<String> {
+        let mut keys: Vec<&String> = map.keys().collect();
+        keys.sort();
+
+        keys.into_iter()
+            .map(|key| format!("\"{}\": {}", key, Self::write_value_for(&map[key], format)))
+            .collect()
+    }
+
+    fn write_header(tscn: &Tscn) -> String {
+        let load_steps = tscn.ext_resource_entries.len() + tscn.sub_resources.len() + 1;
+        let load_steps = if load_steps > 1 {
+            format!(" load_steps={}", load_steps)
+        } else {
+            "".to_string()
+        };
+
+        if tscn.rtype == "Scene" {
+            format!("[gd_scene{} format={}]", load_steps, tscn.format)
+        } else {
+            format!(
+                "[gd_resource type=\"{}\"{} format={}]",
+                tscn.rtype, load_steps, tscn.format
+            )
+        }
+    }
+
+    fn write_ext_resource(id: usize, ext: &ExtResourceEntry, format: usize) -> String {
+        let uid = match ext.uid {
+            Some(uid) => format!(" uid=\"{}\"", uid),
+            None => "".to_string(),
+        };
+
+        // Godot 4 leads with the type
+        if format >= 3 {
+            format!(
+                "[ext_resource type=\"{}\"{} path=\"{}\" id={}]",
+                ext.rtype,
+                uid,
+                ext.path,
+                Self::write_id(id, format)
+            )
+        } else {
+            format!(
+                "[ext_resource path=\"{}\" type=\"{}\"{} id={}]",
+                ext.path, ext.rtype, uid, id
+            )
+        }
+    }
+
+    fn write_node_header(node: &NodeEntry, parent_path: Option<String>, format: usize) -> String {
+        let mut header = format!("[node name=\"{}\"", node.name);
+
+        if !node.rtype.is_empty() {
+            header.push_str(&format!(" type=\"{}\"", node.rtype));
+        }
+
+        if let Some(parent_path) = parent_path.filter(|_| node.level > 0) {
+            header.push_str(&format!(" parent=\"{}\"", parent_path));
+        }
+
+        if node.instance != 0 {
+            header.push_str(&format!(
+                " instance={}",
+                Self::write_value_for(&VarType::ExtResource(node.instance), format)
+            ));
+        }
+
+        if !node.groups.is_empty() {
+            let groups: Vec<String> = node
+                .groups
+                .iter()
+                .map(|group| format!("\"{}\"", group.escape()))
+                .collect();
+            header.push_str(&format!(" groups=[ {} ]", groups.join(", ")));
+        }
+
+        format!("{}]", header)
+    }
+
+    fn write_connection(connection: &Connection) -> String {
+        let flags = if connection.flags != 0 {
+            format!(" flags={}", connection.flags)
+        } else {
+            "".to_string()
+        };
+
+        format!(
+            "[connection signal=\"{}\" from=\"{}\" to=\"{}\" method=\"{}\"{}]",
+            connection.signal, connection.from, connection.to, connection.method, flags
+        )
+    }
+
+    fn write_section(header: String, properties: &PropertyMap, format: usize) -> String {
+        let mut lines = vec![header];
+        for (key, value) in properties.flatten() {
+            lines.push(Self::write_property(&key, value, format));
+        }
+
+        lines.join("\n")
+    }
+
+    fn write_property(key: &str, value: &VarType, format: usize) -> String {
+        match value {
+            VarType::Dict(map) => format!(
+                "{} = {{\n{}\n}}",
+                key,
+                Self::write_map_entries(map, format).join(",\n")
+            ),
+            VarType::ArrMap(arr_map) => format!(
+                "{} = [ {{\n{}\n}} ]",
+                key,
+                arr_map
+                    .iter()
+                    .map(|map| Self::write_map_entries(map, format).join(",\n"))
+                    .collect::<Vec<String>>()
+                    .join("\n}, {\n")
+            ),
+            _ => format!("{} = {}", key, Self::write_value_for(value, format)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tscn_writer::*;
+    use crate::types::Transform3D;
+    use crate::Loader;
+
+    const SCENE: &str = r#"[gd_scene load_steps=4 format=2]
+
+[ext_resource path="res://icon.png" type="Texture" id=1]
+
+[ext_resource path="res://Enemy.tscn" type="PackedScene" id=2]
+
+[sub_resource type="RectangleShape2D" id=1]
+extents = Vector2( 8, 8 )
+
+[node name="Level" type="Node2D"]
+
+[node name="Player" type="KinematicBody2D" parent="." groups=[ "players", "saved" ]]
+collision/layer = 3
+position = Vector2( 32, 48.5 )
+speed = 120.0
+
+[node name="Sprite" type="Sprite" parent="Player"]
+region_rect = Rect2( 0, 0, 16, 16 )
+texture = ExtResource( 1 )
+
+[node name="Shape" type="CollisionShape2D" parent="Player"]
+shape = SubResource( 1 )
+
+[node name="Label" type="Label" parent="."]
+__meta__ = {
+"_edit_lock_": true
+}
+text = "Hello \"C:\\Users\""
+
+[node name="Enemy" parent="." instance=ExtResource( 2 )]
+
+[connection signal="body_entered" from="Player" to="." method="_on_body_entered"]
+
+[editable path="Enemy"]
+"#;
+
+    #[test]
+    fn test_write_scene() {
+        let tscn = Loader::new().parse_tscn(SCENE);
+
+        assert_eq!(tscn.connections.len(), 1);
+        assert_eq!(tscn.nodes.len(), 6);
+        assert_eq!(tscn.editable_instances, vec!["Enemy"]);
+        assert_eq!(tscn.nodes[&1].groups, vec!["players", "saved"]);
+        assert_eq!(
+            tscn.nodes[&4].properties["text"],
+            VarType::Str("Hello \"C:\\Users\"".to_string())
+        );
+        assert_eq!(TscnWriter::write(&tscn), SCENE);
+        assert_eq!(Loader::new().parse_tscn(&TscnWriter::write(&tscn)), tscn);
+    }
+
+    /// Saved by Godot 4, the writer doesn't keep the scene uid and numbers the resource ids
+    const SCENE_GODOT4: &str = r#"[gd_scene load_steps=3 format=3 uid="uid://b6x0qmsiqa1qj"]
+
+[ext_resource type="Texture2D" uid="uid://cecaux1sm7mo0" path="res://icon.svg" id="1_x5k2p"]
+
+[sub_resource type="RectangleShape2D" id="RectangleShape2D_abc12"]
+size = Vector2(16, 8)
+
+[node name="Level" type="Node2D"]
+
+[node name="Sprite" type="Sprite2D" parent="."]
+position = Vector2(8, 4)
+texture = ExtResource("1_x5k2p")
+
+[node name="Shape" type="CollisionShape2D" parent="."]
+shape = SubResource("RectangleShape2D_abc12")
+"#;
+
+    const WRITTEN_GODOT4: &str = r#"[gd_scene load_steps=3 format=3]
+
+[ext_resource type="Texture2D" uid="uid://cecaux1sm7mo0" path="res://icon.svg" id="1"]
+
+[sub_resource type="RectangleShape2D" id="1"]
+size = Vector2(16, 8)
+
+[node name="Level" type="Node2D"]
+
+[node name="Sprite" type="Sprite2D" parent="."]
+position = Vector2(8, 4)
+texture = ExtResource("1")
+
+[node name="Shape" type="CollisionShape2D" parent="."]
+shape = SubResource("1")
+"#;
+
+    #[test]
+    fn test_write_godot4_scene() {
+        let tscn = Loader::new().parse_tscn(SCENE_GODOT4);
+
+        assert_eq!(tscn.format, 3);
+        assert_eq!(TscnWriter::write(&tscn), WRITTEN_GODOT4);
+
+        let written = Loader::new().parse_tscn(WRITTEN_GODOT4);
+        assert_eq!(written.nodes, tscn.nodes);
+        assert_eq!(written.sub_resources, tscn.sub_resources);
+    }
+
+    #[test]
+    fn test_write_string_array() {
+        let scene = "[gd_scene format=2]\n\n\
+                     [node name=\"Menu\" type=\"ItemList\"]\n\
+                     items = PoolStringArray( \"a\\\"b\", \"c\\\\d\" )\n";
+        let tscn = Loader::new().parse_tscn(scene);
+        let items = VarType::StrArr(vec!["a\"b".to_string(), "c\\d".to_string()]);
+        assert_eq!(tscn.nodes[&0].properties["items"], items);
+
+        let written = TscnWriter::write(&tscn);
+        assert_eq!(written, scene);
+        assert_eq!(
+            Loader::new().parse_tscn(&written).nodes[&0].properties["items"],
+            items
+        );
+    }
+
+    #[test]
+    fn test_write_value() {
+        assert_eq!(TscnWriter::write_value(&VarType::Float(1.0)), "1.0");
+        assert_eq!(TscnWriter::write_value(&VarType::Float(0.25)), "0.25");
+        assert_eq!(
+            TscnWriter::write_value(&VarType::IntArr(vec![1, -2])),
+            "PoolIntArray( 1, -2 )"
+        );
+        assert_eq!(
+            TscnWriter::write_value(&VarType::ExtResource(2)),
+            "ExtResource( 2 )"
+        );
+
+        let transform = VarType::Transform3D(Transform3D::identity());
+        assert_eq!(
+            TscnWriter::write_value_for(&transform, 3),
+            "Transform3D(1, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0)"
+        );
+    }
+}
@@ -0,0 +1,768 @@
+use crate::types::{Transform2DTrait, VarType};
+use crate::{PropertyMap, PropertyTrait};
+
+use nalgebra::{Quaternion, UnitQuaternion, Vector2, Vector3};
+use std::convert::TryFrom;
+
+/// Iterations of bezier track time lookup
+const BEZIER_ITERATIONS: usize = 15;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum InterpolationType {
+    Nearest,
+    Linear,
+    Cubic,
+}
+
+impl TryFrom<isize> for InterpolationType {
+    type Error = ();
+
+    fn try_from(value: isize) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(InterpolationType::Nearest),
+            1 => Ok(InterpolationType::Linear),
+            2 => Ok(InterpolationType::Cubic),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum UpdateMode {
+    Continuous,
+    Discrete,
+    Trigger,
+    Capture,
+}
+
+impl TryFrom<isize> for UpdateMode {
+    type Error = ();
+
+    fn try_from(value: isize) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(UpdateMode::Continuous),
+            1 => Ok(UpdateMode::Discrete),
+            2 => Ok(UpdateMode::Trigger),
+            3 => Ok(UpdateMode::Capture),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TransformKey {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helper::vector3"))]
+    pub location: Vector3<f32>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::serde_helper::unit_quaternion")
+    )]
+    pub rotation: UnitQuaternion<f32>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helper::vector3"))]
+    pub scale: Vector3<f32>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MethodKey {
+    pub method: String,
+    pub args: Vec<VarType>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BezierKey {
+    pub value: f32,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helper::vector2"))]
+    pub in_handle: Vector2<f32>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helper::vector2"))]
+    pub out_handle: Vector2<f32>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AudioClip {
+    /// Audio stream resource reference (i.e. `ExtResource( 2 )`)
+    pub stream: VarType,
+    pub start_offset: f32,
+    pub end_offset: f32,
+}
+
+/// Keys of a track, one per `Track::times` entry
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TrackKeys {
+    Value {
+        update: UpdateMode,
+        values: Vec<VarType>,
+    },
+    Transform(Vec<TransformKey>),
+    Method(Vec<MethodKey>),
+    Bezier(Vec<BezierKey>),
+    Audio(Vec<AudioClip>),
+    Animation(Vec<String>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Track {
+    /// Node path with optional property (i.e. `Sprite:frame`)
+    pub path: String,
+    pub interp: InterpolationType,
+    pub loop_wrap: bool,
+    pub enabled: bool,
+    pub times: Vec<f32>,
+    pub transitions: Vec<f32>,
+    pub keys: TrackKeys,
+}
+
+/// Value of a track at some point of time
+#[derive(Debug, Clone, PartialEq)]
+pub enum TrackSample<'a> {
+    Value(VarType),
+    Transform(TransformKey),
+    Bezier(f32),
+    /// Last called method
+    Method(&'a MethodKey),
+    /// Playing clip and playback position within its stream
+    Audio(&'a AudioClip, f32),
+    /// Last started animation
+    Animation(&'a str),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Animation {
+    pub name: String,
+    pub length: f32,
+    pub looping: bool,
+    pub step: f32,
+    pub tracks: Vec<Track>,
+}
+
+impl Animation {
+    /// Builds animation from resource properties (i.e. `length`, `tracks/0/keys`)
+    pub fn from_properties(properties: &PropertyMap) -> Result<Self, String> {
+        let mut ids: Vec<(usize, &PropertyMap)> = match properties.get("tracks") {
+            Some(VarType::Map(tracks)) => tracks
+                .iter()
+                .filter_map(|(id, track)| match track {
+                    VarType::Map(track) => Some((id.parse().ok()?, track)),
+                    _ => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+        ids.sort_by_key(|(id, _)| *id);
+
+        let tracks = ids
+            .into_iter()
+            .map(|(id, track)| {
+                Track::from_properties(track).map_err(|e| format!("Track {}: {}", id, e))
+            })
+            .collect::<Result<Vec<Track>, String>>()?;
+
+        Ok(Animation {
+            name: properties.get_as("resource_name").unwrap_or_default(),
+            length: properties.get_as("length").unwrap_or(1.0),
+            looping: properties.get_as("loop").unwrap_or(false),
+            step: properties.get_as("step").unwrap_or(0.1),
+            tracks,
+        })
+    }
+
+    pub fn find_track(&self, path: &str) -> Option<usize> {
+        self.tracks.iter().position(|track| track.path == path)
+    }
+
+    /// Samples track at `time`, `None` for disabled tracks or when no key applies yet
+    pub fn sample(&self, track: usize, time: f32) -> Option<TrackSample<'_>> {
+        let track = self.tracks.get(track).filter(|track| track.enabled)?;
+        let time = if self.looping && self.length > 0.0 {
+            time.rem_euclid(self.length)
+        } else {
+            time
+        };
+
+        match &track.keys {
+            TrackKeys::Value {
+                update: UpdateMode::Discrete,
+                values,
+            }
+            | TrackKeys::Value {
+                update: UpdateMode::Trigger,
+                values,
+            } => Some(TrackSample::Value(values[track.key_at(time)?].clone())),
+            TrackKeys::Value { values, .. } => {
+                let (prev, next, c) = self.locate(track, time)?;
+                let value = match track.interp {
+                    InterpolationType::Nearest => values[prev].clone(),
+                    InterpolationType::Linear => interpolate(&values[prev], &values[next], c),
+                    InterpolationType::Cubic => {
+                        let (pre, post) = track.neighbours(prev, next);
+                        cubic_interpolate(
+                            &values[pre],
+                            &values[prev],
+                            &values[next],
+                            &values[post],
+                            c,
+                        )
+                    }
+                };
+
+                Some(TrackSample::Value(value))
+            }
+            TrackKeys::Transform(keys) => {
+                let (prev, next, c) = self.locate(track, time)?;
+                let (a, b) = (&keys[prev], &keys[next]);
+                if track.interp == InterpolationType::Nearest {
+                    return Some(TrackSample::Transform(a.clone()));
+                }
+
+                Some(TrackSample::Transform(TransformKey {
+                    location: a.location.lerp(&b.location, c),
+                    rotation: a
+                        .rotation
+                        .try_slerp(&b.rotation, c, f32::EPSILON)
+                        .unwrap_or(if c < 0.5 { a.rotation } else { b.rotation }),
+                    scale: a.scale.lerp(&b.scale, c),
+                }))
+            }
+            TrackKeys::Bezier(keys) => Some(TrackSample::Bezier(track.sample_bezier(keys, time)?)),
+            TrackKeys::Method(keys) => Some(TrackSample::Method(&keys[track.key_at(time)?])),
+            TrackKeys::Audio(clips) => {
+                let key = track.key_at(time)?;
+                let clip = &clips[key];
+
+                Some(TrackSample::Audio(
+                    clip,
+                    time - track.times[key] + clip.start_offset,
+                ))
+            }
+            TrackKeys::Animation(names) => {
+                Some(TrackSample::Animation(&names[track.key_at(time)?]))
+            }
+        }
+    }
+
+    /// Finds keys around `time` and eased weight between them, wrapping around for looped tracks
+    fn locate(&self, track: &Track, time: f32) -> Option<(usize, usize, f32)> {
+        let times = &track.times;
+        let last = times.len().checked_sub(1)?;
+        let wrap = self.looping && track.loop_wrap;
+
+        let (prev, next, c) = match times.iter().rposition(|t| *t <= time) {
+            Some(prev) if prev < last => {
+                let delta = times[prev + 1] - times[prev];
+                (prev, prev + 1, ratio(time - times[prev], delta))
+            }
+            Some(prev) if wrap => {
+                let delta = self.length - times[prev] + times[0];
+                (prev, 0, ratio(time - times[prev], delta))
+            }
+            Some(prev) => (prev, prev, 0.0),
+            None if wrap => {
+                let delta = self.length - times[last] + times[0];
+                (last, 0, ratio(self.length - times[last] + time, delta))
+            }
+            None => (0, 0, 0.0),
+        };
+
+        let transition = track.transitions.get(prev).copied().unwrap_or(1.0);
+        if prev == next || transition == 0.0 {
+            return Some((prev, prev, 0.0));
+        }
+
+        if transition != 1.0 {
+            Some((prev, next, ease(c, transition)))
+        } else {
+            Some((prev, next, c))
+        }
+    }
+}
+
+impl Track {
+    pub fn from_properties(properties: &PropertyMap) -> Result<Self, String> {
+        let rtype: String = properties
+            .get_as("type")
+            .unwrap_or_else(|| "value".to_string());
+        let keys = properties.get_from("keys");
+        let dict = match keys {
+            Some(VarType::Dict(dict)) => Some(dict),
+            _ => None,
+        };
+
+        let (times, keys) = match rtype.as_str() {
+            "transform" => {
+                let reals = reals(keys);
+                let keys: Vec<TransformKey> = reals
+                    .chunks_exact(12)
+                    .map(|key| TransformKey {
+                        location: Vector3::new(key[2], key[3], key[4]),
+                        rotation: UnitQuaternion::from_quaternion(Quaternion::new(
+                            key[8], key[5], key[6], key[7],
+                        )),
+                        scale: Vector3::new(key[9], key[10], key[11]),
+                    })
+                    .collect();
+                let times: Vec<f32> = reals.chunks_exact(12).map(|key| key[0]).collect();
+                let transitions = reals.chunks_exact(12).map(|key| key[1]).collect();
+
+                return Self::new(properties, times, transitions, TrackKeys::Transform(keys));
+            }
+            "value" => {
+                let dict = dict.ok_or("Missing keys")?;
+                let values = match dict.get("values") {
+                    Some(VarType::Arr(values)) => values.clone(),
+                    Some(VarType::ArrMap(maps)) => {
+                        maps.iter().cloned().map(VarType::Dict).collect()
+                    }
+                    _ => Vec::new(),
+                };
+                let update = dict
+                    .get_as::<isize>("update")
+                    .and_then(|mode| UpdateMode::try_from(mode).ok())
+                    .unwrap_or(UpdateMode::Continuous);
+
+                (
+                    reals(dict.get("times")),
+                    TrackKeys::Value { update, values },
+                )
+            }
+            "method" => {
+                let dict = dict.ok_or("Missing keys")?;
+                let calls = match dict.get("values") {
+                    Some(VarType::ArrMap(maps)) => maps
+                        .iter()
+                        .map(|call| MethodKey {
+                            method: call.get_as("method").unwrap_or_default(),
+                            args: match call.get("args") {
+                                Some(VarType::Arr(args)) => args.clone(),
+                                _ => Vec::new(),
+                            },
+                        })
+                        .collect(),
+                    _ => Vec::new(),
+                };
+
+                (reals(dict.get("times")), TrackKeys::Method(calls))
+            }
+            "bezier" => {
+                let dict = dict.ok_or("Missing keys")?;
+                let keys = reals(dict.get("points"))
+                    .chunks_exact(5)
+                    .map(|point| BezierKey {
+                        value: point[0],
+                        in_handle: Vector2::new(point[1], point[2]),
+                        out_handle: Vector2::new(point[3], point[4]),
+                    })
+                    .collect();
+
+                (reals(dict.get("times")), TrackKeys::Bezier(keys))
+            }
+            "audio" => {
+                let dict = dict.ok_or("Missing keys")?;
+                let clips = match dict.get("clips") {
+                    Some(VarType::ArrMap(maps)) => maps
+                        .iter()
+                        .map(|clip| AudioClip {
+                            stream: clip
+                                .get("stream")
+                                .cloned()
+                                .unwrap_or(VarType::None("null".to_string())),
+                            start_offset: clip.get_as("start_offset").unwrap_or(0.0),
+                            end_offset: clip.get_as("end_offset").unwrap_or(0.0),
+                        })
+                        .collect(),
+                    _ => Vec::new(),
+                };
+
+                (reals(dict.get("times")), TrackKeys::Audio(clips))
+            }
+            "animation" => {
+                let dict = dict.ok_or("Missing keys")?;
+                let names = match dict.get("clips") {
+                    Some(VarType::StrArr(names)) => names.clone(),
+                    _ => Vec::new(),
+                };
+
+                (reals(dict.get("times")), TrackKeys::Animation(names))
+            }
+            _ => return Err(format!("Unsupported track type `{}`", rtype)),
+        };
+
+        let transitions = match dict.and_then(|dict| dict.get("transitions")) {
+            Some(transitions) => reals(Some(transitions)),
+            None => vec![1.0; times.len()],
+        };
+
+        Self::new(properties, times, transitions, keys)
+    }
+
+    fn new(
+        properties: &PropertyMap,
+        times: Vec<f32>,
+        transitions: Vec<f32>,
+        keys: TrackKeys,
+    ) -> Result<Self, String> {
+        let count = match &keys {
+            TrackKeys::Value { values, .. } => values.len(),
+            TrackKeys::Transform(keys) => keys.len(),
+            TrackKeys::Method(keys) => keys.len(),
+            TrackKeys::Bezier(keys) => keys.len(),
+            TrackKeys::Audio(clips) => clips.len(),
+            TrackKeys::Animation(names) => names.len(),
+        };
+        if count != times.len() {
+            return Err(format!("{} keys for {} times", count, times.len()));
+        }
+
+        Ok(Track {
+            path: node_path(properties.get_from("path")),
+            interp: properties
+                .get_as::<isize>("interp")
+                .and_then(|interp| InterpolationType::try_from(interp).ok())
+                .unwrap_or(InterpolationType::Linear),
+            loop_wrap: properties.get_as("loop_wrap").unwrap_or(true),
+            enabled: properties.get_as("enabled").unwrap_or(true),
+            times,
+            transitions,
+            keys,
+        })
+    }
+
+    /// Index of the last key at or before `time`
+    pub fn key_at(&self, time: f32) -> Option<usize> {
+        self.times.iter().rposition(|t| *t <= time)
+    }
+
+    fn neighbours(&self, prev: usize, next: usize) -> (usize, usize) {
+        let pre = if prev > 0 && prev < next {
+            prev - 1
+        } else {
+            prev
+        };
+        let post = if next + 1 < self.times.len() && next > prev {
+            next + 1
+        } else {
+            next
+        };
+
+        (pre, post)
+    }
+
+    fn sample_bezier(&self, keys: &[BezierKey], time: f32) -> Option<f32> {
+        let last = keys.len().checked_sub(1)?;
+        let prev = match self.key_at(time) {
+            Some(prev) if prev < last => prev,
+            Some(_) => return Some(keys[last].value),
+            None => return Some(keys[0].value),
+        };
+
+        let duration = self.times[prev + 1] - self.times[prev];
+        let t = time - self.times[prev];
+        let start = Vector2::new(0.0, keys[prev].value);
+        let start_out = start + keys[prev].out_handle;
+        let end = Vector2::new(duration, keys[prev + 1].value);
+        let end_in = end + keys[prev + 1].in_handle;
+
+        let (mut low, mut high) = (0.0, 1.0);
+        for _ in 0..BEZIER_ITERATIONS {
+            let middle = (low + high) / 2.0;
+            if bezier(middle, start, start_out, end_in, end).x < t {
+                low = middle;
+            } else {
+                high = middle;
+            }
+        }
+
+        let low_pos = bezier(low, start, start_out, end_in, end);
+        let high_pos = bezier(high, start, start_out, end_in, end);
+        let c = ratio(t - low_pos.x, high_pos.x - low_pos.x);
+
+        Some(low_pos.lerp(&high_pos, c).y)
+    }
+}
+
+resource_try_from!(Animation, "Animation", Animation::from_properties);
+
+/// Godot's easing curve, `curve` is the key transition (`1` linear, `< 0` in-out)
+pub fn ease(x: f32, curve: f32) -> f32 {
+    let x = x.clamp(0.0, 1.0);
+
+    if curve > 0.0 {
+        if curve < 1.0 {
+            1.0 - (1.0 - x).powf(1.0 / curve)
+        } else {
+            x.powf(curve)
+        }
+    } else if curve < 0.0 {
+        if x < 0.5 {
+            (x * 2.0).powf(-curve) * 0.5
+        } else {
+            (1.0 - (1.0 - (x - 0.5) * 2.0).powf(-curve)) * 0.5 + 0.5
+        }
+    } else {
+        0.0
+    }
+}
+
+/// Interpolates values the way Godot's `Variant::interpolate` does, values that can not be
+/// interpolated switch half way through
+pub fn interpolate(a: &VarType, b: &VarType, c: f32) -> VarType {
+    match (a, b) {
+        (VarType::Num(a), VarType::Num(b)) => VarType::Num(a + ((b - a) as f32 * c) as isize),
+        (VarType::Float(a), VarType::Float(b)) => VarType::Float(a + (b - a) * c),
+        (VarType::Vector(a), VarType::Vector(b)) => VarType::Vector(a.lerp(b, c)),
+        (VarType::Rect2(a), VarType::Rect2(b)) => {
+            VarType::Rect2([a[0].lerp(&b[0], c), a[1].lerp(&b[1], c)])
+        }
+        (VarType::Transform2D(a), VarType::Transform2D(b)) => {
+            VarType::Transform2D(a.interpolate_with(b, c))
+        }
+        _ if c < 0.5 => a.clone(),
+        _ => b.clone(),
+    }
+}
+
+/// Cubic interpolation between `a` and `b`, falls back to linear for non numeric values
+pub fn cubic_interpolate(
+    pre: &VarType,
+    a: &VarType,
+    b: &VarType,
+    post: &VarType,
+    c: f32,
+) -> VarType {
+    match (pre, a, b, post) {
+        (VarType::Float(p0), VarType::Float(p1), VarType::Float(p2), VarType::Float(p3)) => {
+            VarType::Float(cubic(*p0, *p1, *p2, *p3, c))
+        }
+        (VarType::Vector(p0), VarType::Vector(p1), VarType::Vector(p2), VarType::Vector(p3)) => {
+            VarType::Vector(Vector2::new(
+                cubic(p0.x, p1.x, p2.x, p3.x, c),
+                cubic(p0.y, p1.y, p2.y, p3.y, c),
+            ))
+        }
+        _ => interpolate(a, b, c),
+    }
+}
+
+fn cubic(p0: f32, p1: f32, p2: f32, p3: f32, t: f32) -> f32 {
+    let (t2, t3) = (t * t, t * t * t);
+
+    0.5 * ((p1 * 2.0)
+        + (-p0 + p2) * t
+        + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t2
+        + (-p0 + 3.0 * p1 - 3.0 * p2 + p3) * t3)
+}
+
+fn bezier(
+    t: f32,
+    start: Vector2<f32>,
+    control_1: Vector2<f32>,
+    control_2: Vector2<f32>,
+    end: Vector2<f32>,
+) -> Vector2<f32> {
+    let omt = 1.0 - t;
+
+    start * omt.powi(3)
+        + control_1 * (3.0 * omt * omt * t)
+        + control_2 * (3.0 * omt * t * t)
+        + end * t.powi(3)
+}
+
+fn ratio(value: f32, delta: f32) -> f32 {
+    if delta > 0.0 {
+        value / delta
+    } else {
+        0.0
+    }
+}
+
+fn reals(value: Option<&VarType>) -> Vec<f32> {
+    match value {
+        Some(VarType::FloatArr(reals)) => reals.clone(),
+        Some(VarType::Arr(values)) => values
+            .iter()
+            .filter_map(|v| f32::try_from(v).ok())
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Reads `NodePath("Sprite:frame")` (or plain string) path
+fn node_path(value: Option<&VarType>) -> String {
+    match value {
+        Some(VarType::Str(path)) => path.clone(),
+        Some(VarType::None(raw)) => raw
+            .trim_start_matches("NodePath(")
+            .trim_end_matches(')')
+            .trim()
+            .trim_matches('"')
+            .to_string(),
+        _ => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::types::animation::*;
+    use crate::Loader;
+
+    const ANIMATION: &str = r#"[gd_resource type="Animation" load_steps=2 format=2]
+
+[ext_resource path="res://step.wav" type="AudioStream" id=1]
+
+[resource]
+resource_name = "walk"
+length = 2.0
+loop = true
+tracks/0/type = "value"
+tracks/0/path = NodePath("Sprite:position")
+tracks/0/interp = 1
+tracks/0/loop_wrap = true
+tracks/0/imported = false
+tracks/0/enabled = true
+tracks/0/keys = {
+"times": PoolRealArray( 0, 1 ),
+"transitions": PoolRealArray( 1, 2 ),
+"update": 0,
+"values": [ Vector2( 0, 0 ), Vector2( 10, 20 ) ]
+}
+tracks/1/type = "value"
+tracks/1/path = NodePath("Sprite:frame")
+tracks/1/interp = 1
+tracks/1/loop_wrap = true
+tracks/1/imported = false
+tracks/1/enabled = true
+tracks/1/keys = {
+"times": PoolRealArray( 0, 0.5 ),
+"transitions": PoolRealArray( 1, 1 ),
+"update": 1,
+"values": [ 0, 1 ]
+}
+tracks/2/type = "method"
+tracks/2/path = NodePath(".")
+tracks/2/interp = 1
+tracks/2/loop_wrap = true
+tracks/2/imported = false
+tracks/2/enabled = true
+tracks/2/keys = {
+"times": PoolRealArray( 0.5 ),
+"transitions": PoolRealArray( 1 ),
+"values": [ {
+"args": [ "left", 1 ],
+"method": "play_step"
+} ]
+}
+tracks/3/type = "bezier"
+tracks/3/path = NodePath("Sprite:modulate:a")
+tracks/3/interp = 1
+tracks/3/loop_wrap = true
+tracks/3/imported = false
+tracks/3/enabled = true
+tracks/3/keys = {
+"points": PoolRealArray( 0, -0.25, 0, 0.25, 0, 1, -0.25, 0, 0.25, 0 ),
+"times": PoolRealArray( 0, 1 )
+}
+tracks/4/type = "audio"
+tracks/4/path = NodePath("Audio")
+tracks/4/interp = 1
+tracks/4/loop_wrap = true
+tracks/4/imported = false
+tracks/4/enabled = true
+tracks/4/keys = {
+"clips": [ {
+"end_offset": 0.0,
+"start_offset": 0.1,
+"stream": ExtResource( 1 )
+} ],
+"times": PoolRealArray( 0 )
+}
+"#;
+
+    #[test]
+    fn test_animation_sample() {
+        let tscn = Loader::new().parse_tscn(ANIMATION);
+        let animation = Animation::try_from(&tscn).unwrap();
+        assert_eq!(animation.name, "walk");
+        assert_eq!(animation.tracks.len(), 5);
+        assert_eq!(animation.find_track("Sprite:frame"), Some(1));
+
+        // Linear within the first segment, eased (transition 2) when wrapping back to the start
+        assert_eq!(
+            animation.sample(0, 0.5),
+            Some(TrackSample::Value(VarType::Vector(Vector2::new(5.0, 10.0))))
+        );
+        assert_eq!(
+            animation.sample(0, 1.5),
+            Some(TrackSample::Value(VarType::Vector(Vector2::new(7.5, 15.0))))
+        );
+        assert_eq!(
+            animation.sample(0, 2.5),
+            animation.sample(0, 0.5),
+            "looped animations wrap time"
+        );
+
+        assert_eq!(
+            animation.sample(1, 0.75),
+            Some(TrackSample::Value(VarType::Num(1)))
+        );
+        assert_eq!(animation.sample(2, 0.25), None);
+        match animation.sample(2, 1.0) {
+            Some(TrackSample::Method(call)) => {
+                assert_eq!(call.method, "play_step");
+                assert_eq!(
+                    call.args,
+                    vec![VarType::Str("left".to_string()), VarType::Num(1)]
+                );
+            }
+            sample => panic!("Unexpected sample {:?}", sample),
+        }
+
+        match animation.sample(3, 0.5) {
+            Some(TrackSample::Bezier(value)) => assert!((value - 0.5).abs() < 0.01),
+            sample => panic!("Unexpected sample {:?}", sample),
+        }
+        match animation.sample(4, 0.4) {
+            Some(TrackSample::Audio(clip, position)) => {
+                assert_eq!(clip.stream, VarType::ExtResource(1));
+                assert!((position - 0.5).abs() < 1e-6);
+            }
+            sample => panic!("Unexpected sample {:?}", sample),
+        }
+
+        assert!((ease(0.5, 2.0) - 0.25).abs() < 1e-6);
+        assert!((ease(0.25, -2.0) - 0.125).abs() < 1e-6);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_animation_serde() {
+        let animation = Animation::try_from(&Loader::new().parse_tscn(ANIMATION)).unwrap();
+        let encoded = serde_json::to_value(&animation).unwrap();
+        assert_eq!(
+            encoded["tracks"][3]["keys"]["Bezier"][0]["out_handle"],
+            serde_json::json!([0.25, 0.0])
+        );
+        assert_eq!(
+            serde_json::from_value::<Animation>(encoded).unwrap(),
+            animation
+        );
+
+        let key = TransformKey {
+            location: Vector3::new(1.0, 2.0, 3.0),
+            rotation: UnitQuaternion::from_quaternion(Quaternion::new(0.0, 0.0, 1.0, 0.0)),
+            scale: Vector3::repeat(1.0),
+        };
+        let encoded = serde_json::to_value(&key).unwrap();
+        assert_eq!(encoded["rotation"], serde_json::json!([0.0, 1.0, 0.0, 0.0]));
+        assert_eq!(
+            serde_json::from_value::<TransformKey>(encoded).unwrap(),
+            key
+        );
+    }
+}
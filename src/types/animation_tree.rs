@@ -0,0 +1,312 @@
+use crate::types::VarType;
+use crate::{PropertyMap, PropertyTrait, Tscn};
+
+use indexmap::IndexMap;
+use nalgebra::Vector2;
+use std::convert::TryFrom;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SwitchMode {
+    Immediate,
+    Sync,
+    AtEnd,
+}
+
+impl TryFrom<isize> for SwitchMode {
+    type Error = ();
+
+    fn try_from(value: isize) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(SwitchMode::Immediate),
+            1 => Ok(SwitchMode::Sync),
+            2 => Ok(SwitchMode::AtEnd),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transition {
+    pub from: String,
+    pub to: String,
+    pub switch_mode: SwitchMode,
+    pub auto_advance: bool,
+    pub advance_condition: Option<String>,
+    pub xfade_time: f32,
+    pub priority: isize,
+    pub disabled: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct State {
+    pub node: AnimationNode,
+    /// Position in the editor graph
+    pub position: Vector2<f32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct StateMachine {
+    /// States sorted by name
+    pub states: IndexMap<String, State>,
+    pub transitions: Vec<Transition>,
+    pub start_node: Option<String>,
+    pub end_node: Option<String>,
+}
+
+/// AnimationTree node (`AnimationNode*` resources)
+#[derive(Debug, Clone, PartialEq)]
+pub enum AnimationNode {
+    /// Plays animation of the given name
+    Animation(String),
+    StateMachine(StateMachine),
+    /// Blend spaces, blend trees and others with their nested nodes
+    Other {
+        rtype: String,
+        children: Vec<AnimationNode>,
+    },
+}
+
+impl AnimationNode {
+    /// Builds node from `SubResource` / `ExtResource` reference of `tscn`
+    pub fn from_reference(tscn: &Tscn, reference: &VarType) -> Result<Self, String> {
+        let (owner, rtype, properties) = tscn
+            .resource_owner(reference)
+            .ok_or(format!("Missing animation node {:?}", reference))?;
+
+        Self::from_properties(owner, rtype, properties)
+    }
+
+    pub fn from_properties(
+        tscn: &Tscn,
+        rtype: &str,
+        properties: &PropertyMap,
+    ) -> Result<Self, String> {
+        match rtype {
+            "AnimationNodeAnimation" => Ok(AnimationNode::Animation(
+                properties
+                    .get("animation")
+                    .and_then(VarType::string_name)
+                    .unwrap_or_default()
+                    .to_string(),
+            )),
+            "AnimationNodeStateMachine" => Ok(AnimationNode::StateMachine(
+                StateMachine::from_properties(tscn, properties)?,
+            )),
+            _ => {
+                // Blend spaces keep nodes in `blend_point_0/node`, blend trees in `nodes/name/node`
+                let children = properties
+                    .flatten()
+                    .into_iter()
+                    .filter(|(path, _)| path.ends_with("/node"))
+                    .map(|(_, reference)| Self::from_reference(tscn, reference))
+                    .collect::<Result<Vec<AnimationNode>, String>>()?;
+
+                Ok(AnimationNode::Other {
+                    rtype: rtype.to_string(),
+                    children,
+                })
+            }
+        }
+    }
+
+    /// Names of animations played by the node or any nested one, sorted and unique
+    pub fn animations(&self) -> Vec<&str> {
+        let mut animations = Vec::new();
+        self.collect_animations(&mut animations);
+        animations.sort_unstable();
+        animations.dedup();
+
+        animations
+    }
+
+    fn collect_animations<'a>(&'a self, animations: &mut Vec<&'a str>) {
+        match self {
+            AnimationNode::Animation(name) => animations.push(name),
+            AnimationNode::StateMachine(machine) => {
+                for state in machine.states.values() {
+                    state.node.collect_animations(animations);
+                }
+            }
+            AnimationNode::Other { children, .. } => {
+                for child in children {
+                    child.collect_animations(animations);
+                }
+            }
+        }
+    }
+}
+
+impl StateMachine {
+    /// Builds state machine from its properties (i.e. `states/idle/node`, `transitions`)
+    pub fn from_properties(tscn: &Tscn, properties: &PropertyMap) -> Result<Self, String> {
+        let mut states: Vec<(String, State)> = Vec::new();
+        let mut builtin_states: Vec<&str> = Vec::new();
+        if let Some(VarType::Map(map)) = properties.get("states") {
+            for (name, state) in map {
+                let state = match state {
+                    VarType::Map(state) => state,
+                    _ => continue,
+                };
+                let node = match state.get("node") {
+                    Some(node) => node,
+                    // Godot 4 built-in states only keep their position
+                    None if name == "Start" || name == "End" => {
+                        builtin_states.push(name);
+                        continue;
+                    }
+                    None => return Err(format!("State `{}` has no node", name)),
+                };
+
+                states.push((
+                    name.clone(),
+                    State {
+                        node: AnimationNode::from_reference(tscn, node)?,
+                        position: state.get_as("position").unwrap_or_else(Vector2::zeros),
+                    },
+                ));
+            }
+        }
+        states.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut transitions = Vec::new();
+        if let Some(VarType::Arr(arr)) = properties.get("transitions") {
+            for transition in arr.chunks_exact(3) {
+                let (from, to) = match (&transition[0], &transition[1]) {
+                    (VarType::Str(from), VarType::Str(to)) => (from.clone(), to.clone()),
+                    _ => return Err("Invalid state machine transition".to_string()),
+                };
+                let (_, properties) = tscn
+                    .resource_properties(&transition[2])
+                    .ok_or(format!("Missing transition {} -> {}", from, to))?;
+                // Godot 4 `advance_mode`: 0 disabled, 1 enabled, 2 auto
+                let advance_mode = properties.get_as::<isize>("advance_mode");
+
+                transitions.push(Transition {
+                    from,
+                    to,
+                    switch_mode: properties
+                        .get_as::<isize>("switch_mode")
+                        .and_then(|mode| SwitchMode::try_from(mode).ok())
+                        .unwrap_or(SwitchMode::Immediate),
+                    auto_advance: properties.get_as("auto_advance").unwrap_or(false)
+                        || advance_mode == Some(2),
+                    advance_condition: properties
+                        .get("advance_condition")
+                        .and_then(VarType::string_name)
+                        .filter(|condition| !condition.is_empty())
+                        .map(str::to_string),
+                    xfade_time: properties.get_as("xfade_time").unwrap_or(0.0),
+                    priority: properties.get_as("priority").unwrap_or(1),
+                    disabled: properties.get_as("disabled").unwrap_or(false)
+                        || advance_mode == Some(0),
+                });
+            }
+        }
+
+        let node_name = |key: &str, builtin: &str| {
+            properties
+                .get_as::<String>(key)
+                .filter(|name| !name.is_empty())
+                .or_else(|| {
+                    builtin_states
+                        .contains(&builtin)
+                        .then(|| builtin.to_string())
+                })
+        };
+
+        Ok(StateMachine {
+            states: states.into_iter().collect(),
+            transitions,
+            start_node: node_name("start_node", "Start"),
+            end_node: node_name("end_node", "End"),
+        })
+    }
+
+    /// Advance conditions used by the transitions, sorted and unique
+    pub fn conditions(&self) -> Vec<&str> {
+        let mut conditions: Vec<&str> = self
+            .transitions
+            .iter()
+            .filter_map(|transition| transition.advance_condition.as_deref())
+            .collect();
+        conditions.sort_unstable();
+        conditions.dedup();
+
+        conditions
+    }
+
+    /// Renders states and transitions as Graphviz `dot` graph
+    pub fn to_dot(&self) -> String {
+        let mut lines = vec!["digraph StateMachine {".to_string()];
+
+        for (name, state) in &self.states {
+            let label = match &state.node {
+                AnimationNode::Animation(animation) if animation != name => {
+                    format!("{}\\n({})", name, animation)
+                }
+                _ => name.clone(),
+            };
+            let shape = if self.start_node.as_ref() == Some(name) {
+                "doublecircle"
+            } else if self.end_node.as_ref() == Some(name) {
+                "doubleoctagon"
+            } else {
+                "ellipse"
+            };
+
+            lines.push(format!(
+                "    \"{}\" [label=\"{}\", shape={}];",
+                name, label, shape
+            ));
+        }
+
+        // Godot 4 built-in Start and End states aren't among the states
+        for (builtin, shape) in [
+            (&self.start_node, "doublecircle"),
+            (&self.end_node, "doubleoctagon"),
+        ] {
+            if let Some(name) = builtin
+                .as_ref()
+                .filter(|name| !self.states.contains_key(*name))
+            {
+                lines.push(format!(
+                    "    \"{}\" [label=\"{}\", shape={}];",
+                    name, name, shape
+                ));
+            }
+        }
+
+        for transition in &self.transitions {
+            let mut attributes: Vec<String> = Vec::new();
+            if let Some(condition) = &transition.advance_condition {
+                attributes.push(format!("label=\"{}\"", condition));
+            }
+
+            let mut styles = Vec::new();
+            if transition.auto_advance {
+                styles.push("bold");
+            }
+            if transition.disabled {
+                styles.push("dashed");
+            }
+            match styles.as_slice() {
+                [] => {}
+                [style] => attributes.push(format!("style={}", style)),
+                _ => attributes.push(format!("style=\"{}\"", styles.join(","))),
+            }
+
+            let attributes = if attributes.is_empty() {
+                "".to_string()
+            } else {
+                format!(" [{}]", attributes.join(", "))
+            };
+            lines.push(format!(
+                "    \"{}\" -> \"{}\"{};",
+                transition.from, transition.to, attributes
+            ));
+        }
+
+        lines.push("}".to_string());
+        format!("{}\n", lines.join("\n"))
+    }
+}
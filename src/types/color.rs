@@ -0,0 +1,66 @@
+use crate::str_helper::ConstructorHelper;
+use crate::types::VarType;
+
+use std::convert::TryFrom;
+
+/// RGBA color with components in `0..1` (may exceed it for HDR)
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Color {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+    pub a: f32,
+}
+
+impl Color {
+    pub fn new(r: f32, g: f32, b: f32, a: f32) -> Self {
+        Color { r, g, b, a }
+    }
+
+    /// Colors of `PoolColorArray( ... )` (or `PackedColorArray(...)`) text
+    pub fn from_pool(value: &VarType) -> Vec<Color> {
+        match value {
+            VarType::None(text) => text
+                .constructor_reals()
+                .chunks_exact(4)
+                .map(|c| Color::new(c[0], c[1], c[2], c[3]))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    pub fn lerp(&self, other: &Color, weight: f32) -> Color {
+        Color::new(
+            self.r + (other.r - self.r) * weight,
+            self.g + (other.g - self.g) * weight,
+            self.b + (other.b - self.b) * weight,
+            self.a + (other.a - self.a) * weight,
+        )
+    }
+
+    /// 8-bit RGBA, clamped the way Godot rasterizes colors
+    pub fn to_rgba8(&self) -> [u8; 4] {
+        let byte = |component: f32| (component * 255.0).clamp(0.0, 255.0) as u8;
+
+        [byte(self.r), byte(self.g), byte(self.b), byte(self.a)]
+    }
+}
+
+impl TryFrom<&VarType> for Color {
+    type Error = ();
+
+    /// Reads `Color( r, g, b, a )` text
+    fn try_from(value: &VarType) -> Result<Self, Self::Error> {
+        match value {
+            VarType::None(text) if text.starts_with("Color(") => {
+                match text.constructor_reals().as_slice() {
+                    [r, g, b, a] => Ok(Color::new(*r, *g, *b, *a)),
+                    [r, g, b] => Ok(Color::new(*r, *g, *b, 1.0)),
+                    _ => Err(()),
+                }
+            }
+            _ => Err(()),
+        }
+    }
+}
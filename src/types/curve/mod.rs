@@ -0,0 +1,563 @@
+mod path;
+
+pub use path::{Curve2D, Curve3D, PathPoint2D, PathPoint3D};
+
+use crate::types::VarType;
+use crate::{PropertyMap, PropertyTrait};
+
+use nalgebra::{Point2, Vector2};
+use std::convert::TryFrom;
+
+type Point = Point2<f32>;
+
+/// How the tangent of the point is computed
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TangentMode {
+    /// Tangent is set by hand
+    #[default]
+    Free,
+    /// Tangent points towards the neighbour point
+    Linear,
+}
+
+impl TryFrom<isize> for TangentMode {
+    type Error = ();
+
+    fn try_from(value: isize) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(TangentMode::Free),
+            1 => Ok(TangentMode::Linear),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ControlPoint {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helper::point2"))]
+    pub pos: Point,
+    pub left_tangent: f32,
+    pub right_tangent: f32,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub left_mode: TangentMode,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub right_mode: TangentMode,
+}
+
+impl ControlPoint {
+    pub fn new(pos: Point, left_tangent: f32, right_tangent: f32) -> Self {
+        ControlPoint {
+            pos,
+            left_tangent,
+            right_tangent,
+            left_mode: TangentMode::Free,
+            right_mode: TangentMode::Free,
+        }
+    }
+
+    pub fn new_point(x: f32, y: f32, left_tangent: f32, right_tangent: f32) -> Self {
+        Self::new(Point::new(x, y), left_tangent, right_tangent)
+    }
+
+    pub fn with_modes(mut self, left_mode: TangentMode, right_mode: TangentMode) -> Self {
+        self.left_mode = left_mode;
+        self.right_mode = right_mode;
+        self
+    }
+}
+
+/// Godot's `Curve` resource, points are sorted by offset within `0..1`
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct Curve {
+    points: Vec<ControlPoint>,
+    min_value: f32,
+    max_value: f32,
+    bake_resolution: usize,
+    /// Baked values, empty when points have changed
+    #[cfg_attr(feature = "serde", serde(skip))]
+    baked: Vec<f32>,
+}
+
+impl Default for Curve {
+    fn default() -> Self {
+        Curve {
+            points: Vec::new(),
+            min_value: 0.0,
+            max_value: 1.0,
+            bake_resolution: 100,
+            baked: Vec::new(),
+        }
+    }
+}
+
+impl PartialEq for Curve {
+    fn eq(&self, other: &Self) -> bool {
+        self.points == other.points
+            && self.min_value == other.min_value
+            && self.max_value == other.max_value
+            && self.bake_resolution == other.bake_resolution
+    }
+}
+
+impl Curve {
+    const CMP_EPSILON: f32 = 0.00001;
+    const MIN_Y_RANGE: f32 = 0.01;
+    const MAX_BAKE_RESOLUTION: usize = 1000;
+
+    /// Builds curve from `_data` array of `position, left_tangent, right_tangent, left_mode, right_mode` entries
+    pub fn from_data(data: &[VarType]) -> Self {
+        let mut curve = Curve::default();
+
+        for point in data.chunks(5) {
+            if let [VarType::Vector(vector), left_tangent, right_tangent, modes @ ..] = point {
+                let mode = |index: usize| {
+                    modes
+                        .get(index)
+                        .and_then(|mode| isize::try_from(mode).ok())
+                        .and_then(|mode| TangentMode::try_from(mode).ok())
+                        .unwrap_or_default()
+                };
+
+                // Points are stored as is, the same way Godot loads them
+                curve.points.push(
+                    ControlPoint::new_point(
+                        vector.x,
+                        vector.y,
+                        f32::try_from(left_tangent).unwrap_or(0.0),
+                        f32::try_from(right_tangent).unwrap_or(0.0),
+                    )
+                    .with_modes(mode(0), mode(1)),
+                );
+            }
+        }
+
+        curve
+    }
+
+    /// Builds curve from resource properties (`_data`, `min_value`, `max_value`, `bake_resolution`)
+    pub fn from_properties(properties: &PropertyMap) -> Self {
+        let mut curve = match properties.get("_data") {
+            Some(VarType::Curve(curve)) => curve.clone(),
+            Some(VarType::Arr(data)) => Self::from_data(data),
+            _ => Curve::default(),
+        };
+
+        curve.max_value = properties.get_as("max_value").unwrap_or(1.0);
+        curve.set_min_value(properties.get_as("min_value").unwrap_or(0.0));
+        curve.set_bake_resolution(properties.get_as::<isize>("bake_resolution").unwrap_or(100));
+
+        curve
+    }
+
+    /// Serializes points back into `_data` array
+    pub fn to_data(&self) -> Vec<VarType> {
+        self.points
+            .iter()
+            .flat_map(|point| {
+                vec![
+                    VarType::Vector(point.pos.coords),
+                    VarType::Float(point.left_tangent),
+                    VarType::Float(point.right_tangent),
+                    VarType::Num(point.left_mode as isize),
+                    VarType::Num(point.right_mode as isize),
+                ]
+            })
+            .collect()
+    }
+
+    /// Stores the curve into resource properties, defaults are omitted like Godot does
+    pub fn apply(&self, properties: &mut PropertyMap) {
+        properties.insert("_data".to_string(), VarType::Curve(self.clone()));
+        if properties.contains_key("point_count") {
+            properties.insert(
+                "point_count".to_string(),
+                VarType::Num(self.points.len() as isize),
+            );
+        }
+
+        let defaults = Curve::default();
+        let values = [
+            ("min_value", self.min_value, defaults.min_value),
+            ("max_value", self.max_value, defaults.max_value),
+        ];
+        for (key, value, default) in values.iter() {
+            if value != default {
+                properties.insert(key.to_string(), VarType::Float(*value));
+            } else {
+                properties.remove(*key);
+            }
+        }
+
+        if self.bake_resolution != defaults.bake_resolution {
+            properties.insert(
+                "bake_resolution".to_string(),
+                VarType::Num(self.bake_resolution as isize),
+            );
+        } else {
+            properties.remove("bake_resolution");
+        }
+    }
+
+    /// Calculates bezier Interpolation
+    ///
+    /// According to formula from Wikipedia: https://en.wikipedia.org/wiki/B%C3%A9zier_curve#Cubic_B%C3%A9zier_curves
+    fn bezier_interp(t: f32, start: f32, control1: f32, control2: f32, end: f32) -> f32 {
+        let omt = 1.0 - t;
+        let omt2 = omt * omt;
+        let omt3 = omt2 * omt;
+        let t2 = t * t;
+        let t3 = t2 * t;
+
+        start * omt3 + control1 * omt2 * t * 3.0 + control2 * omt * t2 * 3.0 + end * t3
+    }
+
+    pub fn get_points(&self) -> &[ControlPoint] {
+        &self.points
+    }
+
+    pub fn get_min_value(&self) -> f32 {
+        self.min_value
+    }
+
+    pub fn get_max_value(&self) -> f32 {
+        self.max_value
+    }
+
+    pub fn get_bake_resolution(&self) -> usize {
+        self.bake_resolution
+    }
+
+    /// Sets lower bound of the value range, kept below the upper one
+    pub fn set_min_value(&mut self, min_value: f32) {
+        self.min_value = min_value.min(self.max_value - Self::MIN_Y_RANGE);
+    }
+
+    /// Sets upper bound of the value range, kept above the lower one
+    pub fn set_max_value(&mut self, max_value: f32) {
+        self.max_value = max_value.max(self.min_value + Self::MIN_Y_RANGE);
+    }
+
+    /// Sets amount of baked values, clamped to `1..=1000`
+    pub fn set_bake_resolution(&mut self, resolution: isize) {
+        self.bake_resolution = resolution.clamp(1, Self::MAX_BAKE_RESOLUTION as isize) as usize;
+        self.baked.clear();
+    }
+
+    /// Inserts point keeping the order by offset, returns its index
+    pub fn add_point(&mut self, mut control_point: ControlPoint) -> usize {
+        control_point.pos.x = control_point.pos.x.clamp(0.0, 1.0);
+
+        let index = match self.points.first() {
+            None => 0,
+            Some(first) if self.points.len() == 1 => {
+                (control_point.pos.x - first.pos.x > 0.0) as usize
+            }
+            Some(first) => {
+                let i = self.get_index(control_point.pos.x);
+                if i == 0 && control_point.pos.x < first.pos.x {
+                    0
+                } else {
+                    i + 1
+                }
+            }
+        };
+
+        self.points.insert(index, control_point);
+        self.update_auto_tangents(index);
+        self.baked.clear();
+
+        index
+    }
+
+    pub fn remove_point(&mut self, index: usize) -> Option<ControlPoint> {
+        if index >= self.points.len() {
+            return None;
+        }
+
+        self.baked.clear();
+        Some(self.points.remove(index))
+    }
+
+    pub fn clear_points(&mut self) {
+        self.points.clear();
+        self.baked.clear();
+    }
+
+    pub fn set_point_value(&mut self, index: usize, value: f32) {
+        if let Some(point) = self.points.get_mut(index) {
+            point.pos.y = value;
+            self.update_auto_tangents(index);
+            self.baked.clear();
+        }
+    }
+
+    /// Moves point to another offset, returns its new index
+    pub fn set_point_offset(&mut self, index: usize, offset: f32) -> Option<usize> {
+        let point = self.remove_point(index)?;
+        let new_index = self.add_point(ControlPoint {
+            pos: Point::new(offset, point.pos.y),
+            ..point
+        });
+
+        if index != new_index && index < self.points.len() {
+            self.update_auto_tangents(index);
+        }
+        self.update_auto_tangents(new_index);
+
+        Some(new_index)
+    }
+
+    /// Sets left tangent, which makes it free
+    pub fn set_point_left_tangent(&mut self, index: usize, tangent: f32) {
+        if let Some(point) = self.points.get_mut(index) {
+            point.left_tangent = tangent;
+            point.left_mode = TangentMode::Free;
+            self.baked.clear();
+        }
+    }
+
+    /// Sets right tangent, which makes it free
+    pub fn set_point_right_tangent(&mut self, index: usize, tangent: f32) {
+        if let Some(point) = self.points.get_mut(index) {
+            point.right_tangent = tangent;
+            point.right_mode = TangentMode::Free;
+            self.baked.clear();
+        }
+    }
+
+    pub fn set_point_left_mode(&mut self, index: usize, mode: TangentMode) {
+        if index >= self.points.len() {
+            return;
+        }
+
+        self.points[index].left_mode = mode;
+        if mode == TangentMode::Linear && index > 0 {
+            self.points[index].left_tangent =
+                Self::slope(self.points[index].pos, self.points[index - 1].pos);
+        }
+        self.baked.clear();
+    }
+
+    pub fn set_point_right_mode(&mut self, index: usize, mode: TangentMode) {
+        if index >= self.points.len() {
+            return;
+        }
+
+        self.points[index].right_mode = mode;
+        if mode == TangentMode::Linear && index + 1 < self.points.len() {
+            self.points[index].right_tangent =
+                Self::slope(self.points[index].pos, self.points[index + 1].pos);
+        }
+        self.baked.clear();
+    }
+
+    /// Recomputes linear tangents around the point
+    fn update_auto_tangents(&mut self, index: usize) {
+        let pos = self.points[index].pos;
+
+        if index > 0 {
+            let slope = Self::slope(pos, self.points[index - 1].pos);
+            if self.points[index].left_mode == TangentMode::Linear {
+                self.points[index].left_tangent = slope;
+            }
+            if self.points[index - 1].right_mode == TangentMode::Linear {
+                self.points[index - 1].right_tangent = slope;
+            }
+        }
+
+        if index + 1 < self.points.len() {
+            let slope = Self::slope(pos, self.points[index + 1].pos);
+            if self.points[index].right_mode == TangentMode::Linear {
+                self.points[index].right_tangent = slope;
+            }
+            if self.points[index + 1].left_mode == TangentMode::Linear {
+                self.points[index + 1].left_tangent = slope;
+            }
+        }
+    }
+
+    fn slope(from: Point, to: Point) -> f32 {
+        let direction: Vector2<f32> = (to - from).normalize();
+
+        direction.y / direction.x
+    }
+
+    /// Caches `bake_resolution` values for `interpolate_baked`
+    pub fn bake(&mut self) {
+        let resolution = self.bake_resolution;
+        let mut baked = vec![0.0; resolution];
+
+        // Godot samples `i / resolution`, leaving the end slightly short of 1
+        for (i, value) in baked.iter_mut().enumerate().take(resolution - 1).skip(1) {
+            *value = self.interpolate(i as f32 / resolution as f32);
+        }
+
+        if let (Some(first), Some(last)) = (self.points.first(), self.points.last()) {
+            baked[0] = first.pos.y;
+            baked[resolution - 1] = last.pos.y;
+        }
+
+        self.baked = baked;
+    }
+
+    /// Interpolates using baked values, bakes them when points have changed
+    pub fn interpolate_baked(&mut self, offset: f32) -> f32 {
+        if self.baked.is_empty() {
+            self.bake();
+        }
+
+        let size = self.baked.len();
+        if size == 1 {
+            return self.baked[0];
+        }
+
+        let mut fi = offset * size as f32;
+        let mut i = fi.floor();
+        if i < 0.0 {
+            i = 0.0;
+            fi = 0.0;
+        } else if i >= size as f32 {
+            i = (size - 1) as f32;
+            fi = 0.0;
+        }
+
+        let index = i as usize;
+        if index + 1 < size {
+            let t = fi - i;
+            self.baked[index] + (self.baked[index + 1] - self.baked[index]) * t
+        } else {
+            self.baked[size - 1]
+        }
+    }
+
+    pub fn get_index(&self, offset: f32) -> usize {
+        let mut imin = 0;
+        let mut imax = self.points.len() - 1;
+
+        while imax - imin > 1 {
+            let m = (imin + imax) / 2;
+
+            let a = self.points[m].pos.x;
+            let b = self.points[m + 1].pos.x;
+
+            if a < offset && b < offset {
+                imin = m;
+            } else if a > offset {
+                imax = m;
+            } else {
+                return m;
+            }
+        }
+
+        if offset > self.points[imax].pos.x {
+            return imax;
+        }
+
+        imin
+    }
+
+    pub fn interpolate(&self, offset: f32) -> f32 {
+        if self.points.is_empty() {
+            return 0.0;
+        }
+        if self.points.len() == 1 {
+            return self.points[0].pos.y;
+        }
+
+        let i = self.get_index(offset);
+
+        if i == self.points.len() - 1 {
+            return self.points[i].pos.y;
+        }
+
+        let local = offset - self.points[i].pos.x;
+
+        if i == 0 && local <= 0.0 {
+            return self.points[0].pos.y;
+        }
+
+        self.interpolate_local(i, local)
+    }
+
+    fn interpolate_local(&self, index: usize, offset: f32) -> f32 {
+        let point1 = self.points[index].pos;
+        let point2 = self.points[index + 1].pos;
+        let right_tangent = self.points[index].right_tangent;
+        let left_tangent = self.points[index + 1].left_tangent;
+
+        let mut d = point2.x - point1.x;
+        if d.abs() <= Self::CMP_EPSILON {
+            return point2.y;
+        }
+
+        let local_offset = offset / d;
+        d /= 3.0;
+
+        let yac = point1.y + d * right_tangent;
+        let ybc = point2.y - d * left_tangent;
+
+        Self::bezier_interp(local_offset, point1.y, yac, ybc, point2.y)
+    }
+}
+
+resource_try_from!(Curve, "Curve");
+
+#[cfg(test)]
+mod tests {
+    use crate::types::curve::*;
+    use crate::Loader;
+
+    const RESOURCE: &str = r#"[gd_resource type="Curve" format=2]
+
+[resource]
+min_value = -1.0
+bake_resolution = 4
+_data = [ Vector2( 0, -1 ), 0.0, 2.0, 0, 1, Vector2( 1, 1 ), 2.0, 0.0, 1, 0 ]
+"#;
+
+    #[test]
+    fn test_curve() {
+        let mut tscn = Loader::new().parse_tscn(RESOURCE);
+        let mut curve = Curve::try_from(&tscn).unwrap();
+        assert_eq!(curve.get_min_value(), -1.0);
+        assert_eq!(curve.get_bake_resolution(), 4);
+        assert_eq!(curve.get_points()[0].right_mode, TangentMode::Linear);
+        assert!((curve.interpolate(0.5)).abs() < 1e-6);
+
+        // Baked values are 0 -> -1, 0.25 -> -0.5, 0.5 -> 0, 1 -> 1
+        assert!((curve.interpolate_baked(0.125) - -0.75).abs() < 1e-6);
+        assert_eq!(curve.interpolate_baked(2.0), 1.0);
+
+        // Linear tangents follow the new neighbour
+        assert_eq!(
+            curve.add_point(ControlPoint::new_point(0.5, 1.0, 0.0, 0.0)),
+            1
+        );
+        assert_eq!(curve.get_points()[0].right_tangent, 4.0);
+        assert_eq!(curve.get_points()[2].left_tangent, 0.0);
+        assert_eq!(curve.set_point_offset(1, 2.0), Some(1));
+        assert_eq!(curve.get_points()[1].pos.x, 1.0);
+        assert_eq!(curve.remove_point(1).unwrap().pos.y, 1.0);
+        assert_eq!(curve.interpolate_baked(1.0), 1.0);
+
+        curve.set_max_value(-5.0);
+        assert_eq!(curve.get_max_value(), -0.99);
+        curve.set_max_value(1.0);
+        curve.set_point_value(0, 0.0);
+        curve.apply(&mut tscn.resource);
+        assert_eq!(
+            tscn.to_tscn(),
+            r#"[gd_resource type="Curve" format=2]
+
+[resource]
+_data = [ Vector2( 0, 0 ), 0.0, 1.0, 0, 1, Vector2( 1, 1 ), 1.0, 0.0, 1, 0 ]
+bake_resolution = 4
+min_value = -1.0
+"#
+        );
+    }
+}
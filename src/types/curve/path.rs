@@ -0,0 +1,672 @@
+use crate::str_helper::ConstructorHelper;
+use crate::types::VarType;
+use crate::{PropertyMap, PropertyTrait};
+
+use nalgebra::{Vector2, Vector3};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PathPoint2D {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helper::vector2"))]
+    pub position: Vector2<f32>,
+    /// Control point relative to `position`, towards the previous point
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helper::vector2"))]
+    pub in_handle: Vector2<f32>,
+    /// Control point relative to `position`, towards the next point
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helper::vector2"))]
+    pub out_handle: Vector2<f32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PathPoint3D {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helper::vector3"))]
+    pub position: Vector3<f32>,
+    /// Control point relative to `position`, towards the previous point
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helper::vector3"))]
+    pub in_handle: Vector3<f32>,
+    /// Control point relative to `position`, towards the next point
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helper::vector3"))]
+    pub out_handle: Vector3<f32>,
+    /// Rotation (in radians) around the curve
+    pub tilt: f32,
+}
+
+/// Godot's `Curve2D` resource (i.e. of Path2D), baked on every change
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(from = "CurveData<PathPoint2D>"))]
+pub struct Curve2D {
+    points: Vec<PathPoint2D>,
+    bake_interval: f32,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    baked: BakedPath,
+}
+
+/// Godot's `Curve3D` resource (i.e. of Path), baked on every change
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(from = "CurveData<PathPoint3D>"))]
+pub struct Curve3D {
+    points: Vec<PathPoint3D>,
+    bake_interval: f32,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    baked: BakedPath,
+}
+
+/// Deserialized curve, baked again when converted
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct CurveData<P> {
+    points: Vec<P>,
+    bake_interval: f32,
+}
+
+/// Points spaced by the bake interval, 2D curves keep `z` at zero
+#[derive(Debug, Clone, PartialEq, Default)]
+struct BakedPath {
+    points: Vec<Vector3<f32>>,
+    tilts: Vec<f32>,
+    length: f32,
+}
+
+impl PathPoint2D {
+    pub fn new(position: Vector2<f32>, in_handle: Vector2<f32>, out_handle: Vector2<f32>) -> Self {
+        PathPoint2D {
+            position,
+            in_handle,
+            out_handle,
+        }
+    }
+
+    fn to_3d(self) -> PathPoint3D {
+        let lift = |v: Vector2<f32>| Vector3::new(v.x, v.y, 0.0);
+
+        PathPoint3D::new(
+            lift(self.position),
+            lift(self.in_handle),
+            lift(self.out_handle),
+            0.0,
+        )
+    }
+}
+
+impl PathPoint3D {
+    pub fn new(
+        position: Vector3<f32>,
+        in_handle: Vector3<f32>,
+        out_handle: Vector3<f32>,
+        tilt: f32,
+    ) -> Self {
+        PathPoint3D {
+            position,
+            in_handle,
+            out_handle,
+            tilt,
+        }
+    }
+}
+
+impl Default for Curve2D {
+    fn default() -> Self {
+        Curve2D {
+            points: Vec::new(),
+            bake_interval: 5.0,
+            baked: BakedPath::default(),
+        }
+    }
+}
+
+impl Default for Curve3D {
+    fn default() -> Self {
+        Curve3D {
+            points: Vec::new(),
+            bake_interval: 0.2,
+            baked: BakedPath::default(),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<CurveData<PathPoint2D>> for Curve2D {
+    fn from(data: CurveData<PathPoint2D>) -> Self {
+        let mut curve = Curve2D {
+            points: data.points,
+            bake_interval: data.bake_interval,
+            baked: BakedPath::default(),
+        };
+        curve.bake();
+
+        curve
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<CurveData<PathPoint3D>> for Curve3D {
+    fn from(data: CurveData<PathPoint3D>) -> Self {
+        let mut curve = Curve3D {
+            points: data.points,
+            bake_interval: data.bake_interval,
+            baked: BakedPath::default(),
+        };
+        curve.bake();
+
+        curve
+    }
+}
+
+impl Curve2D {
+    /// Builds curve from `_data` (`points` as `in, out, position` triplets) and `bake_interval`
+    pub fn from_properties(properties: &PropertyMap) -> Self {
+        let reals = reals(data(properties, "points"));
+        let points = reals
+            .chunks_exact(6)
+            .map(|p| {
+                PathPoint2D::new(
+                    Vector2::new(p[4], p[5]),
+                    Vector2::new(p[0], p[1]),
+                    Vector2::new(p[2], p[3]),
+                )
+            })
+            .collect();
+
+        let mut curve = Curve2D {
+            points,
+            bake_interval: properties.get_as("bake_interval").unwrap_or(5.0),
+            baked: BakedPath::default(),
+        };
+        curve.bake();
+
+        curve
+    }
+
+    pub fn get_points(&self) -> &[PathPoint2D] {
+        &self.points
+    }
+
+    /// Inserts point at `index` (appends when `None`)
+    pub fn add_point(&mut self, point: PathPoint2D, index: Option<usize>) {
+        let index = index.unwrap_or(self.points.len()).min(self.points.len());
+        self.points.insert(index, point);
+        self.bake();
+    }
+
+    pub fn set_point(&mut self, index: usize, point: PathPoint2D) {
+        if let Some(existing) = self.points.get_mut(index) {
+            *existing = point;
+            self.bake();
+        }
+    }
+
+    pub fn remove_point(&mut self, index: usize) -> Option<PathPoint2D> {
+        if index >= self.points.len() {
+            return None;
+        }
+
+        let point = self.points.remove(index);
+        self.bake();
+
+        Some(point)
+    }
+
+    pub fn get_bake_interval(&self) -> f32 {
+        self.bake_interval
+    }
+
+    pub fn set_bake_interval(&mut self, bake_interval: f32) {
+        self.bake_interval = bake_interval;
+        self.bake();
+    }
+
+    /// Position on the segment starting at point `index`, `t` in `0..1`
+    pub fn interpolate(&self, index: usize, t: f32) -> Vector2<f32> {
+        let points: Vec<PathPoint3D> = self.points.iter().map(|p| p.to_3d()).collect();
+
+        interpolate(&points, index, t).xy()
+    }
+
+    /// Position at fractional point index (i.e. `1.5` is the middle of the second segment)
+    pub fn interpolatef(&self, findex: f32) -> Vector2<f32> {
+        let findex = findex.max(0.0);
+
+        self.interpolate(findex as usize, findex.fract())
+    }
+
+    /// Length of the baked curve
+    pub fn get_baked_length(&self) -> f32 {
+        self.baked.length
+    }
+
+    pub fn get_baked_points(&self) -> Vec<Vector2<f32>> {
+        self.baked.points.iter().map(|p| p.xy()).collect()
+    }
+
+    /// Position at `offset` along the baked curve
+    pub fn interpolate_baked(&self, offset: f32, cubic: bool) -> Vector2<f32> {
+        self.baked
+            .interpolate(offset, self.bake_interval, cubic)
+            .xy()
+    }
+
+    /// Baked point nearest to `point`
+    pub fn get_closest_point(&self, point: Vector2<f32>) -> Vector2<f32> {
+        self.baked
+            .closest(Vector3::new(point.x, point.y, 0.0), self.bake_interval)
+            .0
+            .xy()
+    }
+
+    /// Offset along the baked curve of the point nearest to `point`
+    pub fn get_closest_offset(&self, point: Vector2<f32>) -> f32 {
+        self.baked
+            .closest(Vector3::new(point.x, point.y, 0.0), self.bake_interval)
+            .1
+    }
+
+    fn bake(&mut self) {
+        let points: Vec<PathPoint3D> = self.points.iter().map(|p| p.to_3d()).collect();
+        self.baked = BakedPath::bake(&points, self.bake_interval);
+    }
+}
+
+impl Curve3D {
+    /// Builds curve from `_data` (`points` as `in, out, position` triplets, `tilts`) and `bake_interval`
+    pub fn from_properties(properties: &PropertyMap) -> Self {
+        let reals = reals(data(properties, "points"));
+        let tilts = reals_of(data(properties, "tilts"));
+        let points = reals
+            .chunks_exact(9)
+            .enumerate()
+            .map(|(i, p)| {
+                PathPoint3D::new(
+                    Vector3::new(p[6], p[7], p[8]),
+                    Vector3::new(p[0], p[1], p[2]),
+                    Vector3::new(p[3], p[4], p[5]),
+                    tilts.get(i).copied().unwrap_or(0.0),
+                )
+            })
+            .collect();
+
+        let mut curve = Curve3D {
+            points,
+            bake_interval: properties.get_as("bake_interval").unwrap_or(0.2),
+            baked: BakedPath::default(),
+        };
+        curve.bake();
+
+        curve
+    }
+
+    pub fn get_points(&self) -> &[PathPoint3D] {
+        &self.points
+    }
+
+    /// Inserts point at `index` (appends when `None`)
+    pub fn add_point(&mut self, point: PathPoint3D, index: Option<usize>) {
+        let index = index.unwrap_or(self.points.len()).min(self.points.len());
+        self.points.insert(index, point);
+        self.bake();
+    }
+
+    pub fn set_point(&mut self, index: usize, point: PathPoint3D) {
+        if let Some(existing) = self.points.get_mut(index) {
+            *existing = point;
+            self.bake();
+        }
+    }
+
+    pub fn remove_point(&mut self, index: usize) -> Option<PathPoint3D> {
+        if index >= self.points.len() {
+            return None;
+        }
+
+        let point = self.points.remove(index);
+        self.bake();
+
+        Some(point)
+    }
+
+    pub fn get_bake_interval(&self) -> f32 {
+        self.bake_interval
+    }
+
+    pub fn set_bake_interval(&mut self, bake_interval: f32) {
+        self.bake_interval = bake_interval;
+        self.bake();
+    }
+
+    /// Position on the segment starting at point `index`, `t` in `0..1`
+    pub fn interpolate(&self, index: usize, t: f32) -> Vector3<f32> {
+        interpolate(&self.points, index, t)
+    }
+
+    /// Position at fractional point index (i.e. `1.5` is the middle of the second segment)
+    pub fn interpolatef(&self, findex: f32) -> Vector3<f32> {
+        let findex = findex.max(0.0);
+
+        self.interpolate(findex as usize, findex.fract())
+    }
+
+    /// Length of the baked curve
+    pub fn get_baked_length(&self) -> f32 {
+        self.baked.length
+    }
+
+    pub fn get_baked_points(&self) -> &[Vector3<f32>] {
+        &self.baked.points
+    }
+
+    pub fn get_baked_tilts(&self) -> &[f32] {
+        &self.baked.tilts
+    }
+
+    /// Position at `offset` along the baked curve
+    pub fn interpolate_baked(&self, offset: f32, cubic: bool) -> Vector3<f32> {
+        self.baked.interpolate(offset, self.bake_interval, cubic)
+    }
+
+    /// Tilt at `offset` along the baked curve
+    pub fn interpolate_baked_tilt(&self, offset: f32) -> f32 {
+        self.baked.tilt(offset, self.bake_interval)
+    }
+
+    /// Position and tilt at `offset` along the baked curve
+    pub fn sample(&self, offset: f32, cubic: bool) -> (Vector3<f32>, f32) {
+        (
+            self.interpolate_baked(offset, cubic),
+            self.interpolate_baked_tilt(offset),
+        )
+    }
+
+    /// Baked point nearest to `point`
+    pub fn get_closest_point(&self, point: Vector3<f32>) -> Vector3<f32> {
+        self.baked.closest(point, self.bake_interval).0
+    }
+
+    /// Offset along the baked curve of the point nearest to `point`
+    pub fn get_closest_offset(&self, point: Vector3<f32>) -> f32 {
+        self.baked.closest(point, self.bake_interval).1
+    }
+
+    fn bake(&mut self) {
+        self.baked = BakedPath::bake(&self.points, self.bake_interval);
+    }
+}
+
+impl BakedPath {
+    /// Walks segments in steps of 0.1 and bisects every place a point is due, like Godot 3 does
+    fn bake(points: &[PathPoint3D], interval: f32) -> Self {
+        let (first, last) = match (points.first(), points.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return BakedPath::default(),
+        };
+        if points.len() == 1 || interval <= 0.0 {
+            return BakedPath {
+                points: vec![first.position],
+                tilts: vec![first.tilt],
+                length: 0.0,
+            };
+        }
+
+        let mut pos = first.position;
+        let mut baked_points = vec![pos];
+        let mut tilts = vec![first.tilt];
+
+        for i in 0..points.len() - 1 {
+            let step: f32 = 0.1;
+            let mut p = 0.0;
+
+            while p < 1.0 {
+                let np = (p + step).min(1.0);
+                let mut npp = interpolate(points, i, np);
+
+                if (npp - pos).norm() > interval {
+                    let (mut low, mut high) = (p, np);
+                    let mut mid = low + (high - low) * 0.5;
+
+                    for _ in 0..10 {
+                        npp = interpolate(points, i, mid);
+                        if interval < (npp - pos).norm() {
+                            high = mid;
+                        } else {
+                            low = mid;
+                        }
+                        mid = low + (high - low) * 0.5;
+                    }
+
+                    pos = npp;
+                    p = mid;
+                    baked_points.push(pos);
+                    tilts.push(points[i].tilt + (points[i + 1].tilt - points[i].tilt) * mid);
+                } else {
+                    p = np;
+                }
+            }
+        }
+
+        let remainder = (last.position - pos).norm();
+        let length = (baked_points.len() - 1) as f32 * interval + remainder;
+        baked_points.push(last.position);
+        tilts.push(last.tilt);
+
+        BakedPath {
+            points: baked_points,
+            tilts,
+            length,
+        }
+    }
+
+    /// Baked point index and weight towards the next one at `offset`
+    fn locate(&self, offset: f32, interval: f32) -> Option<(usize, f32)> {
+        let count = self.points.len();
+        if count < 2 || offset < 0.0 {
+            return None;
+        }
+
+        let index = (offset / interval).floor() as usize;
+        if offset >= self.length || index >= count - 1 {
+            return None;
+        }
+
+        let mut frac = offset % interval;
+        if index == count - 2 {
+            // The last segment is shorter than the interval
+            if frac > 0.0 {
+                frac /= self.length % interval;
+            }
+        } else {
+            frac /= interval;
+        }
+
+        Some((index, frac))
+    }
+
+    fn interpolate(&self, offset: f32, interval: f32, cubic: bool) -> Vector3<f32> {
+        let points = &self.points;
+        let (index, frac) = match self.locate(offset, interval) {
+            Some(located) => located,
+            None if points.is_empty() => return Vector3::zeros(),
+            None if offset < 0.0 || points.len() == 1 => return points[0],
+            None => return points[points.len() - 1],
+        };
+
+        let (a, b) = (points[index], points[index + 1]);
+        if !cubic {
+            return a.lerp(&b, frac);
+        }
+
+        let pre = if index > 0 { points[index - 1] } else { a };
+        let post = points.get(index + 2).copied().unwrap_or(b);
+        let (t2, t3) = (frac * frac, frac * frac * frac);
+
+        (a * 2.0
+            + (b - pre) * frac
+            + (pre * 2.0 - a * 5.0 + b * 4.0 - post) * t2
+            + (a * 3.0 - pre - b * 3.0 + post) * t3)
+            * 0.5
+    }
+
+    fn tilt(&self, offset: f32, interval: f32) -> f32 {
+        let tilts = &self.tilts;
+        match self.locate(offset, interval) {
+            Some((index, frac)) => tilts[index] + (tilts[index + 1] - tilts[index]) * frac,
+            None if tilts.is_empty() => 0.0,
+            None if offset < 0.0 => tilts[0],
+            None => tilts[tilts.len() - 1],
+        }
+    }
+
+    /// Nearest point on the baked segments and its offset
+    fn closest(&self, to: Vector3<f32>, interval: f32) -> (Vector3<f32>, f32) {
+        match self.points.len() {
+            0 => return (Vector3::zeros(), 0.0),
+            1 => return (self.points[0], 0.0),
+            _ => (),
+        }
+
+        let mut nearest = (self.points[0], 0.0);
+        let mut nearest_distance = f32::MAX;
+        for (i, segment) in self.points.windows(2).enumerate() {
+            let origin = segment[0];
+            let direction = (segment[1] - origin) / interval;
+            let d = (to - origin).dot(&direction).clamp(0.0, interval);
+            let projection = origin + direction * d;
+            let distance = (projection - to).norm_squared();
+
+            if distance < nearest_distance {
+                nearest = (projection, i as f32 * interval + d);
+                nearest_distance = distance;
+            }
+        }
+
+        nearest
+    }
+}
+
+/// Cubic bezier between point `index` and the next one
+fn interpolate(points: &[PathPoint3D], index: usize, t: f32) -> Vector3<f32> {
+    let start = match points.get(index) {
+        Some(start) => start,
+        None => return points.last().map_or_else(Vector3::zeros, |p| p.position),
+    };
+    let end = match points.get(index + 1) {
+        Some(end) => end,
+        None => return start.position,
+    };
+
+    let control1 = start.position + start.out_handle;
+    let control2 = end.position + end.in_handle;
+    let omt = 1.0 - t;
+
+    start.position * (omt * omt * omt)
+        + control1 * (omt * omt * t * 3.0)
+        + control2 * (omt * t * t * 3.0)
+        + end.position * (t * t * t)
+}
+
+/// Entry of the `_data` dictionary
+fn data<'a>(properties: &'a PropertyMap, key: &str) -> Option<&'a VarType> {
+    match properties.get("_data") {
+        Some(VarType::Dict(data)) => data.get(key),
+        _ => None,
+    }
+}
+
+/// Reals of `PoolVector2Array` / `PoolRealArray` values, or of constructor text
+/// (i.e. `PoolVector3Array( 0, 0, 0 )`, `PackedVector2Array(0, 0)`)
+fn reals(value: Option<&VarType>) -> Vec<f32> {
+    match value {
+        Some(VarType::VectorArr(vectors)) => vectors.iter().flat_map(|v| vec![v.x, v.y]).collect(),
+        value => reals_of(value),
+    }
+}
+
+fn reals_of(value: Option<&VarType>) -> Vec<f32> {
+    match value {
+        Some(VarType::FloatArr(reals)) => reals.clone(),
+        Some(VarType::IntArr(ints)) => ints.iter().map(|i| *i as f32).collect(),
+        Some(VarType::None(text)) => text.constructor_reals(),
+        _ => Vec::new(),
+    }
+}
+
+resource_try_from!(Curve2D, "Curve2D");
+
+resource_try_from!(Curve3D, "Curve3D");
+
+#[cfg(test)]
+mod tests {
+    use crate::types::curve::path::*;
+    use crate::Loader;
+
+    use std::convert::TryFrom;
+
+    const SCENE: &str = r#"[gd_scene load_steps=3 format=2]
+
+[sub_resource type="Curve2D" id=1]
+bake_interval = 10.0
+_data = {
+"points": PoolVector2Array( 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 25, 0 )
+}
+
+[sub_resource type="Curve3D" id=2]
+bake_interval = 1.0
+_data = {
+"points": PoolVector3Array( 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 4 ),
+"tilts": PoolRealArray( 0, 2 )
+}
+
+[node name="Patrol" type="Path2D"]
+curve = SubResource( 1 )
+
+[node name="Rail" type="Path" parent="."]
+curve = SubResource( 2 )
+"#;
+
+    #[test]
+    fn test_path_curves() {
+        let tscn = Loader::new().parse_tscn(SCENE);
+        // Bisection leaves baked points slightly off the interval, as in Godot
+        let curve = Curve2D::try_from(&tscn.sub_resources[&1]).unwrap();
+        assert_eq!(curve.get_points().len(), 2);
+        assert!((curve.get_baked_length() - 25.0).abs() < 1e-2);
+        assert_eq!(curve.get_baked_points().len(), 4);
+        assert!((curve.interpolate_baked(15.0, false).x - 15.0).abs() < 1e-2);
+        assert!((curve.interpolate_baked(10.0, true) - curve.get_baked_points()[1]).norm() < 1e-2);
+        assert_eq!(curve.interpolatef(0.5), Vector2::new(12.5, 0.0));
+
+        let point = Vector2::new(12.0, 7.0);
+        assert!((curve.get_closest_point(point) - Vector2::new(12.0, 0.0)).norm() < 1e-2);
+        assert!((curve.get_closest_offset(point) - 12.0).abs() < 1e-2);
+
+        let curve = Curve3D::try_from(&tscn.sub_resources[&2]).unwrap();
+        let (position, tilt) = curve.sample(2.0, false);
+        assert!((position - Vector3::new(0.0, 0.0, 2.0)).norm() < 1e-2);
+        assert!((tilt - 1.0).abs() < 1e-2);
+        assert_eq!(
+            curve.sample(10.0, false),
+            (Vector3::new(0.0, 0.0, 4.0), 2.0)
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_path_curves_serde() {
+        let tscn = Loader::new().parse_tscn(SCENE);
+
+        let curve = Curve2D::try_from(&tscn.sub_resources[&1]).unwrap();
+        let encoded = serde_json::to_value(&curve).unwrap();
+        assert_eq!(
+            encoded["points"][1]["position"],
+            serde_json::json!([25.0, 0.0])
+        );
+        assert!(encoded.get("baked").is_none());
+        // Baked again on load
+        assert_eq!(serde_json::from_value::<Curve2D>(encoded).unwrap(), curve);
+
+        let curve = Curve3D::try_from(&tscn.sub_resources[&2]).unwrap();
+        let encoded = serde_json::to_value(&curve).unwrap();
+        assert_eq!(encoded["points"][1]["tilt"], serde_json::json!(2.0));
+        assert_eq!(serde_json::from_value::<Curve3D>(encoded).unwrap(), curve);
+    }
+}
@@ -0,0 +1,578 @@
+use crate::types::VarType;
+use crate::{PropertyMap, PropertyTrait, Tscn};
+
+use std::convert::TryFrom;
+
+/// Exported variable, `export(...) var` in Godot 3 or `@export...` in Godot 4
+#[derive(Debug, Clone, PartialEq)]
+pub struct Export {
+    pub name: String,
+    /// Declared type, inferred from the default when missing
+    pub vtype: Option<String>,
+    /// Export annotation (i.e. `export`, `export_range`)
+    pub annotation: String,
+    /// Annotation arguments, or Godot 3 export arguments after the type
+    pub hints: Vec<String>,
+    /// Default value expression
+    pub default: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Signal {
+    pub name: String,
+    /// Argument names, without types
+    pub args: Vec<String>,
+}
+
+/// Declarations of GDScript source, inner classes are skipped
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct GDScript {
+    pub source: String,
+    /// Base class name or script path
+    pub extends: Option<String>,
+    pub class_name: Option<String>,
+    pub signals: Vec<Signal>,
+    pub exports: Vec<Export>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScriptIssue {
+    /// Value which can't be assigned to the export's type
+    TypeMismatch {
+        name: String,
+        expected: String,
+        value: VarType,
+    },
+}
+
+impl GDScript {
+    pub fn parse(source: &str) -> Self {
+        let mut script = GDScript {
+            source: source.to_string(),
+            ..GDScript::default()
+        };
+        // Godot 4 annotations may precede the `var` line
+        let mut annotations: Vec<String> = Vec::new();
+
+        for line in logical_lines(source) {
+            if line.starts_with(char::is_whitespace) || line.trim().is_empty() {
+                continue;
+            }
+
+            let mut rest = line.trim();
+            while let Some(annotation) = rest.strip_prefix('@') {
+                let end = annotation_end(annotation);
+                annotations.push(annotation[..end].to_string());
+                rest = annotation[end..].trim_start();
+            }
+            if rest.is_empty() {
+                // Groups only apply to the inspector
+                annotations.retain(|annotation| !is_export_grouping(annotation));
+                continue;
+            }
+
+            let (keyword, tail) = split_word(rest);
+            match keyword {
+                "extends" => script.extends = Some(unquote(tail).to_string()),
+                "class_name" => {
+                    // `class_name Name, "res://icon.png"` or `class_name Name extends Base`
+                    let (name, tail) = split_word(tail);
+                    script.class_name = Some(name.trim_end_matches(',').to_string());
+                    if let ("extends", base) = split_word(tail) {
+                        script.extends = Some(unquote(base).to_string());
+                    }
+                }
+                "signal" => script.signals.push(Signal::parse(tail)),
+                "export" => {
+                    if let Some(export) = Export::parse_godot3(rest) {
+                        script.exports.push(export);
+                    }
+                }
+                "var" => {
+                    let annotation = annotations.iter().find(|annotation| {
+                        annotation.starts_with("export") && !is_export_grouping(annotation)
+                    });
+                    if let Some(annotation) = annotation {
+                        if let Some(export) = Export::parse_godot4(annotation, tail) {
+                            script.exports.push(export);
+                        }
+                    }
+                }
+                _ => {}
+            }
+
+            annotations.clear();
+        }
+
+        script
+    }
+
+    /// Reads `script/source` of GDScript resource properties
+    pub fn from_properties(properties: &PropertyMap) -> Self {
+        GDScript::parse(
+            &properties
+                .get_as::<String>("script/source")
+                .unwrap_or_default(),
+        )
+    }
+
+    /// Resolves `SubResource` or `ExtResource` script, ext resources must have been loaded
+    pub fn from_reference(tscn: &Tscn, reference: &VarType) -> Result<Self, String> {
+        let (rtype, properties) = tscn
+            .resource_properties(reference)
+            .ok_or("Script resource is not loaded")?;
+        if rtype != "GDScript" {
+            return Err(format!("Expected GDScript resource, got {}", rtype));
+        }
+
+        Ok(GDScript::from_properties(properties))
+    }
+
+    /// Script attached to the node
+    pub fn from_node(tscn: &Tscn, node_id: usize) -> Result<Self, String> {
+        let script = tscn
+            .node_property(node_id, "script")
+            .ok_or(format!("Node {} has no script", node_id))?;
+
+        GDScript::from_reference(tscn, script)
+    }
+
+    pub fn export(&self, name: &str) -> Option<&Export> {
+        self.exports.iter().find(|export| export.name == name)
+    }
+
+    /// Exported values of `properties` which don't fit the export type
+    pub fn check(&self, properties: &PropertyMap) -> Vec<ScriptIssue> {
+        self.exports
+            .iter()
+            .filter_map(|export| {
+                let value = properties.get(&export.name)?;
+                if export.accepts(value) != Some(false) {
+                    return None;
+                }
+
+                Some(ScriptIssue::TypeMismatch {
+                    name: export.name.clone(),
+                    expected: export.vtype.clone().unwrap_or_default(),
+                    value: value.clone(),
+                })
+            })
+            .collect()
+    }
+
+    /// Exports which `properties` don't set, so keep their defaults
+    pub fn unset_exports(&self, properties: &PropertyMap) -> Vec<&Export> {
+        self.exports
+            .iter()
+            .filter(|export| !properties.contains_key(&export.name))
+            .collect()
+    }
+}
+
+impl Signal {
+    fn parse(declaration: &str) -> Self {
+        let (name, args) = match declaration.split_once('(') {
+            Some((name, args)) => (
+                name,
+                args.trim_end()
+                    .trim_end_matches(')')
+                    .split(',')
+                    .map(|arg| arg.split(':').next().unwrap_or("").trim().to_string())
+                    .filter(|arg| !arg.is_empty())
+                    .collect(),
+            ),
+            None => (declaration, Vec::new()),
+        };
+
+        Signal {
+            name: name.trim().to_string(),
+            args,
+        }
+    }
+}
+
+impl Export {
+    /// `export var name = 1` or `export(int, 0, 10) var name`
+    fn parse_godot3(line: &str) -> Option<Self> {
+        let line = line.strip_prefix("export")?.trim_start();
+        let (args, rest) = match line.strip_prefix('(') {
+            Some(args) => {
+                let end = closing_paren(args)?;
+                (split_args(&args[..end]), args[end + 1..].trim_start())
+            }
+            None => (Vec::new(), line),
+        };
+
+        // `export onready var` is allowed too
+        let rest = rest.strip_prefix("onready").unwrap_or(rest).trim_start();
+        let mut export = Export::parse_var(rest.strip_prefix("var")?)?;
+        export.annotation = "export".to_string();
+
+        let mut args = args.into_iter();
+        if let Some(vtype) = args.next() {
+            export.vtype = Some(vtype);
+        }
+        export.hints = args.collect();
+
+        Some(export)
+    }
+
+    /// Declaration after `var` with `annotation` text (i.e. `export_range(0, 10)`)
+    fn parse_godot4(annotation: &str, declaration: &str) -> Option<Self> {
+        let mut export = Export::parse_var(declaration)?;
+
+        match annotation.split_once('(') {
+            Some((name, args)) => {
+                export.annotation = name.trim().to_string();
+                export.hints = split_args(&args[..closing_paren(args)?]);
+            }
+            None => export.annotation = annotation.trim().to_string(),
+        }
+
+        Some(export)
+    }
+
+    /// `name: Type = default`, `name := default` or `name = default setget ...`
+    fn parse_var(declaration: &str) -> Option<Self> {
+        let declaration = declaration.trim();
+        let declaration = match declaration.find(" setget ") {
+            Some(index) => &declaration[..index],
+            None => declaration,
+        };
+        // Godot 4 property with `set`/`get` block
+        let declaration = declaration.trim_end_matches(':').trim();
+
+        let (declaration, default) = match declaration.split_once('=') {
+            Some((declaration, default)) => (declaration, Some(default.trim().to_string())),
+            None => (declaration, None),
+        };
+        let (name, vtype) = match declaration.split_once(':') {
+            Some((name, vtype)) => (name.trim(), Some(vtype.trim()).filter(|t| !t.is_empty())),
+            None => (declaration.trim(), None),
+        };
+        if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+            return None;
+        }
+
+        Some(Export {
+            name: name.to_string(),
+            vtype: vtype
+                .map(str::to_string)
+                .or_else(|| default.as_deref().and_then(infer_type)),
+            annotation: String::new(),
+            hints: Vec::new(),
+            default,
+        })
+    }
+
+    /// Whether `value` fits the export type, `None` when the type isn't known (i.e. enums)
+    pub fn accepts(&self, value: &VarType) -> Option<bool> {
+        let vtype = self.vtype.as_deref()?;
+
+        value
+            .fits_type(vtype)
+            .or_else(|| match value.variant_type() {
+                // Classes accept resources, but also enums (integers) are named by type
+                Some("Object") | Some("Nil") => Some(true),
+                _ => None,
+            })
+    }
+}
+
+impl TryFrom<&Tscn> for GDScript {
+    type Error = String;
+
+    /// Reads `GDScript` resource, `.gd` files are loaded as such
+    fn try_from(tscn: &Tscn) -> Result<Self, Self::Error> {
+        if tscn.rtype != "GDScript" {
+            return Err(format!("Expected GDScript resource, got {}", tscn.rtype));
+        }
+
+        Ok(GDScript::from_properties(&tscn.resource))
+    }
+}
+
+/// Lines without comments, brackets and `\` continuations are joined
+fn logical_lines(source: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0isize;
+
+    for line in source.lines() {
+        let line = if current.is_empty() {
+            line
+        } else {
+            line.trim_start()
+        };
+        let mut quote: Option<char> = None;
+        let mut escaped = false;
+        for c in line.chars() {
+            match quote {
+                Some(q) => {
+                    if escaped {
+                        escaped = false;
+                    } else if c == '\\' {
+                        escaped = true;
+                    } else if c == q {
+                        quote = None;
+                    }
+                }
+                None => match c {
+                    '#' => break,
+                    '"' | '\'' => quote = Some(c),
+                    '(' | '[' | '{' => depth += 1,
+                    ')' | ']' | '}' => depth -= 1,
+                    _ => {}
+                },
+            }
+            current.push(c);
+        }
+
+        if current.ends_with('\\') {
+            current.pop();
+        } else if depth <= 0 {
+            lines.push(std::mem::take(&mut current));
+            depth = 0;
+        } else {
+            current.push(' ');
+        }
+    }
+
+    if !current.is_empty() {
+        lines.push(current);
+    }
+
+    lines
+}
+
+/// `@export_group`, `@export_subgroup` and `@export_category` don't export variables
+fn is_export_grouping(annotation: &str) -> bool {
+    ["export_group", "export_subgroup", "export_category"]
+        .iter()
+        .any(|grouping| annotation.starts_with(grouping))
+}
+
+/// Length of annotation name with its arguments
+fn annotation_end(text: &str) -> usize {
+    let name_end = text
+        .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .unwrap_or(text.len());
+
+    match text[name_end..].strip_prefix('(') {
+        Some(args) => closing_paren(args).map_or(text.len(), |end| name_end + end + 2),
+        None => name_end,
+    }
+}
+
+/// Index of `)` closing already opened parenthesis
+fn closing_paren(text: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut quote: Option<char> = None;
+
+    for (i, c) in text.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '(' | '[' | '{') => depth += 1,
+            (None, ')') if depth == 0 => return Some(i),
+            (None, ')' | ']' | '}') => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// Comma separated arguments, outside of nested brackets and strings
+fn split_args(text: &str) -> Vec<String> {
+    let mut args = vec![String::new()];
+    let mut depth = 0usize;
+    let mut quote: Option<char> = None;
+
+    for c in text.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '(' | '[' | '{') => depth += 1,
+            (None, ')' | ']' | '}') => depth = depth.saturating_sub(1),
+            (None, ',') if depth == 0 => {
+                args.push(String::new());
+                continue;
+            }
+            _ => {}
+        }
+        args.last_mut().unwrap().push(c);
+    }
+
+    args.into_iter()
+        .map(|arg| arg.trim().to_string())
+        .filter(|arg| !arg.is_empty())
+        .collect()
+}
+
+/// First word (up to whitespace or `(`) and the rest
+fn split_word(text: &str) -> (&str, &str) {
+    let text = text.trim_start();
+    match text.find(|c: char| c.is_whitespace() || c == '(') {
+        Some(0) => (&text[..1], text[1..].trim()),
+        Some(index) if text[index..].starts_with('(') => (&text[..index], text[index..].trim()),
+        Some(index) => (&text[..index], text[index..].trim()),
+        None => (text, ""),
+    }
+}
+
+fn unquote(text: &str) -> &str {
+    text.trim().trim_matches('"').trim_matches('\'')
+}
+
+/// Type of default value literal
+fn infer_type(default: &str) -> Option<String> {
+    let default = default.trim();
+    let vtype = if default.starts_with('"') || default.starts_with('\'') {
+        "String"
+    } else if default == "true" || default == "false" {
+        "bool"
+    } else if default.parse::<isize>().is_ok() {
+        "int"
+    } else if default.parse::<f64>().is_ok() {
+        "float"
+    } else if default.starts_with('[') {
+        "Array"
+    } else if default.starts_with('{') {
+        "Dictionary"
+    } else if default.starts_with(char::is_uppercase) && default.ends_with(')') {
+        // Constructors (i.e. `Vector2(1, 2)`), but not `Class.new()`
+        default
+            .split('(')
+            .next()
+            .filter(|name| !name.contains('.'))?
+    } else {
+        return None;
+    };
+
+    Some(vtype.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::types::gdscript::*;
+    use crate::Loader;
+
+    const SCRIPT: &str = r#"extends KinematicBody2D
+class_name Player, "res://icon.png"
+
+signal hit(damage, source)
+signal died
+
+export var speed = 200.0 # Pixels per second
+export(int, 0, 100) var health = 100
+export(String, FILE, "*.json") var config
+export var tint: Color = Color(1, 1, 1, 1) setget set_tint
+export(Array, int) var levels = [
+	1, 2, 3,
+]
+var velocity = Vector2()
+
+class Inner:
+	export var hidden = 1
+"#;
+
+    #[test]
+    fn test_gdscript() {
+        let script = GDScript::parse(SCRIPT);
+        assert_eq!(script.extends.as_deref(), Some("KinematicBody2D"));
+        assert_eq!(script.class_name.as_deref(), Some("Player"));
+        assert_eq!(
+            script.signals,
+            vec![
+                Signal {
+                    name: "hit".to_string(),
+                    args: vec!["damage".to_string(), "source".to_string()]
+                },
+                Signal {
+                    name: "died".to_string(),
+                    args: Vec::new()
+                }
+            ]
+        );
+
+        assert_eq!(
+            script
+                .exports
+                .iter()
+                .map(|export| (export.name.as_str(), export.vtype.as_deref()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("speed", Some("float")),
+                ("health", Some("int")),
+                ("config", Some("String")),
+                ("tint", Some("Color")),
+                ("levels", Some("Array")),
+            ]
+        );
+        let health = script.export("health").unwrap();
+        assert_eq!(health.hints, vec!["0", "100"]);
+        assert_eq!(health.default.as_deref(), Some("100"));
+        assert_eq!(script.export("levels").unwrap().hints, vec!["int"]);
+
+        let godot4 = GDScript::parse(
+            "class_name Enemy extends CharacterBody2D\n\n\
+             @export_range(0, 10, 0.5) var armor := 2.5\n\
+             @export_enum(\"Melee\", \"Ranged\")\n\
+             var kind: int\n\
+             @export_group(\"Loot\")\n\
+             @export var drop: PackedScene\n\
+             @onready var sprite = $Sprite\n",
+        );
+        assert_eq!(godot4.extends.as_deref(), Some("CharacterBody2D"));
+        let armor = godot4.export("armor").unwrap();
+        assert_eq!(armor.annotation, "export_range");
+        assert_eq!(armor.hints, vec!["0", "10", "0.5"]);
+        assert_eq!(armor.vtype.as_deref(), Some("float"));
+        assert_eq!(
+            godot4.export("kind").unwrap().hints,
+            vec!["\"Melee\"", "\"Ranged\""]
+        );
+        assert_eq!(godot4.exports.len(), 3);
+    }
+
+    #[test]
+    fn test_node_exports() {
+        let scene = format!(
+            "[gd_scene load_steps=2 format=2]\n\n\
+             [sub_resource type=\"GDScript\" id=1]\n\
+             script/source = \"{}\"\n\n\
+             [node name=\"Player\" type=\"KinematicBody2D\"]\n\
+             script = SubResource( 1 )\n\
+             health = 50\n\
+             speed = \"fast\"\n",
+            SCRIPT.replace('"', "\\\"")
+        );
+        let tscn = Loader::new().parse_tscn(&scene);
+        let script = GDScript::from_node(&tscn, 0).unwrap();
+        let properties = &tscn.nodes[&0].properties;
+
+        assert_eq!(
+            script.check(properties),
+            vec![ScriptIssue::TypeMismatch {
+                name: "speed".to_string(),
+                expected: "float".to_string(),
+                value: VarType::Str("fast".to_string())
+            }]
+        );
+        assert_eq!(
+            script
+                .unset_exports(properties)
+                .iter()
+                .map(|export| (export.name.as_str(), export.default.as_deref()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("config", None),
+                ("tint", Some("Color(1, 1, 1, 1)")),
+                ("levels", Some("[ 1, 2, 3, ]"))
+            ]
+        );
+    }
+}
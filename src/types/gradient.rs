@@ -0,0 +1,289 @@
+use crate::str_helper::ConstructorHelper;
+use crate::types::{Color, VarType};
+use crate::{PropertyMap, PropertyTrait, Tscn};
+
+use std::convert::TryFrom;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GradientInterpolation {
+    #[default]
+    Linear,
+    /// Color of the previous point, without blending
+    Constant,
+    Cubic,
+}
+
+impl TryFrom<isize> for GradientInterpolation {
+    type Error = ();
+
+    fn try_from(value: isize) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(GradientInterpolation::Linear),
+            1 => Ok(GradientInterpolation::Constant),
+            2 => Ok(GradientInterpolation::Cubic),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GradientPoint {
+    pub offset: f32,
+    pub color: Color,
+}
+
+/// Godot's `Gradient` resource, points are sorted by offset
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Gradient {
+    points: Vec<GradientPoint>,
+    pub interpolation: GradientInterpolation,
+}
+
+/// Godot's `GradientTexture` (`GradientTexture1D` in Godot 4) resource
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GradientTexture {
+    pub gradient: Gradient,
+    pub width: usize,
+}
+
+impl Default for Gradient {
+    /// Black to white, like a new Godot gradient
+    fn default() -> Self {
+        Gradient {
+            points: vec![
+                GradientPoint {
+                    offset: 0.0,
+                    color: Color::new(0.0, 0.0, 0.0, 1.0),
+                },
+                GradientPoint {
+                    offset: 1.0,
+                    color: Color::new(1.0, 1.0, 1.0, 1.0),
+                },
+            ],
+            interpolation: GradientInterpolation::Linear,
+        }
+    }
+}
+
+impl Gradient {
+    /// Builds gradient from `offsets`, `colors` and `interpolation_mode` properties
+    pub fn from_properties(properties: &PropertyMap) -> Self {
+        let mut gradient = Gradient::default();
+
+        if let (Some(offsets), Some(colors)) = (properties.get("offsets"), properties.get("colors"))
+        {
+            let offsets: Vec<f32> = match offsets {
+                VarType::FloatArr(offsets) => offsets.clone(),
+                VarType::None(text) => text.constructor_reals(),
+                _ => Vec::new(),
+            };
+
+            gradient.points = offsets
+                .into_iter()
+                .zip(Color::from_pool(colors))
+                .map(|(offset, color)| GradientPoint { offset, color })
+                .collect();
+            gradient.sort();
+        }
+
+        gradient.interpolation = properties
+            .get_as::<isize>("interpolation_mode")
+            .and_then(|mode| GradientInterpolation::try_from(mode).ok())
+            .unwrap_or_default();
+
+        gradient
+    }
+
+    pub fn get_points(&self) -> &[GradientPoint] {
+        &self.points
+    }
+
+    pub fn add_point(&mut self, offset: f32, color: Color) {
+        self.points.push(GradientPoint { offset, color });
+        self.sort();
+    }
+
+    pub fn remove_point(&mut self, index: usize) -> Option<GradientPoint> {
+        if index >= self.points.len() {
+            return None;
+        }
+
+        Some(self.points.remove(index))
+    }
+
+    /// Color at `offset`, opaque black when there are no points
+    pub fn interpolate(&self, offset: f32) -> Color {
+        let points = &self.points;
+        if points.is_empty() {
+            return Color::new(0.0, 0.0, 0.0, 1.0);
+        }
+
+        // Index of the first point after the offset
+        let second = points.partition_point(|point| point.offset <= offset);
+        if second == 0 {
+            return points[0].color;
+        }
+        if second >= points.len() {
+            return points[points.len() - 1].color;
+        }
+
+        let first = second - 1;
+        let (a, b) = (&points[first], &points[second]);
+        let weight = (offset - a.offset) / (b.offset - a.offset);
+
+        match self.interpolation {
+            GradientInterpolation::Linear => a.color.lerp(&b.color, weight),
+            GradientInterpolation::Constant => a.color,
+            GradientInterpolation::Cubic => {
+                let pre = &points[first.saturating_sub(1)].color;
+                let post = &points[(second + 1).min(points.len() - 1)].color;
+                let cubic = |pre: f32, from: f32, to: f32, post: f32| {
+                    0.5 * ((from * 2.0)
+                        + (-pre + to) * weight
+                        + (2.0 * pre - 5.0 * from + 4.0 * to - post) * weight * weight
+                        + (-pre + 3.0 * from - 3.0 * to + post) * weight * weight * weight)
+                };
+
+                Color::new(
+                    cubic(pre.r, a.color.r, b.color.r, post.r),
+                    cubic(pre.g, a.color.g, b.color.g, post.g),
+                    cubic(pre.b, a.color.b, b.color.b, post.b),
+                    cubic(pre.a, a.color.a, b.color.a, post.a),
+                )
+            }
+        }
+    }
+
+    fn sort(&mut self) {
+        self.points.sort_by(|a, b| {
+            a.offset
+                .partial_cmp(&b.offset)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+    }
+}
+
+impl GradientTexture {
+    /// Builds texture from its properties, the gradient is resolved within `tscn`
+    pub fn from_properties(tscn: &Tscn, properties: &PropertyMap) -> Result<Self, String> {
+        let gradient = match properties.get("gradient") {
+            Some(reference) => {
+                let (_, rtype, properties) = tscn
+                    .resource_owner(reference)
+                    .ok_or("Gradient resource is not loaded")?;
+                if rtype != "Gradient" {
+                    return Err(format!("Expected Gradient resource, got {}", rtype));
+                }
+
+                Gradient::from_properties(properties)
+            }
+            None => Gradient::default(),
+        };
+
+        Ok(GradientTexture {
+            gradient,
+            width: properties.get_as::<isize>("width").unwrap_or(2048).max(1) as usize,
+        })
+    }
+
+    /// Rasterizes the gradient into `width` RGBA pixels
+    pub fn to_rgba8(&self) -> Vec<u8> {
+        let last = (self.width.max(2) - 1) as f32;
+
+        (0..self.width)
+            .flat_map(|i| self.gradient.interpolate(i as f32 / last).to_rgba8())
+            .collect()
+    }
+}
+
+resource_try_from!(Gradient, "Gradient");
+
+impl TryFrom<&Tscn> for GradientTexture {
+    type Error = String;
+
+    fn try_from(tscn: &Tscn) -> Result<Self, Self::Error> {
+        if tscn.rtype != "GradientTexture" && tscn.rtype != "GradientTexture1D" {
+            return Err(format!(
+                "Expected GradientTexture resource, got {}",
+                tscn.rtype
+            ));
+        }
+
+        GradientTexture::from_properties(tscn, &tscn.resource)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::types::gradient::*;
+    use crate::Loader;
+
+    const RESOURCE: &str = r#"[gd_resource type="GradientTexture" load_steps=2 format=2]
+
+[sub_resource type="Gradient" id=1]
+offsets = PoolRealArray( 0, 0.5, 1 )
+colors = PoolColorArray( 1, 0, 0, 1, 0, 1, 0, 1, 0, 0, 1, 0 )
+
+[resource]
+gradient = SubResource( 1 )
+width = 5
+"#;
+
+    #[test]
+    fn test_gradient() {
+        let tscn = Loader::new().parse_tscn(RESOURCE);
+        let mut texture = GradientTexture::try_from(&tscn).unwrap();
+        assert_eq!(texture.gradient.get_points().len(), 3);
+        assert_eq!(
+            texture.gradient.interpolate(0.25),
+            Color::new(0.5, 0.5, 0.0, 1.0)
+        );
+        assert_eq!(
+            texture.gradient.interpolate(-1.0),
+            Color::new(1.0, 0.0, 0.0, 1.0)
+        );
+        assert_eq!(
+            texture.to_rgba8(),
+            vec![255, 0, 0, 255, 127, 127, 0, 255, 0, 255, 0, 255, 0, 127, 127, 127, 0, 0, 255, 0]
+        );
+
+        texture.gradient.interpolation = GradientInterpolation::Constant;
+        assert_eq!(
+            texture.gradient.interpolate(0.75),
+            Color::new(0.0, 1.0, 0.0, 1.0)
+        );
+
+        // Catmull-Rom through the neighbours overshoots past the middle point
+        texture.gradient.interpolation = GradientInterpolation::Cubic;
+        assert!(texture.gradient.interpolate(0.25).g > 0.5);
+
+        let mut tscn = Loader::new().parse_tscn(
+            "[gd_resource type=\"GradientTexture\" format=2]\n\n[resource]\ngradient = ExtResource( 1 )\n",
+        );
+        let mut gradient = Loader::new().parse_tscn(RESOURCE);
+        gradient.rtype = "Gradient".to_string();
+        gradient.resource = gradient.sub_resources[&1].properties.clone();
+        tscn.ext_resources.insert(1, gradient);
+        let texture = GradientTexture::try_from(&tscn).unwrap();
+        assert_eq!(texture.gradient.get_points().len(), 3);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_gradient_serde() {
+        let texture = GradientTexture::try_from(&Loader::new().parse_tscn(RESOURCE)).unwrap();
+        let encoded = serde_json::to_value(&texture).unwrap();
+        assert_eq!(
+            encoded["gradient"]["points"][1],
+            serde_json::json!({ "offset": 0.5, "color": { "r": 0.0, "g": 1.0, "b": 0.0, "a": 1.0 } })
+        );
+        assert_eq!(
+            serde_json::from_value::<GradientTexture>(encoded).unwrap(),
+            texture
+        );
+    }
+}
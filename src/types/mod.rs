@@ -1,5 +1,74 @@
+/// Implements `TryFrom<&Tscn>` (resource file) and `TryFrom<&NodeEntry>` (sub resource)
+/// for a type built out of the properties of `rtype` resource, by its `from_properties`
+/// unless another builder returning `Result` is given
+macro_rules! resource_try_from {
+    ($type:ident, $rtype:literal) => {
+        resource_try_from!($type, $rtype, |properties| Ok($type::from_properties(
+            properties
+        )));
+    };
+    ($type:ident, $rtype:literal, $build:expr) => {
+        impl std::convert::TryFrom<&crate::Tscn> for $type {
+            type Error = String;
+
+            fn try_from(tscn: &crate::Tscn) -> Result<Self, Self::Error> {
+                if tscn.rtype != $rtype {
+                    return Err(format!("Expected {} resource, got {}", $rtype, tscn.rtype));
+                }
+
+                ($build)(&tscn.resource)
+            }
+        }
+
+        impl std::convert::TryFrom<&crate::NodeEntry> for $type {
+            type Error = String;
+
+            fn try_from(sub_resource: &crate::NodeEntry) -> Result<Self, Self::Error> {
+                if sub_resource.rtype != $rtype {
+                    return Err(format!(
+                        "Expected {} resource, got {}",
+                        $rtype, sub_resource.rtype
+                    ));
+                }
+
+                ($build)(&sub_resource.properties)
+            }
+        }
+    };
+}
+
+pub mod animation;
+pub mod animation_tree;
+pub mod color;
 pub mod curve;
+pub mod gdscript;
+pub mod gradient;
+pub mod shader;
+pub mod shape;
+pub mod sprite_frames;
+pub mod theme;
+pub mod tileset;
+pub mod transform;
+#[allow(clippy::module_inception)]
 pub mod types;
 
-pub use curve::{ControlPoint, Curve};
+pub use animation::{
+    Animation, AudioClip, BezierKey, InterpolationType, MethodKey, Track, TrackKeys, TrackSample,
+    TransformKey, UpdateMode,
+};
+pub use animation_tree::{AnimationNode, State, StateMachine, SwitchMode, Transition};
+pub use color::Color;
+pub use curve::{ControlPoint, Curve, Curve2D, Curve3D, PathPoint2D, PathPoint3D, TangentMode};
+pub use gdscript::{Export, GDScript, ScriptIssue, Signal};
+pub use gradient::{Gradient, GradientInterpolation, GradientPoint, GradientTexture};
+pub use shader::{ParamIssue, Shader, ShaderMaterial, Uniform};
+pub use shape::{Aabb, Shape};
+pub use sprite_frames::{SpriteAnimation, SpriteFrame, SpriteFrames};
+pub use theme::{
+    StyleBox, StyleBoxFlat, StyleBoxLine, StyleBoxTexture, Theme, ThemeDataType, ThemeItem,
+};
+pub use tileset::{
+    Autotile, BitmaskMode, Tile, TileCoord, TileMode, TilePolygon, TileSet, TileShape,
+};
+pub use transform::{Transform2D, Transform2DTrait, Transform3D, Transform3DTrait};
 pub use types::VarType;
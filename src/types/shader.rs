@@ -0,0 +1,415 @@
+use crate::types::VarType;
+use crate::{PropertyMap, PropertyTrait, Tscn};
+
+use indexmap::IndexMap;
+use std::convert::TryFrom;
+
+/// `uniform` declaration of shader source
+#[derive(Debug, Clone, PartialEq)]
+pub struct Uniform {
+    pub name: String,
+    /// Type without precision qualifier (i.e. `vec4`, `sampler2D`)
+    pub utype: String,
+    /// Array size text of array uniforms (i.e. `4` for `float weights[4]`)
+    pub array_size: Option<String>,
+    /// Hints after `:` (i.e. `hint_range(0, 1)`, `source_color`)
+    pub hints: Vec<String>,
+    /// Default value expression after `=`
+    pub default: Option<String>,
+}
+
+/// Shader source with its material uniforms, `global` and `instance` uniforms are skipped
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Shader {
+    pub code: String,
+    /// `shader_type` (i.e. `canvas_item`, `spatial`)
+    pub shader_type: Option<String>,
+    pub uniforms: Vec<Uniform>,
+}
+
+/// Parameter values of `ShaderMaterial` (`shader_param/*` or Godot 4 `shader_parameter/*`)
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ShaderMaterial {
+    /// Shader resource reference
+    pub shader: Option<VarType>,
+    /// Values by parameter name, sorted by name
+    pub params: IndexMap<String, VarType>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParamIssue {
+    /// Parameter without uniform in the shader
+    Unknown { name: String },
+    /// Value which can't be assigned to the uniform's type
+    TypeMismatch {
+        name: String,
+        expected: String,
+        value: VarType,
+    },
+}
+
+impl Shader {
+    pub fn parse(code: &str) -> Self {
+        let mut shader = Shader {
+            code: code.to_string(),
+            ..Shader::default()
+        };
+
+        for statement in top_level_statements(&strip_comments(code)) {
+            let mut words = statement.split_whitespace();
+            match words.next() {
+                Some("shader_type") => shader.shader_type = words.next().map(str::to_string),
+                Some("uniform") => {
+                    if let Some(uniform) = Uniform::parse(&statement["uniform".len()..]) {
+                        shader.uniforms.push(uniform);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        shader
+    }
+
+    /// Reads the `code` of Shader resource properties
+    pub fn from_properties(properties: &PropertyMap) -> Self {
+        Shader::parse(&properties.get_as::<String>("code").unwrap_or_default())
+    }
+
+    /// Resolves `SubResource` or `ExtResource` shader, ext resources must have been loaded
+    pub fn from_reference(tscn: &Tscn, reference: &VarType) -> Result<Self, String> {
+        let (rtype, properties) = tscn
+            .resource_properties(reference)
+            .ok_or("Shader resource is not loaded")?;
+        if rtype != "Shader" {
+            return Err(format!("Expected Shader resource, got {}", rtype));
+        }
+
+        Ok(Shader::from_properties(properties))
+    }
+
+    pub fn uniform(&self, name: &str) -> Option<&Uniform> {
+        self.uniforms.iter().find(|uniform| uniform.name == name)
+    }
+}
+
+impl Uniform {
+    /// Parses declaration after the `uniform` keyword
+    fn parse(declaration: &str) -> Option<Self> {
+        let (declaration, default) = match split_top_level(declaration, '=').as_slice() {
+            [declaration] => (declaration.to_string(), None),
+            [declaration, default] => (declaration.to_string(), Some(default.trim().to_string())),
+            _ => return None,
+        };
+
+        let (declaration, hints) = match declaration.split_once(':') {
+            Some((declaration, hints)) => (
+                declaration.to_string(),
+                split_top_level(hints, ',')
+                    .iter()
+                    .map(|hint| hint.trim().to_string())
+                    .filter(|hint| !hint.is_empty())
+                    .collect(),
+            ),
+            None => (declaration, Vec::new()),
+        };
+
+        let words: Vec<&str> = declaration
+            .split_whitespace()
+            .filter(|word| !["lowp", "mediump", "highp"].contains(word))
+            .collect();
+        let (utype, name) = match words.as_slice() {
+            [utype, name] => (utype.to_string(), name.to_string()),
+            // `float weights [4]`
+            [utype, name, size] => (utype.to_string(), format!("{}{}", name, size)),
+            _ => return None,
+        };
+
+        // The size may follow either the type or the name
+        let (utype, type_size) = split_array(&utype);
+        let (name, name_size) = split_array(&name);
+
+        Some(Uniform {
+            name,
+            utype,
+            array_size: name_size.or(type_size),
+            hints,
+            default,
+        })
+    }
+
+    /// Whether `value` can be assigned to the uniform, `None` when the value or the uniform
+    /// type isn't recognized
+    pub fn accepts(&self, value: &VarType) -> Option<bool> {
+        if self.array_size.is_some() {
+            return value.fits_type("Array");
+        }
+        if self.utype.contains("sampler") {
+            return value
+                .variant_type()
+                .map(|vtype| vtype == "Object" || vtype == "Nil");
+        }
+
+        // Variant types which the engine converts to the uniform type
+        let types: &[&str] = match self.utype.as_str() {
+            "bool" => &["bool"],
+            "int" | "uint" => &["int"],
+            "float" => &["float"],
+            "vec2" => &["Vector2"],
+            "ivec2" | "uvec2" => &["Vector2i"],
+            "vec3" => &["Vector3", "Color"],
+            "ivec3" | "uvec3" => &["Vector3i"],
+            "vec4" => &["Vector4", "Plane", "Quaternion", "Color", "Rect2"],
+            "ivec4" | "uvec4" => &["Vector4i"],
+            "mat3" => &["Basis"],
+            "mat4" => &["Transform3D", "Projection"],
+            _ => return None,
+        };
+
+        value.variant_type()?;
+        Some(
+            types
+                .iter()
+                .any(|vtype| value.fits_type(vtype) == Some(true)),
+        )
+    }
+}
+
+impl ShaderMaterial {
+    pub fn from_properties(properties: &PropertyMap) -> Self {
+        let mut params: Vec<(String, VarType)> = ["shader_param", "shader_parameter"]
+            .iter()
+            .filter_map(|group| match properties.get(*group) {
+                Some(VarType::Map(map)) => Some(map),
+                _ => None,
+            })
+            .flat_map(|map| map.flatten())
+            .map(|(name, value)| (name, value.clone()))
+            .collect();
+        params.sort_by(|a, b| a.0.cmp(&b.0));
+
+        ShaderMaterial {
+            shader: properties.get("shader").cloned(),
+            params: params.into_iter().collect(),
+        }
+    }
+
+    /// Resolves the material's shader within `tscn`
+    pub fn shader(&self, tscn: &Tscn) -> Result<Shader, String> {
+        let reference = self.shader.as_ref().ok_or("Material has no shader")?;
+
+        Shader::from_reference(tscn, reference)
+    }
+
+    /// Parameters which the shader doesn't declare or which don't fit the uniform type
+    pub fn check(&self, shader: &Shader) -> Vec<ParamIssue> {
+        self.params
+            .iter()
+            .filter_map(|(name, value)| match shader.uniform(name) {
+                None => Some(ParamIssue::Unknown { name: name.clone() }),
+                Some(uniform) if uniform.accepts(value) == Some(false) => {
+                    Some(ParamIssue::TypeMismatch {
+                        name: name.clone(),
+                        expected: uniform.utype.clone(),
+                        value: value.clone(),
+                    })
+                }
+                Some(_) => None,
+            })
+            .collect()
+    }
+
+    /// Material value of the uniform, or its default expression
+    pub fn value(&self, uniform: &Uniform) -> Option<VarType> {
+        self.params
+            .get(&uniform.name)
+            .cloned()
+            .or_else(|| uniform.default.clone().map(VarType::None))
+    }
+}
+
+impl TryFrom<&Tscn> for Shader {
+    type Error = String;
+
+    /// Reads `Shader` resource, `.shader` / `.gdshader` files are loaded as such
+    fn try_from(tscn: &Tscn) -> Result<Self, Self::Error> {
+        if tscn.rtype != "Shader" {
+            return Err(format!("Expected Shader resource, got {}", tscn.rtype));
+        }
+
+        Ok(Shader::from_properties(&tscn.resource))
+    }
+}
+
+impl TryFrom<&Tscn> for ShaderMaterial {
+    type Error = String;
+
+    /// Reads `ShaderMaterial` resource (`.tres`)
+    fn try_from(tscn: &Tscn) -> Result<Self, Self::Error> {
+        if tscn.rtype != "ShaderMaterial" {
+            return Err(format!(
+                "Expected ShaderMaterial resource, got {}",
+                tscn.rtype
+            ));
+        }
+
+        Ok(ShaderMaterial::from_properties(&tscn.resource))
+    }
+}
+
+/// Removes `//` and `/* */` comments
+fn strip_comments(code: &str) -> String {
+    let mut result = String::with_capacity(code.len());
+    let mut rest = code;
+
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix("//") {
+            rest = after.find('\n').map_or("", |end| &after[end..]);
+        } else if let Some(after) = rest.strip_prefix("/*") {
+            rest = after.find("*/").map_or("", |end| &after[end + 2..]);
+            result.push(' ');
+        } else {
+            let c = rest.chars().next().unwrap();
+            result.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+
+    result
+}
+
+/// `;` separated statements outside of function bodies and structs
+fn top_level_statements(code: &str) -> Vec<String> {
+    let mut statements = Vec::new();
+    let mut statement = String::new();
+    let mut depth = 0usize;
+
+    for c in code.chars() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth = depth.saturating_sub(1);
+                // Function bodies end without `;`
+                if depth == 0 {
+                    statement.clear();
+                }
+            }
+            ';' if depth == 0 => {
+                statements.push(statement.trim().to_string());
+                statement.clear();
+            }
+            _ if depth == 0 => statement.push(c),
+            _ => {}
+        }
+    }
+
+    statements
+}
+
+/// Splits on `separator` outside of parentheses
+fn split_top_level(text: &str, separator: char) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    let mut depth = 0usize;
+
+    for c in text.chars() {
+        match c {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+
+        if c == separator && depth == 0 {
+            parts.push(String::new());
+        } else {
+            parts.last_mut().unwrap().push(c);
+        }
+    }
+
+    parts
+}
+
+/// Splits `name[4]` into name and size
+fn split_array(text: &str) -> (String, Option<String>) {
+    match text.split_once('[') {
+        Some((name, size)) => (
+            name.to_string(),
+            Some(size.trim_end_matches(']').trim().to_string()),
+        ),
+        None => (text.to_string(), None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::types::shader::*;
+    use crate::Loader;
+
+    const SCENE: &str = r#"[gd_scene load_steps=3 format=2]
+
+[sub_resource type="Shader" id=1]
+code = "shader_type canvas_item;
+
+// Tint of the sprite
+uniform vec4 tint : hint_color = vec4(1.0);
+uniform float strength : hint_range(0, 1, 0.1) = 0.5;
+uniform lowp sampler2D noise;
+uniform float weights[3];
+global uniform float time_scale;
+
+void fragment() {
+	COLOR = texture(TEXTURE, UV) * tint;
+}
+"
+
+[sub_resource type="ShaderMaterial" id=2]
+shader = SubResource( 1 )
+shader_param/tint = Color( 1, 0, 0, 1 )
+shader_param/strength = true
+shader_param/noise = null
+shader_param/speed = 2.0
+
+[node name="Sprite" type="Sprite"]
+material = SubResource( 2 )
+"#;
+
+    #[test]
+    fn test_shader_params() {
+        let tscn = Loader::new().parse_tscn(SCENE);
+        let material = ShaderMaterial::from_properties(&tscn.sub_resources[&2].properties);
+        let shader = material.shader(&tscn).unwrap();
+
+        assert_eq!(shader.shader_type.as_deref(), Some("canvas_item"));
+        assert_eq!(
+            shader
+                .uniforms
+                .iter()
+                .map(|uniform| uniform.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["tint", "strength", "noise", "weights"]
+        );
+
+        let strength = shader.uniform("strength").unwrap();
+        assert_eq!(strength.hints, vec!["hint_range(0, 1, 0.1)"]);
+        assert_eq!(strength.default.as_deref(), Some("0.5"));
+        assert_eq!(shader.uniform("noise").unwrap().utype, "sampler2D");
+        assert_eq!(
+            shader.uniform("weights").unwrap().array_size.as_deref(),
+            Some("3")
+        );
+
+        assert_eq!(
+            material.check(&shader),
+            vec![
+                ParamIssue::Unknown {
+                    name: "speed".to_string()
+                },
+                ParamIssue::TypeMismatch {
+                    name: "strength".to_string(),
+                    expected: "float".to_string(),
+                    value: VarType::Bool(true)
+                },
+            ]
+        );
+        assert_eq!(material.value(shader.uniform("weights").unwrap()), None);
+    }
+}
@@ -0,0 +1,607 @@
+use crate::str_helper::ConstructorHelper;
+use crate::types::VarType;
+use crate::{PropertyMap, PropertyTrait, Tscn};
+
+use nalgebra::{Vector2, Vector3};
+use std::convert::TryFrom;
+
+/// Axis aligned bounding box, flat (zero `z` size) for 2D shapes
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Aabb {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helper::vector3"))]
+    pub position: Vector3<f32>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helper::vector3"))]
+    pub size: Vector3<f32>,
+}
+
+/// Collision shape resource geometry, in the shape's local space
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Shape {
+    /// `RectangleShape2D`, centered at the origin
+    Rectangle {
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde_helper::vector2"))]
+        extents: Vector2<f32>,
+    },
+    /// `CircleShape2D`
+    Circle { radius: f32 },
+    /// `CapsuleShape2D`, segment between the centers of the caps
+    Capsule2D {
+        radius: f32,
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde_helper::vector2"))]
+        a: Vector2<f32>,
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde_helper::vector2"))]
+        b: Vector2<f32>,
+    },
+    /// `ConvexPolygonShape2D`
+    ConvexPolygon {
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde_helper::vector2_vec"))]
+        points: Vec<Vector2<f32>>,
+    },
+    /// `ConcavePolygonShape2D`, hollow
+    ConcavePolygon {
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde_helper::segment2_vec"))]
+        segments: Vec<[Vector2<f32>; 2]>,
+    },
+    /// `SegmentShape2D`
+    Segment {
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde_helper::vector2"))]
+        a: Vector2<f32>,
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde_helper::vector2"))]
+        b: Vector2<f32>,
+    },
+    /// `RayShape2D` (`SeparationRayShape2D` in Godot 4), cast along `+y`
+    Ray2D { length: f32 },
+    /// `BoxShape` (`BoxShape3D` in Godot 4), centered at the origin
+    Box {
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde_helper::vector3"))]
+        extents: Vector3<f32>,
+    },
+    /// `SphereShape`
+    Sphere { radius: f32 },
+    /// `CapsuleShape`, segment between the centers of the caps
+    Capsule3D {
+        radius: f32,
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde_helper::vector3"))]
+        a: Vector3<f32>,
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde_helper::vector3"))]
+        b: Vector3<f32>,
+    },
+    /// `CylinderShape`, along `y`
+    Cylinder { radius: f32, height: f32 },
+    /// `ConvexPolygonShape`
+    ConvexMesh {
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde_helper::vector3_vec"))]
+        points: Vec<Vector3<f32>>,
+    },
+    /// `ConcavePolygonShape`, triangles of the mesh
+    ConcaveMesh {
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde_helper::triangle3_vec"))]
+        faces: Vec<[Vector3<f32>; 3]>,
+    },
+    /// `RayShape` (`SeparationRayShape3D` in Godot 4), cast along `+z`
+    Ray3D { length: f32 },
+}
+
+impl Aabb {
+    /// Smallest box holding all the points, empty at the origin when there are none
+    pub fn from_points(points: impl IntoIterator<Item = Vector3<f32>>) -> Self {
+        let mut points = points.into_iter();
+        let first = match points.next() {
+            Some(first) => first,
+            None => {
+                return Aabb {
+                    position: Vector3::zeros(),
+                    size: Vector3::zeros(),
+                }
+            }
+        };
+
+        let (min, max) = points.fold((first, first), |(min, max), point| {
+            (min.inf(&point), max.sup(&point))
+        });
+
+        Aabb {
+            position: min,
+            size: max - min,
+        }
+    }
+
+    pub fn end(&self) -> Vector3<f32> {
+        self.position + self.size
+    }
+
+    /// Grows the box by `by` in every direction
+    pub fn grow(&self, by: f32) -> Self {
+        Aabb {
+            position: self.position.add_scalar(-by),
+            size: self.size.add_scalar(by * 2.0),
+        }
+    }
+
+    pub fn contains_point(&self, point: Vector3<f32>) -> bool {
+        let end = self.end();
+
+        (0..3).all(|i| point[i] >= self.position[i] && point[i] <= end[i])
+    }
+}
+
+impl Shape {
+    /// Builds shape from resource type and properties, both Godot 3 and Godot 4 names are read.
+    ///
+    /// `format` is the text format of the file holding the resource, `CapsuleShape2D` height
+    /// includes the caps since Godot 4 (format `3`)
+    pub fn from_properties(
+        rtype: &str,
+        properties: &PropertyMap,
+        format: usize,
+    ) -> Result<Self, String> {
+        let real = |key: &str, default: f32| properties.get_as(key).unwrap_or(default);
+
+        let shape = match rtype {
+            "RectangleShape2D" => Shape::Rectangle {
+                extents: match properties.get_as::<Vector2<f32>>("size") {
+                    Some(size) => size / 2.0,
+                    None => properties
+                        .get_as("extents")
+                        .unwrap_or_else(|| Vector2::new(10.0, 10.0)),
+                },
+            },
+            "CircleShape2D" => Shape::Circle {
+                radius: real("radius", 10.0),
+            },
+            "CapsuleShape2D" => {
+                let radius = real("radius", 10.0);
+                let half = if format >= 3 {
+                    (real("height", 30.0) / 2.0 - radius).max(0.0)
+                } else {
+                    real("height", 20.0) / 2.0
+                };
+                Shape::Capsule2D {
+                    radius,
+                    a: Vector2::new(0.0, -half),
+                    b: Vector2::new(0.0, half),
+                }
+            }
+            "ConvexPolygonShape2D" => Shape::ConvexPolygon {
+                points: vectors2(properties.get("points")),
+            },
+            "ConcavePolygonShape2D" => Shape::ConcavePolygon {
+                segments: vectors2(properties.get("segments"))
+                    .chunks_exact(2)
+                    .map(|segment| [segment[0], segment[1]])
+                    .collect(),
+            },
+            "SegmentShape2D" => Shape::Segment {
+                a: properties.get_as("a").unwrap_or_else(Vector2::zeros),
+                b: properties
+                    .get_as("b")
+                    .unwrap_or_else(|| Vector2::new(0.0, 10.0)),
+            },
+            "RayShape2D" | "SeparationRayShape2D" => Shape::Ray2D {
+                length: real("length", 20.0),
+            },
+            "BoxShape" => Shape::Box {
+                extents: vector3(properties.get("extents")).unwrap_or_else(|| Vector3::repeat(1.0)),
+            },
+            "BoxShape3D" => Shape::Box {
+                extents: vector3(properties.get("size")).unwrap_or_else(|| Vector3::repeat(1.0))
+                    / 2.0,
+            },
+            "SphereShape" | "SphereShape3D" => Shape::Sphere {
+                radius: real("radius", if rtype == "SphereShape" { 1.0 } else { 0.5 }),
+            },
+            // Godot 3 capsule lies along `z` and its height excludes the caps
+            "CapsuleShape" => {
+                let half = real("height", 1.0) / 2.0;
+                Shape::Capsule3D {
+                    radius: real("radius", 1.0),
+                    a: Vector3::new(0.0, 0.0, -half),
+                    b: Vector3::new(0.0, 0.0, half),
+                }
+            }
+            "CapsuleShape3D" => {
+                let radius = real("radius", 0.5);
+                let half = (real("height", 2.0) / 2.0 - radius).max(0.0);
+                Shape::Capsule3D {
+                    radius,
+                    a: Vector3::new(0.0, -half, 0.0),
+                    b: Vector3::new(0.0, half, 0.0),
+                }
+            }
+            "CylinderShape" | "CylinderShape3D" => Shape::Cylinder {
+                radius: real("radius", if rtype == "CylinderShape" { 1.0 } else { 0.5 }),
+                height: real("height", 2.0),
+            },
+            "ConvexPolygonShape" | "ConvexPolygonShape3D" => Shape::ConvexMesh {
+                points: vectors3(properties.get("points")),
+            },
+            "ConcavePolygonShape" | "ConcavePolygonShape3D" => Shape::ConcaveMesh {
+                faces: vectors3(properties.get("data"))
+                    .chunks_exact(3)
+                    .map(|face| [face[0], face[1], face[2]])
+                    .collect(),
+            },
+            "RayShape" | "SeparationRayShape3D" => Shape::Ray3D {
+                length: real("length", 1.0),
+            },
+            _ => return Err(format!("Unsupported shape {}", rtype)),
+        };
+
+        Ok(shape)
+    }
+
+    /// Builds shape from `SubResource` / `ExtResource` reference (i.e. CollisionShape2D `shape`)
+    pub fn from_reference(tscn: &Tscn, reference: &VarType) -> Result<Self, String> {
+        let (owner, rtype, properties) = tscn
+            .resource_owner(reference)
+            .ok_or(format!("Missing shape {:?}", reference))?;
+
+        Self::from_properties(rtype, properties, owner.format)
+    }
+
+    pub fn is_3d(&self) -> bool {
+        matches!(
+            self,
+            Shape::Box { .. }
+                | Shape::Sphere { .. }
+                | Shape::Capsule3D { .. }
+                | Shape::Cylinder { .. }
+                | Shape::ConvexMesh { .. }
+                | Shape::ConcaveMesh { .. }
+                | Shape::Ray3D { .. }
+        )
+    }
+
+    pub fn aabb(&self) -> Aabb {
+        let flat = |v: &Vector2<f32>| Vector3::new(v.x, v.y, 0.0);
+
+        match self {
+            Shape::Rectangle { extents } => Aabb::from_points(vec![-flat(extents), flat(extents)]),
+            Shape::Circle { radius } => Aabb::from_points(vec![
+                Vector3::new(-radius, -radius, 0.0),
+                Vector3::new(*radius, *radius, 0.0),
+            ]),
+            Shape::Capsule2D { radius, a, b } => {
+                let aabb = Aabb::from_points(vec![flat(a), flat(b)]).grow(*radius);
+                Aabb {
+                    position: Vector3::new(aabb.position.x, aabb.position.y, 0.0),
+                    size: Vector3::new(aabb.size.x, aabb.size.y, 0.0),
+                }
+            }
+            Shape::ConvexPolygon { points } => Aabb::from_points(points.iter().map(flat)),
+            Shape::ConcavePolygon { segments } => {
+                Aabb::from_points(segments.iter().flatten().map(flat))
+            }
+            Shape::Segment { a, b } => Aabb::from_points(vec![flat(a), flat(b)]),
+            Shape::Ray2D { length } => {
+                Aabb::from_points(vec![Vector3::zeros(), Vector3::new(0.0, *length, 0.0)])
+            }
+            Shape::Box { extents } => Aabb::from_points(vec![-extents, *extents]),
+            Shape::Sphere { radius } => Aabb::from_points(vec![Vector3::zeros()]).grow(*radius),
+            Shape::Capsule3D { radius, a, b } => Aabb::from_points(vec![*a, *b]).grow(*radius),
+            Shape::Cylinder { radius, height } => Aabb::from_points(vec![
+                Vector3::new(-radius, -height / 2.0, -radius),
+                Vector3::new(*radius, height / 2.0, *radius),
+            ]),
+            Shape::ConvexMesh { points } => Aabb::from_points(points.iter().copied()),
+            Shape::ConcaveMesh { faces } => Aabb::from_points(faces.iter().flatten().copied()),
+            Shape::Ray3D { length } => {
+                Aabb::from_points(vec![Vector3::zeros(), Vector3::new(0.0, 0.0, *length)])
+            }
+        }
+    }
+
+    /// 2D bounds as `Rect2` (position, size)
+    pub fn rect(&self) -> [Vector2<f32>; 2] {
+        let aabb = self.aabb();
+
+        [aabb.position.xy(), aabb.size.xy()]
+    }
+
+    /// Whether the point (in the shape's space) is inside, 2D shapes ignore `z`.
+    ///
+    /// Segments, rays and concave polygons are hollow
+    pub fn contains_point(&self, point: Vector3<f32>) -> bool {
+        let point2 = point.xy();
+
+        match self {
+            Shape::Rectangle { extents } => {
+                point2.x.abs() <= extents.x && point2.y.abs() <= extents.y
+            }
+            Shape::Circle { radius } => point2.norm() <= *radius,
+            Shape::Capsule2D { radius, a, b } => {
+                distance_to_segment(point2.push(0.0), a.push(0.0), b.push(0.0)) <= *radius
+            }
+            Shape::ConvexPolygon { points } => polygon_contains(points, point2),
+            Shape::ConcavePolygon { .. } | Shape::Segment { .. } | Shape::Ray2D { .. } => false,
+            Shape::Box { extents } => (0..3).all(|i| point[i].abs() <= extents[i]),
+            Shape::Sphere { radius } => point.norm() <= *radius,
+            Shape::Capsule3D { radius, a, b } => distance_to_segment(point, *a, *b) <= *radius,
+            Shape::Cylinder { radius, height } => {
+                point.y.abs() <= height / 2.0 && point.xz().norm() <= *radius
+            }
+            Shape::ConvexMesh { points } => hull_contains(points, point),
+            Shape::ConcaveMesh { faces } => mesh_contains(faces, point),
+            Shape::Ray3D { .. } => false,
+        }
+    }
+
+    /// Same as `contains_point` for 2D points
+    pub fn contains_point_2d(&self, point: Vector2<f32>) -> bool {
+        self.contains_point(point.push(0.0))
+    }
+}
+
+fn distance_to_segment(point: Vector3<f32>, a: Vector3<f32>, b: Vector3<f32>) -> f32 {
+    let ab = b - a;
+    let length = ab.norm_squared();
+    let t = if length > 0.0 {
+        ((point - a).dot(&ab) / length).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+
+    (a + ab * t - point).norm()
+}
+
+/// Even-odd test, also valid for concave outlines
+fn polygon_contains(points: &[Vector2<f32>], point: Vector2<f32>) -> bool {
+    let mut inside = false;
+    let mut previous = match points.last() {
+        Some(last) => *last,
+        None => return false,
+    };
+
+    for current in points {
+        if (current.y > point.y) != (previous.y > point.y)
+            && point.x
+                < (previous.x - current.x) * (point.y - current.y) / (previous.y - current.y)
+                    + current.x
+        {
+            inside = !inside;
+        }
+        previous = *current;
+    }
+
+    inside
+}
+
+/// Half-space test against the hull faces, i.e. planes through three of the points keeping
+/// all the others on one side. Cubic in the number of points, which stays small for shapes
+fn hull_contains(points: &[Vector3<f32>], point: Vector3<f32>) -> bool {
+    let epsilon = Aabb::from_points(points.iter().copied()).size.norm() * 1e-5;
+    let mut has_face = false;
+
+    for (i, a) in points.iter().enumerate() {
+        for (j, b) in points.iter().enumerate().skip(i + 1) {
+            for c in points.iter().skip(j + 1) {
+                let normal = (b - a).cross(&(c - a));
+                if normal.norm() <= epsilon * epsilon {
+                    continue;
+                }
+                let normal = normal.normalize();
+
+                let (mut above, mut below) = (false, false);
+                for other in points {
+                    let distance = normal.dot(&(other - a));
+                    above |= distance > epsilon;
+                    below |= distance < -epsilon;
+                }
+                // Not a face when the points lie on both sides (or all in the plane)
+                if above == below {
+                    continue;
+                }
+
+                has_face = true;
+                let distance = normal.dot(&(point - a));
+                if (above && distance < -epsilon) || (below && distance > epsilon) {
+                    return false;
+                }
+            }
+        }
+    }
+
+    // Flat hulls have no inside
+    has_face
+}
+
+/// Counts faces crossed by a ray along `+x`, meshes are expected to be closed
+fn mesh_contains(faces: &[[Vector3<f32>; 3]], point: Vector3<f32>) -> bool {
+    let direction = Vector3::x();
+    let crossings = faces
+        .iter()
+        .filter(|[a, b, c]| {
+            // Möller–Trumbore intersection
+            let (edge1, edge2) = (b - a, c - a);
+            let h = direction.cross(&edge2);
+            let det = edge1.dot(&h);
+            if det.abs() < f32::EPSILON {
+                return false;
+            }
+
+            let s = point - a;
+            let u = s.dot(&h) / det;
+            let q = s.cross(&edge1);
+            let v = direction.dot(&q) / det;
+
+            (0.0..=1.0).contains(&u) && v >= 0.0 && u + v <= 1.0 && edge2.dot(&q) / det > 0.0
+        })
+        .count();
+
+    crossings % 2 == 1
+}
+
+fn vectors2(value: Option<&VarType>) -> Vec<Vector2<f32>> {
+    match value {
+        Some(VarType::VectorArr(vectors)) => vectors.clone(),
+        Some(VarType::None(text)) => text
+            .constructor_reals()
+            .chunks_exact(2)
+            .map(|v| Vector2::new(v[0], v[1]))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn vectors3(value: Option<&VarType>) -> Vec<Vector3<f32>> {
+    match value {
+        Some(VarType::None(text)) => text
+            .constructor_reals()
+            .chunks_exact(3)
+            .map(|v| Vector3::new(v[0], v[1], v[2]))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn vector3(value: Option<&VarType>) -> Option<Vector3<f32>> {
+    vectors3(value).first().copied()
+}
+
+impl TryFrom<&Tscn> for Shape {
+    type Error = String;
+
+    fn try_from(tscn: &Tscn) -> Result<Self, Self::Error> {
+        Shape::from_properties(&tscn.rtype, &tscn.resource, tscn.format)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::types::shape::*;
+    use crate::Loader;
+
+    const SCENE: &str = r#"[gd_scene load_steps=5 format=2]
+
+[sub_resource type="RectangleShape2D" id=1]
+extents = Vector2( 8, 4 )
+
+[sub_resource type="ConvexPolygonShape2D" id=2]
+points = PoolVector2Array( 0, 0, 10, 0, 0, 10 )
+
+[sub_resource type="CapsuleShape" id=3]
+radius = 0.5
+height = 2.0
+
+[sub_resource type="ConcavePolygonShape" id=4]
+data = PoolVector3Array( 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 0, 0, 1, 1, 0, 0, 1, 0, 0, 0, 0, 1, 0, 1, 0 )
+
+[node name="Body" type="StaticBody2D"]
+
+[node name="CollisionShape2D" type="CollisionShape2D" parent="."]
+shape = SubResource( 1 )
+"#;
+
+    #[test]
+    fn test_shapes() {
+        let tscn = Loader::new().parse_tscn(SCENE);
+        let sub_resource = |id: usize| {
+            let entry = &tscn.sub_resources[&id];
+            Shape::from_properties(&entry.rtype, &entry.properties, tscn.format).unwrap()
+        };
+
+        let rectangle = Shape::from_reference(&tscn, &tscn.nodes[&1].properties["shape"]).unwrap();
+        assert_eq!(
+            rectangle.rect(),
+            [Vector2::new(-8.0, -4.0), Vector2::new(16.0, 8.0)]
+        );
+        assert!(rectangle.contains_point_2d(Vector2::new(7.0, -3.0)));
+        assert!(!rectangle.contains_point_2d(Vector2::new(7.0, 5.0)));
+
+        let polygon = sub_resource(2);
+        assert!(polygon.contains_point_2d(Vector2::new(2.0, 2.0)));
+        assert!(!polygon.contains_point_2d(Vector2::new(6.0, 6.0)));
+
+        let capsule = sub_resource(3);
+        assert!(capsule.is_3d());
+        assert_eq!(capsule.aabb().size, Vector3::new(1.0, 1.0, 3.0));
+        assert!(capsule.contains_point(Vector3::new(0.0, 0.0, 1.4)));
+        assert!(!capsule.contains_point(Vector3::new(0.4, 0.0, 1.4)));
+
+        // Tetrahedron between the origin and the unit axes
+        let mesh = sub_resource(4);
+        assert_eq!(mesh.aabb().size, Vector3::repeat(1.0));
+        assert!(mesh.contains_point(Vector3::new(0.2, 0.2, 0.2)));
+        assert!(!mesh.contains_point(Vector3::new(0.5, 0.5, 0.5)));
+    }
+
+    #[test]
+    fn test_capsule_and_hull() {
+        let resource = |rtype: &str, format: usize, properties: &str| {
+            let text = format!(
+                "[gd_resource type=\"{}\" format={}]\n\n[resource]\n{}\n",
+                rtype, format, properties
+            );
+            Shape::try_from(&Loader::new().parse_tscn(&text)).unwrap()
+        };
+
+        // Godot 4 height includes the caps
+        let capsule = resource("CapsuleShape2D", 3, "radius = 5.0\nheight = 30.0");
+        assert_eq!(capsule.rect()[1], Vector2::new(10.0, 30.0));
+        let capsule = resource("CapsuleShape2D", 2, "radius = 5.0\nheight = 30.0");
+        assert_eq!(capsule.rect()[1], Vector2::new(10.0, 40.0));
+
+        let hull = resource(
+            "ConvexPolygonShape",
+            2,
+            "points = PoolVector3Array( 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1 )",
+        );
+        assert!(hull.contains_point(Vector3::new(0.2, 0.2, 0.2)));
+        assert!(hull.contains_point(Vector3::new(1.0, 0.0, 0.0)));
+        // Inside the bounding box, but past the slanted face
+        assert!(!hull.contains_point(Vector3::new(0.5, 0.5, 0.5)));
+        assert!(!hull.contains_point(Vector3::new(-0.1, 0.2, 0.2)));
+
+        let flat = resource(
+            "ConvexPolygonShape",
+            2,
+            "points = PoolVector3Array( 0, 0, 0, 1, 0, 0, 0, 1, 0 )",
+        );
+        assert!(!flat.contains_point(Vector3::new(0.2, 0.2, 0.0)));
+    }
+
+    #[test]
+    fn test_godot4_rectangle() {
+        let tscn = Loader::new().parse_tscn(
+            r#"[gd_scene load_steps=2 format=3 uid="uid://dx3l0v7k2m1qa"]
+
+[sub_resource type="RectangleShape2D" id="RectangleShape2D_k8f1w"]
+size = Vector2(16, 8)
+
+[node name="Body" type="StaticBody2D"]
+
+[node name="CollisionShape2D" type="CollisionShape2D" parent="."]
+position = Vector2(4, 0)
+shape = SubResource("RectangleShape2D_k8f1w")
+"#,
+        );
+
+        let rectangle = Shape::from_reference(&tscn, &tscn.nodes[&1].properties["shape"]).unwrap();
+        assert_eq!(
+            rectangle.rect(),
+            [Vector2::new(-8.0, -4.0), Vector2::new(16.0, 8.0)]
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_shape_serde() {
+        let tscn = Loader::new().parse_tscn(SCENE);
+
+        for id in 1..=4 {
+            let entry = &tscn.sub_resources[&id];
+            let shape =
+                Shape::from_properties(&entry.rtype, &entry.properties, tscn.format).unwrap();
+            let encoded = serde_json::to_value(&shape).unwrap();
+            assert_eq!(serde_json::from_value::<Shape>(encoded).unwrap(), shape);
+        }
+
+        let rectangle = Shape::from_reference(&tscn, &tscn.nodes[&1].properties["shape"]).unwrap();
+        assert_eq!(
+            serde_json::to_value(&rectangle).unwrap(),
+            serde_json::json!({ "Rectangle": { "extents": [8.0, 4.0] } })
+        );
+    }
+}
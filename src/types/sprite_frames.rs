@@ -0,0 +1,297 @@
+use crate::types::VarType;
+use crate::{PropertyMap, PropertyTrait, Tscn};
+
+use indexmap::IndexMap;
+use nalgebra::Vector2;
+use std::convert::TryFrom;
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SpriteFrame {
+    /// Texture resource reference
+    pub texture: VarType,
+    /// Path of the image, through AtlasTexture when needed
+    pub path: Option<String>,
+    /// AtlasTexture region (position, size)
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helper::rect2_option"))]
+    pub region: Option<[Vector2<f32>; 2]>,
+    /// Relative duration, always 1 in Godot 3
+    pub duration: f32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SpriteAnimation {
+    pub name: String,
+    pub frames: Vec<SpriteFrame>,
+    /// Frames per second
+    pub speed: f32,
+    pub looping: bool,
+}
+
+/// Animations of `SpriteFrames` resource
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SpriteFrames {
+    pub animations: IndexMap<String, SpriteAnimation>,
+}
+
+impl SpriteAnimation {
+    /// Length in seconds
+    pub fn length(&self) -> f32 {
+        if self.speed <= 0.0 {
+            return 0.0;
+        }
+
+        self.frames.iter().map(|frame| frame.duration).sum::<f32>() / self.speed
+    }
+
+    /// Index of the frame shown at `time` seconds
+    pub fn frame_at(&self, time: f32) -> Option<usize> {
+        let length = self.length();
+        if self.frames.is_empty() || length <= 0.0 {
+            return None;
+        }
+
+        let time = if self.looping {
+            time.rem_euclid(length)
+        } else {
+            time.clamp(0.0, length)
+        };
+
+        let mut end = 0.0;
+        for (i, frame) in self.frames.iter().enumerate() {
+            end += frame.duration / self.speed;
+            if time < end {
+                return Some(i);
+            }
+        }
+
+        Some(self.frames.len() - 1)
+    }
+}
+
+impl SpriteFrames {
+    /// Builds animations from `animations` property, textures are resolved within `tscn`
+    pub fn from_properties(tscn: &Tscn, properties: &PropertyMap) -> Result<Self, String> {
+        let animations = match properties.get("animations") {
+            Some(VarType::ArrMap(animations)) => animations,
+            Some(_) => return Err("Invalid SpriteFrames animations".to_string()),
+            None => return Ok(SpriteFrames::default()),
+        };
+
+        let mut result = IndexMap::new();
+        for animation in animations {
+            let name: String = animation.get_as("name").unwrap_or_default();
+            let frames = match animation.get("frames") {
+                // Godot 3 keeps plain textures
+                Some(VarType::Arr(frames)) => frames
+                    .iter()
+                    .map(|texture| Self::frame(tscn, texture, 1.0))
+                    .collect(),
+                // Godot 4 keeps `{ "duration": 1.0, "texture": ... }`
+                Some(VarType::ArrMap(frames)) => frames
+                    .iter()
+                    .filter_map(|frame| {
+                        let duration = frame.get_as("duration").unwrap_or(1.0);
+                        Some(Self::frame(tscn, frame.get("texture")?, duration))
+                    })
+                    .collect(),
+                _ => Vec::new(),
+            };
+
+            result.insert(
+                name.clone(),
+                SpriteAnimation {
+                    name,
+                    frames,
+                    speed: animation.get_as("speed").unwrap_or(5.0),
+                    looping: animation.get_as("loop").unwrap_or(true),
+                },
+            );
+        }
+
+        Ok(SpriteFrames { animations: result })
+    }
+
+    fn frame(tscn: &Tscn, texture: &VarType, duration: f32) -> SpriteFrame {
+        let mut path = None;
+        let mut region = None;
+
+        let (mut owner, mut reference) = (tscn, texture);
+        // Nested AtlasTextures are not followed
+        for _ in 0..2 {
+            if let VarType::ExtResource(id) = reference {
+                path = owner
+                    .ext_resource_entries
+                    .get(id)
+                    .map(|ext| ext.path.clone());
+            }
+
+            match owner.resource_owner(reference) {
+                Some((atlas_owner, "AtlasTexture", properties)) => {
+                    region = properties.get_as("region");
+                    match properties.get("atlas") {
+                        Some(atlas) => {
+                            owner = atlas_owner;
+                            reference = atlas;
+                        }
+                        None => break,
+                    }
+                }
+                _ => break,
+            }
+        }
+
+        SpriteFrame {
+            texture: texture.clone(),
+            path,
+            region,
+            duration,
+        }
+    }
+}
+
+impl TryFrom<&Tscn> for SpriteFrames {
+    type Error = String;
+
+    /// Reads `SpriteFrames` resource (`.tres`)
+    fn try_from(tscn: &Tscn) -> Result<Self, Self::Error> {
+        if tscn.rtype != "SpriteFrames" {
+            return Err(format!(
+                "Expected SpriteFrames resource, got {}",
+                tscn.rtype
+            ));
+        }
+
+        Self::from_properties(tscn, &tscn.resource)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::nodes::AnimatedSprite;
+    use crate::types::sprite_frames::*;
+    use crate::Loader;
+
+    const SCENE: &str = r#"[gd_scene load_steps=4 format=2]
+
+[ext_resource path="res://player.png" type="Texture" id=1]
+
+[sub_resource type="AtlasTexture" id=1]
+atlas = ExtResource( 1 )
+region = Rect2( 16, 0, 16, 16 )
+
+[sub_resource type="SpriteFrames" id=2]
+animations = [ {
+"frames": [ ExtResource( 1 ), SubResource( 1 ) ],
+"loop": true,
+"name": "idle",
+"speed": 4.0
+}, {
+"frames": [  ],
+"loop": false,
+"name": "run",
+"speed": 8.0
+} ]
+
+[node name="Sprite" type="AnimatedSprite"]
+frames = SubResource( 2 )
+animation = "idle"
+frame = 1
+playing = true
+"#;
+
+    #[test]
+    fn test_sprite_frames() {
+        let tscn = Loader::new().parse_tscn(SCENE);
+        let sprite = AnimatedSprite::from_node(&tscn.nodes[&0]).unwrap();
+        assert!(sprite.playing);
+
+        let frames = sprite.sprite_frames(&tscn).unwrap();
+        assert_eq!(
+            frames.animations.keys().collect::<Vec<_>>(),
+            vec!["idle", "run"]
+        );
+
+        let idle = &frames.animations["idle"];
+        assert_eq!(idle.length(), 0.5);
+        assert_eq!(idle.frame_at(0.3), Some(1));
+        assert_eq!(idle.frame_at(0.6), Some(0));
+        assert!(!frames.animations["run"].looping);
+
+        let frame = sprite.current_frame(&frames).unwrap();
+        assert_eq!(frame.path.as_deref(), Some("res://player.png"));
+        assert_eq!(
+            frame.region,
+            Some([Vector2::new(16.0, 0.0), Vector2::new(16.0, 16.0)])
+        );
+        assert_eq!(idle.frames[0].region, None);
+    }
+
+    const FRAMES: &str = r#"[gd_resource type="SpriteFrames" load_steps=3 format=2]
+
+[ext_resource path="res://enemy.png" type="Texture" id=1]
+
+[sub_resource type="AtlasTexture" id=1]
+atlas = ExtResource( 1 )
+region = Rect2( 0, 32, 16, 16 )
+
+[resource]
+animations = [ {
+"frames": [ SubResource( 1 ) ],
+"loop": true,
+"name": "default",
+"speed": 5.0
+} ]
+"#;
+
+    #[test]
+    fn test_external_sprite_frames() {
+        let mut tscn = Loader::new().parse_tscn(
+            r#"[gd_scene load_steps=3 format=2]
+
+[ext_resource path="res://frames.tres" type="SpriteFrames" id=1]
+
+[ext_resource path="res://player.png" type="Texture" id=2]
+
+[node name="Enemy" type="AnimatedSprite"]
+frames = ExtResource( 1 )
+"#,
+        );
+        tscn.ext_resources
+            .insert(1, Loader::new().parse_tscn(FRAMES));
+
+        let sprite = AnimatedSprite::from_node(&tscn.nodes[&0]).unwrap();
+        let frames = sprite.sprite_frames(&tscn).unwrap();
+        let frame = sprite.current_frame(&frames).unwrap();
+
+        // Atlas and its texture belong to `frames.tres`
+        assert_eq!(frame.texture, VarType::SubResource(1));
+        assert_eq!(frame.path.as_deref(), Some("res://enemy.png"));
+        assert_eq!(
+            frame.region,
+            Some([Vector2::new(0.0, 32.0), Vector2::new(16.0, 16.0)])
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_sprite_frames_serde() {
+        let tscn = Loader::new().parse_tscn(SCENE);
+        let sprite = AnimatedSprite::from_node(&tscn.nodes[&0]).unwrap();
+        let frames = sprite.sprite_frames(&tscn).unwrap();
+
+        let encoded = serde_json::to_value(&frames).unwrap();
+        let idle = &encoded["animations"]["idle"]["frames"];
+        assert_eq!(idle[0]["region"], serde_json::Value::Null);
+        assert_eq!(
+            idle[1]["region"],
+            serde_json::json!({ "position": [16.0, 0.0], "size": [16.0, 16.0] })
+        );
+        assert_eq!(
+            serde_json::from_value::<SpriteFrames>(encoded).unwrap(),
+            frames
+        );
+    }
+}
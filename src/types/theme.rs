@@ -0,0 +1,565 @@
+use crate::types::{Color, VarType};
+use crate::{PropertyMap, PropertyTrait, Tscn};
+
+use nalgebra::Vector2;
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+
+/// Kind of theme item, named by the property group (i.e. `Button/colors/font_color`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ThemeDataType {
+    Color,
+    Constant,
+    Font,
+    /// Godot 4 only
+    FontSize,
+    Icon,
+    Style,
+}
+
+impl ThemeDataType {
+    pub const ALL: [ThemeDataType; 6] = [
+        ThemeDataType::Color,
+        ThemeDataType::Constant,
+        ThemeDataType::Font,
+        ThemeDataType::FontSize,
+        ThemeDataType::Icon,
+        ThemeDataType::Style,
+    ];
+
+    /// Property group of the data type
+    pub fn group(&self) -> &'static str {
+        match self {
+            ThemeDataType::Color => "colors",
+            ThemeDataType::Constant => "constants",
+            ThemeDataType::Font => "fonts",
+            ThemeDataType::FontSize => "font_sizes",
+            ThemeDataType::Icon => "icons",
+            ThemeDataType::Style => "styles",
+        }
+    }
+
+    pub fn from_group(group: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|data_type| data_type.group() == group)
+    }
+}
+
+/// Content margins are `-1` when the stylebox doesn't override them
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StyleBoxFlat {
+    pub content_margins: [f32; 4],
+    pub bg_color: Color,
+    pub draw_center: bool,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helper::vector2"))]
+    pub skew: Vector2<f32>,
+    /// Left, top, right and bottom border widths
+    pub border_widths: [isize; 4],
+    pub border_color: Color,
+    pub border_blend: bool,
+    /// Top left, top right, bottom right and bottom left corner radii
+    pub corner_radii: [isize; 4],
+    pub corner_detail: isize,
+    pub expand_margins: [f32; 4],
+    pub shadow_color: Color,
+    pub shadow_size: isize,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helper::vector2"))]
+    pub shadow_offset: Vector2<f32>,
+    pub anti_aliasing: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StyleBoxTexture {
+    pub content_margins: [f32; 4],
+    /// Texture resource reference
+    pub texture: Option<VarType>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helper::rect2"))]
+    pub region_rect: [Vector2<f32>; 2],
+    /// Nine-patch margins, `margin_*` in Godot 3 and `texture_margin_*` in Godot 4
+    pub texture_margins: [f32; 4],
+    pub expand_margins: [f32; 4],
+    /// Horizontal and vertical stretch modes (stretch, tile or tile fit)
+    pub axis_stretch: [isize; 2],
+    pub modulate_color: Color,
+    pub draw_center: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StyleBoxLine {
+    pub content_margins: [f32; 4],
+    pub color: Color,
+    pub grow_begin: f32,
+    pub grow_end: f32,
+    pub thickness: isize,
+    pub vertical: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StyleBox {
+    Empty {
+        content_margins: [f32; 4],
+    },
+    Flat(StyleBoxFlat),
+    Texture(StyleBoxTexture),
+    Line(StyleBoxLine),
+    /// Reference to a resource which is not loaded
+    Unresolved(VarType),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ThemeItem {
+    Color(Color),
+    Constant(isize),
+    /// Font resource reference
+    Font(VarType),
+    FontSize(isize),
+    /// Texture resource reference
+    Icon(VarType),
+    Style(StyleBox),
+}
+
+/// Godot's `Theme` resource, items are sorted by theme type, data type and name
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Theme {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helper::map_entries"))]
+    items: BTreeMap<(String, ThemeDataType, String), ThemeItem>,
+    /// Base types of type variations (Godot 4 `<type>/base_type`)
+    pub base_types: HashMap<String, String>,
+    pub default_font: Option<VarType>,
+    pub default_font_size: Option<isize>,
+    pub default_base_scale: Option<f32>,
+}
+
+const SIDES: [&str; 4] = ["left", "top", "right", "bottom"];
+
+impl StyleBox {
+    /// Builds stylebox of `rtype`, `None` when it isn't a StyleBox type
+    pub fn from_properties(rtype: &str, properties: &PropertyMap) -> Option<Self> {
+        let real = |key: &str, default: f32| properties.get_as(key).unwrap_or(default);
+        let int = |key: &str, default: isize| properties.get_as(key).unwrap_or(default);
+        let flag = |key: &str, default: bool| properties.get_as(key).unwrap_or(default);
+        let color = |key: &str, default: Color| {
+            properties
+                .get(key)
+                .and_then(|color| Color::try_from(color).ok())
+                .unwrap_or(default)
+        };
+        let vector = |key: &str| properties.get_as(key).unwrap_or_else(Vector2::zeros);
+        let sides = |prefix: &str, default: f32| {
+            SIDES.map(|side| real(&format!("{}_{}", prefix, side), default))
+        };
+        let content_margins = sides("content_margin", -1.0);
+
+        Some(match rtype {
+            "StyleBoxEmpty" => StyleBox::Empty { content_margins },
+            "StyleBoxFlat" => StyleBox::Flat(StyleBoxFlat {
+                content_margins,
+                bg_color: color("bg_color", Color::new(0.6, 0.6, 0.6, 1.0)),
+                draw_center: flag("draw_center", true),
+                skew: vector("skew"),
+                border_widths: SIDES.map(|side| int(&format!("border_width_{}", side), 0)),
+                border_color: color("border_color", Color::new(0.8, 0.8, 0.8, 1.0)),
+                border_blend: flag("border_blend", false),
+                corner_radii: ["top_left", "top_right", "bottom_right", "bottom_left"]
+                    .map(|corner| int(&format!("corner_radius_{}", corner), 0)),
+                corner_detail: int("corner_detail", 8),
+                expand_margins: sides("expand_margin", 0.0),
+                shadow_color: color("shadow_color", Color::new(0.0, 0.0, 0.0, 0.6)),
+                shadow_size: int("shadow_size", 0),
+                shadow_offset: vector("shadow_offset"),
+                anti_aliasing: flag("anti_aliasing", true),
+            }),
+            "StyleBoxTexture" => StyleBox::Texture(StyleBoxTexture {
+                content_margins,
+                texture: properties.get("texture").cloned(),
+                region_rect: properties
+                    .get_as("region_rect")
+                    .unwrap_or([Vector2::zeros(), Vector2::zeros()]),
+                texture_margins: SIDES.map(|side| {
+                    properties
+                        .get_as(&format!("texture_margin_{}", side))
+                        .unwrap_or_else(|| real(&format!("margin_{}", side), 0.0))
+                }),
+                expand_margins: sides("expand_margin", 0.0),
+                axis_stretch: [
+                    int("axis_stretch_horizontal", 0),
+                    int("axis_stretch_vertical", 0),
+                ],
+                modulate_color: color("modulate_color", Color::new(1.0, 1.0, 1.0, 1.0)),
+                draw_center: flag("draw_center", true),
+            }),
+            "StyleBoxLine" => StyleBox::Line(StyleBoxLine {
+                content_margins,
+                color: color("color", Color::new(0.0, 0.0, 0.0, 1.0)),
+                grow_begin: real("grow_begin", 1.0),
+                grow_end: real("grow_end", 1.0),
+                thickness: int("thickness", 1),
+                vertical: flag("vertical", false),
+            }),
+            _ => return None,
+        })
+    }
+
+    /// Resolves `SubResource` or `ExtResource` stylebox within `tscn`
+    pub fn from_reference(tscn: &Tscn, reference: &VarType) -> Result<Self, String> {
+        match tscn.resource_properties(reference) {
+            Some((rtype, properties)) => Self::from_properties(rtype, properties)
+                .ok_or(format!("Expected StyleBox resource, got {}", rtype)),
+            None => Ok(StyleBox::Unresolved(reference.clone())),
+        }
+    }
+
+    /// Left, top, right and bottom content margins, `-1` when not overridden
+    pub fn content_margins(&self) -> Option<[f32; 4]> {
+        match self {
+            StyleBox::Empty { content_margins } => Some(*content_margins),
+            StyleBox::Flat(style) => Some(style.content_margins),
+            StyleBox::Texture(style) => Some(style.content_margins),
+            StyleBox::Line(style) => Some(style.content_margins),
+            StyleBox::Unresolved(_) => None,
+        }
+    }
+}
+
+impl ThemeItem {
+    /// Reads item value, resources are resolved within `tscn`. `None` for empty (`null`)
+    /// or mismatching values
+    pub fn from_value(tscn: &Tscn, data_type: ThemeDataType, value: &VarType) -> Option<Self> {
+        let reference = match value {
+            VarType::SubResource(_) | VarType::ExtResource(_) => Some(value.clone()),
+            _ => None,
+        };
+
+        match data_type {
+            ThemeDataType::Color => Color::try_from(value).ok().map(ThemeItem::Color),
+            ThemeDataType::Constant => isize::try_from(value).ok().map(ThemeItem::Constant),
+            ThemeDataType::FontSize => isize::try_from(value).ok().map(ThemeItem::FontSize),
+            ThemeDataType::Font => reference.map(ThemeItem::Font),
+            ThemeDataType::Icon => reference.map(ThemeItem::Icon),
+            ThemeDataType::Style => StyleBox::from_reference(tscn, reference.as_ref()?)
+                .ok()
+                .map(ThemeItem::Style),
+        }
+    }
+}
+
+impl Theme {
+    /// Builds theme from `<type>/<data type>/<name>` properties (nested by `insert_to`),
+    /// resources are resolved within `tscn`
+    pub fn from_properties(tscn: &Tscn, properties: &PropertyMap) -> Self {
+        let mut theme = Theme {
+            default_font: properties
+                .get("default_font")
+                .filter(|font| !matches!(font, VarType::None(_)))
+                .cloned(),
+            default_font_size: properties.get_as("default_font_size"),
+            default_base_scale: properties.get_as("default_base_scale"),
+            ..Theme::default()
+        };
+
+        for (theme_type, value) in properties {
+            let groups = match value {
+                VarType::Map(groups) => groups,
+                _ => continue,
+            };
+
+            for (group, items) in groups {
+                if group == "base_type" {
+                    if let Some(name) = items.string_name() {
+                        theme
+                            .base_types
+                            .insert(theme_type.clone(), name.to_string());
+                    }
+                    continue;
+                }
+
+                let (data_type, items) = match (ThemeDataType::from_group(group), items) {
+                    (Some(data_type), VarType::Map(items)) => (data_type, items),
+                    _ => continue,
+                };
+
+                for (name, value) in items {
+                    if let Some(item) = ThemeItem::from_value(tscn, data_type, value) {
+                        theme.set_item(data_type, name, theme_type, item);
+                    }
+                }
+            }
+        }
+
+        theme
+    }
+
+    /// Resolves `SubResource` or `ExtResource` theme, ext resources must have been loaded
+    pub fn from_reference(tscn: &Tscn, reference: &VarType) -> Result<Self, String> {
+        match reference {
+            VarType::ExtResource(id) => Theme::try_from(
+                tscn.ext_resources
+                    .get(id)
+                    .ok_or("Theme resource is not loaded")?,
+            ),
+            VarType::SubResource(id) => {
+                let sub = tscn
+                    .sub_resources
+                    .get(id)
+                    .ok_or(format!("Missing sub resource {}", id))?;
+                if sub.rtype != "Theme" {
+                    return Err(format!("Expected Theme resource, got {}", sub.rtype));
+                }
+
+                Ok(Theme::from_properties(tscn, &sub.properties))
+            }
+            _ => Err("Invalid Theme reference".to_string()),
+        }
+    }
+
+    pub fn get_item(
+        &self,
+        data_type: ThemeDataType,
+        name: &str,
+        theme_type: &str,
+    ) -> Option<&ThemeItem> {
+        self.items
+            .get(&(theme_type.to_string(), data_type, name.to_string()))
+    }
+
+    pub fn has_item(&self, data_type: ThemeDataType, name: &str, theme_type: &str) -> bool {
+        self.get_item(data_type, name, theme_type).is_some()
+    }
+
+    pub fn set_item(
+        &mut self,
+        data_type: ThemeDataType,
+        name: &str,
+        theme_type: &str,
+        item: ThemeItem,
+    ) {
+        self.items
+            .insert((theme_type.to_string(), data_type, name.to_string()), item);
+    }
+
+    pub fn clear_item(
+        &mut self,
+        data_type: ThemeDataType,
+        name: &str,
+        theme_type: &str,
+    ) -> Option<ThemeItem> {
+        self.items
+            .remove(&(theme_type.to_string(), data_type, name.to_string()))
+    }
+
+    /// Sorted theme types which have items
+    pub fn get_type_list(&self) -> Vec<&str> {
+        let mut types: Vec<&str> = self.items.keys().map(|key| key.0.as_str()).collect();
+        types.dedup();
+        types
+    }
+
+    /// Sorted item names of `data_type` in `theme_type`
+    pub fn get_item_list(&self, data_type: ThemeDataType, theme_type: &str) -> Vec<&str> {
+        self.items
+            .keys()
+            .filter(|key| key.0 == theme_type && key.1 == data_type)
+            .map(|key| key.2.as_str())
+            .collect()
+    }
+
+    /// Type variation (or type) followed by its base types
+    pub fn type_chain(&self, theme_type: &str) -> Vec<String> {
+        let mut chain = vec![theme_type.to_string()];
+        while let Some(base) = self.base_types.get(chain.last().unwrap()) {
+            if chain.contains(base) {
+                break;
+            }
+            chain.push(base.clone());
+        }
+
+        chain
+    }
+
+    pub fn get_color(&self, name: &str, theme_type: &str) -> Option<Color> {
+        match self.get_item(ThemeDataType::Color, name, theme_type)? {
+            ThemeItem::Color(color) => Some(*color),
+            _ => None,
+        }
+    }
+
+    pub fn get_constant(&self, name: &str, theme_type: &str) -> Option<isize> {
+        match self.get_item(ThemeDataType::Constant, name, theme_type)? {
+            ThemeItem::Constant(constant) => Some(*constant),
+            _ => None,
+        }
+    }
+
+    pub fn get_font_size(&self, name: &str, theme_type: &str) -> Option<isize> {
+        match self.get_item(ThemeDataType::FontSize, name, theme_type)? {
+            ThemeItem::FontSize(size) => Some(*size),
+            _ => None,
+        }
+    }
+
+    pub fn get_font(&self, name: &str, theme_type: &str) -> Option<&VarType> {
+        match self.get_item(ThemeDataType::Font, name, theme_type)? {
+            ThemeItem::Font(font) => Some(font),
+            _ => None,
+        }
+    }
+
+    pub fn get_icon(&self, name: &str, theme_type: &str) -> Option<&VarType> {
+        match self.get_item(ThemeDataType::Icon, name, theme_type)? {
+            ThemeItem::Icon(icon) => Some(icon),
+            _ => None,
+        }
+    }
+
+    pub fn get_stylebox(&self, name: &str, theme_type: &str) -> Option<&StyleBox> {
+        match self.get_item(ThemeDataType::Style, name, theme_type)? {
+            ThemeItem::Style(style) => Some(style),
+            _ => None,
+        }
+    }
+}
+
+impl TryFrom<&Tscn> for Theme {
+    type Error = String;
+
+    /// Reads `Theme` resource (`.tres`)
+    fn try_from(tscn: &Tscn) -> Result<Self, Self::Error> {
+        if tscn.rtype != "Theme" {
+            return Err(format!("Expected Theme resource, got {}", tscn.rtype));
+        }
+
+        Ok(Theme::from_properties(tscn, &tscn.resource))
+    }
+}
+
+impl TryFrom<&Tscn> for StyleBox {
+    type Error = String;
+
+    /// Reads StyleBox resource (`.tres`)
+    fn try_from(tscn: &Tscn) -> Result<Self, Self::Error> {
+        StyleBox::from_properties(&tscn.rtype, &tscn.resource)
+            .ok_or(format!("Expected StyleBox resource, got {}", tscn.rtype))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::nodes::Control;
+    use crate::types::theme::*;
+    use crate::Loader;
+
+    const SCENE: &str = r#"[gd_scene load_steps=4 format=2]
+
+[ext_resource path="res://font.tres" type="DynamicFont" id=1]
+
+[sub_resource type="StyleBoxFlat" id=1]
+bg_color = Color( 0.1, 0.2, 0.3, 1 )
+corner_radius_top_left = 4
+content_margin_left = 8.0
+
+[sub_resource type="Theme" id=2]
+default_font = ExtResource( 1 )
+Button/colors/font_color = Color( 1, 0, 0, 1 )
+Button/constants/hseparation = 2
+Button/fonts/font = null
+Button/styles/normal = SubResource( 1 )
+Label/colors/font_color = Color( 0, 1, 0, 1 )
+BoxContainer/constants/separation = 6
+
+[node name="Menu" type="Control"]
+theme = SubResource( 2 )
+
+[node name="Items" type="VBoxContainer" parent="."]
+
+[node name="Play" type="Button" parent="Items"]
+custom_colors/font_color = Color( 0, 0, 1, 1 )
+
+[node name="Quit" type="Button" parent="Items"]
+
+[node name="Mute" type="CheckBox" parent="Items"]
+
+[node name="World" type="Node2D" parent="."]
+
+[node name="Score" type="Label" parent="World"]
+"#;
+
+    #[test]
+    fn test_theme() {
+        let tscn = Loader::new().parse_tscn(SCENE);
+        let theme = Theme::from_reference(&tscn, &VarType::SubResource(2)).unwrap();
+        assert_eq!(
+            theme.get_type_list(),
+            vec!["BoxContainer", "Button", "Label"]
+        );
+        assert_eq!(
+            theme.get_item_list(ThemeDataType::Color, "Button"),
+            vec!["font_color"]
+        );
+        assert!(!theme.has_item(ThemeDataType::Font, "font", "Button"));
+
+        let style = match theme.get_stylebox("normal", "Button") {
+            Some(StyleBox::Flat(style)) => style,
+            style => panic!("Unexpected stylebox {:?}", style),
+        };
+        assert_eq!(style.bg_color, Color::new(0.1, 0.2, 0.3, 1.0));
+        assert_eq!(style.corner_radii, [4, 0, 0, 0]);
+        assert_eq!(style.content_margins, [8.0, -1.0, -1.0, -1.0]);
+
+        let item = |id: usize, data_type: ThemeDataType, name: &str| {
+            Control::theme_item(&tscn, id, data_type, name)
+        };
+        let color = |r: f32, g: f32, b: f32| Some(ThemeItem::Color(Color::new(r, g, b, 1.0)));
+
+        assert_eq!(
+            item(2, ThemeDataType::Color, "font_color"),
+            color(0.0, 0.0, 1.0)
+        );
+        assert_eq!(
+            item(3, ThemeDataType::Color, "font_color"),
+            color(1.0, 0.0, 0.0)
+        );
+        assert_eq!(
+            item(3, ThemeDataType::Font, "font"),
+            Some(ThemeItem::Font(VarType::ExtResource(1)))
+        );
+        assert!(matches!(
+            item(3, ThemeDataType::Style, "normal"),
+            Some(ThemeItem::Style(StyleBox::Flat(_)))
+        ));
+        assert_eq!(item(3, ThemeDataType::Constant, "vseparation"), None);
+        // The theme doesn't propagate through Node2D
+        assert_eq!(item(6, ThemeDataType::Color, "font_color"), None);
+
+        // Items of parent classes apply: VBoxContainer to BoxContainer, CheckBox to Button
+        assert_eq!(
+            item(1, ThemeDataType::Constant, "separation"),
+            Some(ThemeItem::Constant(6))
+        );
+        assert_eq!(
+            item(4, ThemeDataType::Color, "font_color"),
+            color(1.0, 0.0, 0.0)
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_theme_serde() {
+        let tscn = Loader::new().parse_tscn(SCENE);
+        let theme = Theme::from_reference(&tscn, &VarType::SubResource(2)).unwrap();
+        let encoded = serde_json::to_value(&theme).unwrap();
+        assert_eq!(
+            encoded["items"][0],
+            serde_json::json!([["BoxContainer", "Constant", "separation"], { "Constant": 6 }])
+        );
+        assert_eq!(serde_json::from_value::<Theme>(encoded).unwrap(), theme);
+    }
+}
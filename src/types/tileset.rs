@@ -0,0 +1,476 @@
+use crate::types::{Transform2D, VarType};
+use crate::{PropertyMap, PropertyTrait, Tscn};
+
+use indexmap::IndexMap;
+use nalgebra::Vector2;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+/// Subtile coordinate inside of autotile / atlas
+pub type TileCoord = (i32, i32);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TileMode {
+    Single,
+    Auto,
+    Atlas,
+}
+
+impl TryFrom<isize> for TileMode {
+    type Error = ();
+
+    fn try_from(value: isize) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(TileMode::Single),
+            1 => Ok(TileMode::Auto),
+            2 => Ok(TileMode::Atlas),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BitmaskMode {
+    TwoByTwo,
+    ThreeByThreeMinimal,
+    ThreeByThree,
+}
+
+impl TryFrom<isize> for BitmaskMode {
+    type Error = ();
+
+    fn try_from(value: isize) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(BitmaskMode::TwoByTwo),
+            1 => Ok(BitmaskMode::ThreeByThreeMinimal),
+            2 => Ok(BitmaskMode::ThreeByThree),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Autotile {
+    pub bitmask_mode: BitmaskMode,
+    /// Bitmask of each subtile, see `Autotile::TOP_LEFT` and others
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helper::coord_map"))]
+    pub bitmask_flags: HashMap<TileCoord, u32>,
+    pub icon_coordinate: TileCoord,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helper::vector2"))]
+    pub tile_size: Vector2<f32>,
+    pub spacing: isize,
+}
+
+impl Autotile {
+    pub const TOP_LEFT: u32 = 1;
+    pub const TOP: u32 = 2;
+    pub const TOP_RIGHT: u32 = 4;
+    pub const LEFT: u32 = 8;
+    pub const CENTER: u32 = 16;
+    pub const RIGHT: u32 = 32;
+    pub const BOTTOM_LEFT: u32 = 64;
+    pub const BOTTOM: u32 = 128;
+    pub const BOTTOM_RIGHT: u32 = 256;
+
+    /// Region of subtile within the texture, given region of the whole tile
+    pub fn subtile_region(
+        &self,
+        region: &[Vector2<f32>; 2],
+        coord: TileCoord,
+    ) -> [Vector2<f32>; 2] {
+        let step = self.tile_size.add_scalar(self.spacing as f32);
+
+        [
+            region[0] + Vector2::new(coord.0 as f32 * step.x, coord.1 as f32 * step.y),
+            self.tile_size,
+        ]
+    }
+
+    /// Subtiles with exactly matching bitmask, sorted by coordinate
+    pub fn find_subtiles(&self, bitmask: u32) -> Vec<TileCoord> {
+        let mut found: Vec<TileCoord> = self
+            .bitmask_flags
+            .iter()
+            .filter(|(_, flags)| **flags == bitmask)
+            .map(|(coord, _)| *coord)
+            .collect();
+
+        found.sort_by_key(|(x, y)| (*y, *x));
+        found
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TileShape {
+    /// Shape resource reference (i.e. `SubResource( 1 )`)
+    pub shape: VarType,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helper::transform2d"))]
+    pub transform: Transform2D,
+    pub one_way: bool,
+    pub one_way_margin: f32,
+    pub autotile_coord: TileCoord,
+}
+
+/// Occluder or navigation polygon of a (sub)tile
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TilePolygon {
+    /// Polygon resource reference (i.e. `SubResource( 2 )`)
+    pub polygon: VarType,
+    pub autotile_coord: TileCoord,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Tile {
+    pub id: usize,
+    pub name: String,
+    /// Texture resource reference (i.e. `ExtResource( 1 )`)
+    pub texture: Option<VarType>,
+    pub normal_map: Option<VarType>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helper::vector2"))]
+    pub tex_offset: Vector2<f32>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helper::rect2"))]
+    pub region: [Vector2<f32>; 2],
+    pub tile_mode: TileMode,
+    /// Autotile settings, present for auto and atlas tiles
+    pub autotile: Option<Autotile>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helper::vector2"))]
+    pub shape_offset: Vector2<f32>,
+    pub shapes: Vec<TileShape>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helper::vector2"))]
+    pub occluder_offset: Vector2<f32>,
+    pub occluders: Vec<TilePolygon>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helper::vector2"))]
+    pub navigation_offset: Vector2<f32>,
+    pub navigations: Vec<TilePolygon>,
+    pub z_index: isize,
+}
+
+impl Tile {
+    /// Builds tile from its properties (everything under `<id>/`)
+    pub fn from_properties(id: usize, properties: &PropertyMap) -> Self {
+        let tile_mode = properties
+            .get_as::<isize>("tile_mode")
+            .and_then(|mode| TileMode::try_from(mode).ok())
+            .unwrap_or(TileMode::Single);
+
+        let autotile = if tile_mode != TileMode::Single {
+            Some(Autotile {
+                bitmask_mode: properties
+                    .get_as::<isize>("autotile/bitmask_mode")
+                    .and_then(|mode| BitmaskMode::try_from(mode).ok())
+                    .unwrap_or(BitmaskMode::TwoByTwo),
+                bitmask_flags: Self::pairs(properties.get_from("autotile/bitmask_flags"))
+                    .into_iter()
+                    .filter_map(|(coord, flags)| {
+                        let flags = isize::try_from(flags).ok()?;
+                        Some((coord, flags as u32))
+                    })
+                    .collect(),
+                icon_coordinate: properties
+                    .get_as::<Vector2<f32>>("autotile/icon_coordinate")
+                    .map(Self::coord)
+                    .unwrap_or((0, 0)),
+                tile_size: properties
+                    .get_as("autotile/tile_size")
+                    .unwrap_or_else(|| Vector2::new(64.0, 64.0)),
+                spacing: properties.get_as("autotile/spacing").unwrap_or(0),
+            })
+        } else {
+            None
+        };
+
+        Tile {
+            id,
+            name: properties.get_as("name").unwrap_or_default(),
+            texture: properties.get_from("texture").cloned(),
+            normal_map: properties.get_from("normal_map").cloned(),
+            tex_offset: properties
+                .get_as("tex_offset")
+                .unwrap_or_else(Vector2::zeros),
+            region: properties
+                .get_as("region")
+                .unwrap_or_else(|| [Vector2::zeros(), Vector2::zeros()]),
+            tile_mode,
+            autotile,
+            shape_offset: properties
+                .get_as("shape_offset")
+                .unwrap_or_else(Vector2::zeros),
+            shapes: Self::shapes(properties),
+            occluder_offset: properties
+                .get_as("occluder_offset")
+                .unwrap_or_else(Vector2::zeros),
+            occluders: Self::polygons(properties, "occluder", "autotile/occluder_map"),
+            navigation_offset: properties
+                .get_as("navigation_offset")
+                .unwrap_or_else(Vector2::zeros),
+            navigations: Self::polygons(properties, "navigation", "autotile/navpoly_map"),
+            z_index: properties.get_as("z_index").unwrap_or(0),
+        }
+    }
+
+    /// Path of the texture, when it is an external resource of `tscn`
+    pub fn texture_path<'a>(&self, tscn: &'a Tscn) -> Option<&'a str> {
+        if let Some(VarType::ExtResource(id)) = self.texture {
+            tscn.ext_resource_entries
+                .get(&id)
+                .map(|ext| ext.path.as_str())
+        } else {
+            None
+        }
+    }
+
+    /// Collision shapes of subtile (single tiles use `(0, 0)`)
+    pub fn shapes_at(&self, coord: TileCoord) -> impl Iterator<Item = &TileShape> {
+        self.shapes
+            .iter()
+            .filter(move |shape| shape.autotile_coord == coord)
+    }
+
+    /// Placement of the shape relative to the top left corner of the cell
+    pub fn shape_placement(&self, shape: &TileShape) -> Transform2D {
+        Transform2D::new_translation(&self.shape_offset) * shape.transform
+    }
+
+    fn shapes(properties: &PropertyMap) -> Vec<TileShape> {
+        if let Some(VarType::ArrMap(shapes)) = properties.get_from("shapes") {
+            return shapes
+                .iter()
+                .filter_map(|shape| {
+                    Some(TileShape {
+                        shape: shape.get("shape")?.clone(),
+                        transform: shape
+                            .get_as("shape_transform")
+                            .unwrap_or_else(Transform2D::identity),
+                        one_way: shape.get_as("one_way").unwrap_or(false),
+                        one_way_margin: Self::margin(shape.get("one_way_margin")),
+                        autotile_coord: shape
+                            .get_as::<Vector2<f32>>("autotile_coord")
+                            .map(Self::coord)
+                            .unwrap_or((0, 0)),
+                    })
+                })
+                .collect();
+        }
+
+        // Older files only have the single `shape` of the tile
+        match properties.get_from("shape") {
+            Some(shape) if shape != &VarType::None("null".to_string()) => vec![TileShape {
+                shape: shape.clone(),
+                transform: properties
+                    .get_as("shape_transform")
+                    .unwrap_or_else(Transform2D::identity),
+                one_way: properties.get_as("shape_one_way").unwrap_or(false),
+                one_way_margin: Self::margin(properties.get_from("shape_one_way_margin")),
+                autotile_coord: (0, 0),
+            }],
+            _ => Vec::new(),
+        }
+    }
+
+    fn polygons(properties: &PropertyMap, single: &str, map: &str) -> Vec<TilePolygon> {
+        let mut polygons: Vec<TilePolygon> = Self::pairs(properties.get_from(map))
+            .into_iter()
+            .map(|(coord, polygon)| TilePolygon {
+                polygon: polygon.clone(),
+                autotile_coord: coord,
+            })
+            .collect();
+
+        if let Some(polygon) = properties.get_from(single) {
+            if polygons.is_empty() && polygon != &VarType::None("null".to_string()) {
+                polygons.push(TilePolygon {
+                    polygon: polygon.clone(),
+                    autotile_coord: (0, 0),
+                });
+            }
+        }
+
+        polygons
+    }
+
+    /// Reads `[ Vector2( x, y ), value, ... ]` arrays
+    fn pairs(value: Option<&VarType>) -> Vec<(TileCoord, &VarType)> {
+        match value {
+            Some(VarType::Arr(arr)) => arr
+                .chunks(2)
+                .filter_map(|pair| match pair {
+                    [VarType::Vector(coord), value] => Some((Self::coord(*coord), value)),
+                    _ => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Whole margins are saved as integers (i.e. `"one_way_margin": 1`)
+    fn margin(value: Option<&VarType>) -> f32 {
+        match value {
+            Some(VarType::Num(margin)) => *margin as f32,
+            Some(VarType::Float(margin)) => *margin,
+            _ => 1.0,
+        }
+    }
+
+    fn coord(vector: Vector2<f32>) -> TileCoord {
+        (vector.x as i32, vector.y as i32)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TileSet {
+    /// Tiles sorted by id
+    pub tiles: IndexMap<usize, Tile>,
+}
+
+impl TileSet {
+    /// Builds tile set from resource properties (i.e. `0/name`, `0/region`)
+    pub fn from_properties(properties: &PropertyMap) -> Self {
+        let mut tiles: Vec<Tile> = properties
+            .iter()
+            .filter_map(|(key, value)| match value {
+                VarType::Map(tile) => Some(Tile::from_properties(key.parse().ok()?, tile)),
+                _ => None,
+            })
+            .collect();
+        tiles.sort_by_key(|tile| tile.id);
+
+        TileSet {
+            tiles: tiles.into_iter().map(|tile| (tile.id, tile)).collect(),
+        }
+    }
+
+    pub fn find_by_name(&self, name: &str) -> Option<&Tile> {
+        self.tiles.values().find(|tile| tile.name == name)
+    }
+}
+
+resource_try_from!(TileSet, "TileSet");
+
+#[cfg(test)]
+mod tests {
+    use crate::types::tileset::*;
+    use crate::types::Transform2DTrait;
+    use crate::Loader;
+
+    const TILESET: &str = r#"[gd_resource type="TileSet" load_steps=4 format=2]
+
+[ext_resource path="res://tiles.png" type="Texture" id=1]
+
+[sub_resource type="RectangleShape2D" id=1]
+extents = Vector2( 16, 16 )
+
+[sub_resource type="OccluderPolygon2D" id=2]
+polygon = PoolVector2Array( 0, 0, 32, 0, 32, 32, 0, 32 )
+
+[resource]
+0/name = "wall"
+0/texture = ExtResource( 1 )
+0/tex_offset = Vector2( 0, 0 )
+0/region = Rect2( 0, 0, 64, 32 )
+0/tile_mode = 1
+0/autotile/bitmask_mode = 0
+0/autotile/bitmask_flags = [ Vector2( 0, 0 ), 432, Vector2( 1, 0 ), 438 ]
+0/autotile/icon_coordinate = Vector2( 1, 0 )
+0/autotile/tile_size = Vector2( 32, 32 )
+0/autotile/spacing = 0
+0/autotile/occluder_map = [ Vector2( 1, 0 ), SubResource( 2 ) ]
+0/autotile/navpoly_map = [  ]
+0/occluder_offset = Vector2( 0, 0 )
+0/navigation_offset = Vector2( 0, 0 )
+0/shape_offset = Vector2( 0, 0 )
+0/shape_transform = Transform2D( 1, 0, 0, 1, 0, 0 )
+0/shape = SubResource( 1 )
+0/shape_one_way = false
+0/shape_one_way_margin = 1.0
+0/shapes = [ {
+"autotile_coord": Vector2( 1, 0 ),
+"one_way": false,
+"one_way_margin": 2,
+"shape": SubResource( 1 ),
+"shape_transform": Transform2D( 1, 0, 0, 1, 16, 16 )
+} ]
+0/z_index = 2
+1/name = "floor"
+1/texture = ExtResource( 1 )
+1/region = Rect2( 64, 0, 32, 32 )
+1/tile_mode = 0
+1/occluder_offset = Vector2( 0, 0 )
+1/navigation_offset = Vector2( 0, 0 )
+1/shape_offset = Vector2( 0, 0 )
+1/shape_transform = Transform2D( 1, 0, 0, 1, 0, 0 )
+1/shapes = [  ]
+1/z_index = 0
+2/name = "ground"
+2/texture = ExtResource( 1 )
+2/region = Rect2( 0, 64, 128, 64 )
+2/tile_mode = 2
+"#;
+
+    #[test]
+    fn test_tileset() {
+        let tscn = Loader::new().parse_tscn(TILESET);
+        let tileset = TileSet::try_from(&tscn).unwrap();
+        assert_eq!(
+            tileset.tiles.keys().copied().collect::<Vec<usize>>(),
+            vec![0, 1, 2]
+        );
+
+        let wall = tileset.find_by_name("wall").unwrap();
+        let autotile = wall.autotile.as_ref().unwrap();
+        assert_eq!(wall.tile_mode, TileMode::Auto);
+        assert_eq!(wall.texture_path(&tscn), Some("res://tiles.png"));
+        assert_eq!(wall.z_index, 2);
+        assert_eq!(autotile.bitmask_flags[&(1, 0)], 438);
+        assert_eq!(autotile.find_subtiles(432), vec![(0, 0)]);
+        assert_eq!(
+            autotile.subtile_region(&wall.region, (1, 0))[0],
+            Vector2::new(32.0, 0.0)
+        );
+        assert_eq!(wall.occluders[0].polygon, VarType::SubResource(2));
+        assert!(wall.navigations.is_empty());
+
+        let shape = wall.shapes_at((1, 0)).next().unwrap();
+        assert_eq!(shape.shape, VarType::SubResource(1));
+        assert_eq!(shape.one_way_margin, 2.0);
+        assert_eq!(
+            wall.shape_placement(shape).get_origin(),
+            Vector2::new(16.0, 16.0)
+        );
+
+        let floor = &tileset.tiles[&1];
+        assert_eq!(floor.tile_mode, TileMode::Single);
+        assert!(floor.autotile.is_none() && floor.shapes.is_empty());
+
+        // Godot doesn't save the default 64x64 subtile size
+        let ground = &tileset.tiles[&2];
+        let atlas = ground.autotile.as_ref().unwrap();
+        assert_eq!(atlas.tile_size, Vector2::new(64.0, 64.0));
+        assert_eq!(
+            atlas.subtile_region(&ground.region, (1, 0))[0],
+            Vector2::new(64.0, 64.0)
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_tileset_serde() {
+        let tileset = TileSet::try_from(&Loader::new().parse_tscn(TILESET)).unwrap();
+        let encoded = serde_json::to_value(&tileset).unwrap();
+        assert_eq!(
+            encoded["tiles"]["0"]["autotile"]["bitmask_flags"],
+            serde_json::json!([[[0, 0], 432], [[1, 0], 438]])
+        );
+        assert_eq!(
+            encoded["tiles"]["0"]["tex_offset"],
+            serde_json::json!([0.0, 0.0])
+        );
+        assert_eq!(serde_json::from_value::<TileSet>(encoded).unwrap(), tileset);
+    }
+}
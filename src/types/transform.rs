@@ -0,0 +1,256 @@
+use nalgebra::{Matrix3, Matrix4, Point2, Point3, Vector2, Vector3};
+
+/// 2D affine transform, columns are Godot's `x`, `y` and `origin`
+pub type Transform2D = Matrix3<f32>;
+
+/// 3D affine transform, columns are Godot's basis `x`, `y`, `z` and `origin`
+pub type Transform3D = Matrix4<f32>;
+
+pub trait Transform2DTrait {
+    /// Builds transform from Godot's `x.x, x.y, y.x, y.y, origin.x, origin.y` order
+    fn from_reals(reals: [f32; 6]) -> Self;
+    /// Rotation (in radians) and origin only transform
+    fn from_rotation(rotation: f32, origin: Vector2<f32>) -> Self;
+    /// Transform of Node2D `position`, `rotation` (in radians), `scale` and `skew` (in radians)
+    fn from_components(
+        position: Vector2<f32>,
+        rotation: f32,
+        scale: Vector2<f32>,
+        skew: f32,
+    ) -> Self;
+    /// Reals in Godot's `x.x, x.y, y.x, y.y, origin.x, origin.y` order
+    fn to_reals(&self) -> [f32; 6];
+    fn get_origin(&self) -> Vector2<f32>;
+    fn get_rotation(&self) -> f32;
+    fn get_scale(&self) -> Vector2<f32>;
+    /// Transforms point by the transform
+    fn xform(&self, point: Vector2<f32>) -> Vector2<f32>;
+    /// Interpolates origin, rotation and scale separately, the same way Godot does
+    fn interpolate_with(&self, other: &Self, weight: f32) -> Self;
+}
+
+impl Transform2DTrait for Transform2D {
+    fn from_reals(r: [f32; 6]) -> Self {
+        Matrix3::new(r[0], r[2], r[4], r[1], r[3], r[5], 0.0, 0.0, 1.0)
+    }
+
+    fn from_rotation(rotation: f32, origin: Vector2<f32>) -> Self {
+        let (sin, cos) = rotation.sin_cos();
+
+        Self::from_reals([cos, sin, -sin, cos, origin.x, origin.y])
+    }
+
+    fn from_components(
+        position: Vector2<f32>,
+        rotation: f32,
+        scale: Vector2<f32>,
+        skew: f32,
+    ) -> Self {
+        let (sin, cos) = rotation.sin_cos();
+        let (skew_sin, skew_cos) = (rotation + skew).sin_cos();
+
+        Self::from_reals([
+            cos * scale.x,
+            sin * scale.x,
+            -skew_sin * scale.y,
+            skew_cos * scale.y,
+            position.x,
+            position.y,
+        ])
+    }
+
+    fn to_reals(&self) -> [f32; 6] {
+        [
+            self[(0, 0)],
+            self[(1, 0)],
+            self[(0, 1)],
+            self[(1, 1)],
+            self[(0, 2)],
+            self[(1, 2)],
+        ]
+    }
+
+    fn get_origin(&self) -> Vector2<f32> {
+        Vector2::new(self[(0, 2)], self[(1, 2)])
+    }
+
+    fn get_rotation(&self) -> f32 {
+        self[(1, 0)].atan2(self[(0, 0)])
+    }
+
+    fn get_scale(&self) -> Vector2<f32> {
+        let det_sign = (self[(0, 0)] * self[(1, 1)] - self[(0, 1)] * self[(1, 0)]).signum();
+        let x = Vector2::new(self[(0, 0)], self[(1, 0)]);
+        let y = Vector2::new(self[(0, 1)], self[(1, 1)]);
+
+        Vector2::new(x.norm(), det_sign * y.norm())
+    }
+
+    fn xform(&self, point: Vector2<f32>) -> Vector2<f32> {
+        self.transform_point(&Point2::from(point)).coords
+    }
+
+    fn interpolate_with(&self, other: &Self, weight: f32) -> Self {
+        let (r1, r2) = (self.get_rotation(), other.get_rotation());
+        let v1 = Vector2::new(r1.cos(), r1.sin());
+        let v2 = Vector2::new(r2.cos(), r2.sin());
+        let dot = v1.dot(&v2).clamp(-1.0, 1.0);
+
+        let v = if dot > 0.9995 {
+            v1.lerp(&v2, weight).normalize()
+        } else {
+            let angle = weight * dot.acos();
+            let v3 = (v2 - v1 * dot).normalize();
+            v1 * angle.cos() + v3 * angle.sin()
+        };
+
+        let mut result = Self::from_rotation(
+            v.y.atan2(v.x),
+            self.get_origin().lerp(&other.get_origin(), weight),
+        );
+        let scale = self.get_scale().lerp(&other.get_scale(), weight);
+        for row in 0..2 {
+            result[(row, 0)] *= scale.x;
+            result[(row, 1)] *= scale.y;
+        }
+
+        result
+    }
+}
+
+pub trait Transform3DTrait {
+    /// Builds transform from Godot's basis rows followed by origin (`Transform( ... )` order)
+    fn from_reals(reals: [f32; 12]) -> Self;
+    /// Builds transform from basis (columns are the axes) and origin
+    fn from_basis(basis: Matrix3<f32>, origin: Vector3<f32>) -> Self;
+    /// Reals in Godot's `Transform( ... )` order
+    fn to_reals(&self) -> [f32; 12];
+    fn get_basis(&self) -> Matrix3<f32>;
+    fn get_origin(&self) -> Vector3<f32>;
+    /// Euler angles (in radians) in Godot's YXZ convention, scale is ignored
+    fn get_euler(&self) -> Vector3<f32>;
+    fn get_scale(&self) -> Vector3<f32>;
+    /// Transforms point by the transform
+    fn xform(&self, point: Vector3<f32>) -> Vector3<f32>;
+}
+
+impl Transform3DTrait for Transform3D {
+    fn from_reals(r: [f32; 12]) -> Self {
+        let basis = Matrix3::new(r[0], r[1], r[2], r[3], r[4], r[5], r[6], r[7], r[8]);
+
+        Self::from_basis(basis, Vector3::new(r[9], r[10], r[11]))
+    }
+
+    fn from_basis(basis: Matrix3<f32>, origin: Vector3<f32>) -> Self {
+        let mut transform = basis.to_homogeneous();
+        transform[(0, 3)] = origin.x;
+        transform[(1, 3)] = origin.y;
+        transform[(2, 3)] = origin.z;
+
+        transform
+    }
+
+    fn to_reals(&self) -> [f32; 12] {
+        let mut reals = [0.0; 12];
+        for row in 0..3 {
+            for column in 0..3 {
+                reals[row * 3 + column] = self[(row, column)];
+            }
+            reals[9 + row] = self[(row, 3)];
+        }
+
+        reals
+    }
+
+    fn get_basis(&self) -> Matrix3<f32> {
+        self.fixed_slice::<nalgebra::U3, nalgebra::U3>(0, 0)
+            .into_owned()
+    }
+
+    fn get_origin(&self) -> Vector3<f32> {
+        Vector3::new(self[(0, 3)], self[(1, 3)], self[(2, 3)])
+    }
+
+    fn get_euler(&self) -> Vector3<f32> {
+        // Gram-Schmidt on the axes, mirrored back when the basis flips
+        let basis = self.get_basis();
+        let x = basis.column(0).normalize();
+        let y = (basis.column(1) - x * x.dot(&basis.column(1))).normalize();
+        let z = (basis.column(2) - x * x.dot(&basis.column(2)) - y * y.dot(&basis.column(2)))
+            .normalize();
+        let mut m = Matrix3::from_columns(&[x, y, z]);
+        if basis.determinant() < 0.0 {
+            m = -m;
+        }
+
+        let m12 = m[(1, 2)];
+        if m12 >= 1.0 {
+            Vector3::new(
+                -std::f32::consts::FRAC_PI_2,
+                -m[(0, 1)].atan2(m[(0, 0)]),
+                0.0,
+            )
+        } else if m12 <= -1.0 {
+            Vector3::new(std::f32::consts::FRAC_PI_2, m[(0, 1)].atan2(m[(0, 0)]), 0.0)
+        } else if m[(1, 0)] == 0.0
+            && m[(0, 1)] == 0.0
+            && m[(0, 2)] == 0.0
+            && m[(2, 0)] == 0.0
+            && m[(0, 0)] == 1.0
+        {
+            // Pure X rotation, in its simplest form
+            Vector3::new((-m12).atan2(m[(1, 1)]), 0.0, 0.0)
+        } else {
+            Vector3::new(
+                (-m12).asin(),
+                m[(0, 2)].atan2(m[(2, 2)]),
+                m[(1, 0)].atan2(m[(1, 1)]),
+            )
+        }
+    }
+
+    fn get_scale(&self) -> Vector3<f32> {
+        let basis = self.get_basis();
+        let det_sign = basis.determinant().signum();
+
+        Vector3::new(
+            basis.column(0).norm(),
+            basis.column(1).norm(),
+            basis.column(2).norm(),
+        ) * det_sign
+    }
+
+    fn xform(&self, point: Vector3<f32>) -> Vector3<f32> {
+        self.transform_point(&Point3::from(point)).coords
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::types::transform::*;
+
+    use std::f32::consts::FRAC_PI_2;
+
+    #[test]
+    fn test_interpolate_with() {
+        let from = Transform2D::from_components(Vector2::zeros(), 0.0, Vector2::new(2.0, 1.0), 0.0);
+        let to = Transform2D::from_components(
+            Vector2::new(10.0, 0.0),
+            FRAC_PI_2,
+            Vector2::new(2.0, 1.0),
+            0.0,
+        );
+
+        let half = from.interpolate_with(&to, 0.5);
+        let expected = Transform2D::from_components(
+            Vector2::new(5.0, 0.0),
+            FRAC_PI_2 / 2.0,
+            Vector2::new(2.0, 1.0),
+            0.0,
+        );
+        assert!((half - expected).norm() < 1e-5, "{}", half);
+        assert!((to.interpolate_with(&to, 0.5) - to).norm() < 1e-5);
+        // `x` axis keeps its length of 2 after the rotation
+        assert!((half.xform(Vector2::new(1.0, 0.0)) - Vector2::new(6.414, 1.414)).norm() < 1e-3);
+    }
+}
@@ -1,28 +1,175 @@
-use crate::types::Curve;
+use crate::str_helper::ConstructorHelper;
+use crate::types::{Curve, Transform2D, Transform3D, Transform3DTrait};
 
-use nalgebra::Vector2;
+use nalgebra::{Matrix3, Vector2};
 use std::collections::HashMap;
 use std::convert::TryFrom;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum VarType {
     Num(isize),
     Bool(bool),
     Float(f32),
     Str(String),
     Curve(Curve),
-    Rect2([Vector2<f32>; 2]),
+    Rect2(
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde_helper::rect2"))]
+        [Vector2<f32>; 2],
+    ),
+    Transform2D(
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde_helper::transform2d"))]
+        Transform2D,
+    ),
+    /// 3x3 matrix, columns are the `x`, `y` and `z` axes
+    Basis(#[cfg_attr(feature = "serde", serde(with = "crate::serde_helper::basis"))] Matrix3<f32>),
+    Transform3D(
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde_helper::transform3d"))]
+        Transform3D,
+    ),
     IntArr(Vec<isize>),
+    StrArr(Vec<String>),
     FloatArr(Vec<f32>),
-    Vector(Vector2<f32>),
-    VectorArr(Vec<Vector2<f32>>),
+    Vector(
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde_helper::vector2"))] Vector2<f32>,
+    ),
+    VectorArr(
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde_helper::vector2_vec"))]
+        Vec<Vector2<f32>>,
+    ),
     ArrMap(Vec<HashMap<String, VarType>>),
+    /// Nested properties (i.e. `tracks/0/type`)
     Map(HashMap<String, VarType>),
+    /// Dictionary literal (i.e. `_data = { ... }`)
+    Dict(HashMap<String, VarType>),
     SubResource(usize),
     ExtResource(usize),
+    Arr(Vec<VarType>),
     None(String),
 }
 
+/// Built-in Variant types as spelled by Godot 4, array types aside
+const VARIANT_TYPES: &[&str] = &[
+    "bool",
+    "int",
+    "float",
+    "String",
+    "StringName",
+    "NodePath",
+    "Vector2",
+    "Vector2i",
+    "Rect2",
+    "Rect2i",
+    "Vector3",
+    "Vector3i",
+    "Vector4",
+    "Vector4i",
+    "Transform2D",
+    "Plane",
+    "Quaternion",
+    "AABB",
+    "Basis",
+    "Transform3D",
+    "Projection",
+    "Color",
+    "RID",
+    "Callable",
+    "Signal",
+    "Dictionary",
+];
+
+/// Godot 4 name of the Variant type, Godot 3 names (i.e. `Transform`, `PoolIntArray`) renamed
+fn variant_name(name: &str) -> &str {
+    match name {
+        "real" => "float",
+        "Quat" => "Quaternion",
+        "Transform" => "Transform3D",
+        "PoolByteArray" => "PackedByteArray",
+        "PoolIntArray" => "PackedInt32Array",
+        "PoolRealArray" => "PackedFloat32Array",
+        "PoolStringArray" => "PackedStringArray",
+        "PoolVector2Array" => "PackedVector2Array",
+        "PoolVector3Array" => "PackedVector3Array",
+        "PoolColorArray" => "PackedColorArray",
+        name => name,
+    }
+}
+
+/// `Array`, typed `Array[int]` or a packed array
+fn is_array_type(name: &str) -> bool {
+    name == "Array" || name.starts_with("Array[") || name.starts_with("Packed")
+}
+
+impl VarType {
+    /// Godot 4 Variant type of the value (`int`, `Vector2`, `PackedInt32Array`, ...), `Object`
+    /// for resource references and `Nil` for `null`. Values which weren't parsed are named by
+    /// their constructor, `None` when it can't be told (i.e. nested properties)
+    pub fn variant_type(&self) -> Option<&str> {
+        Some(match self {
+            VarType::Num(_) => "int",
+            VarType::Bool(_) => "bool",
+            VarType::Float(_) => "float",
+            VarType::Str(_) => "String",
+            VarType::Rect2(_) => "Rect2",
+            VarType::Transform2D(_) => "Transform2D",
+            VarType::Basis(_) => "Basis",
+            VarType::Transform3D(_) => "Transform3D",
+            VarType::IntArr(_) => "PackedInt32Array",
+            VarType::StrArr(_) => "PackedStringArray",
+            VarType::FloatArr(_) => "PackedFloat32Array",
+            VarType::Vector(_) => "Vector2",
+            VarType::VectorArr(_) => "PackedVector2Array",
+            VarType::Arr(_) | VarType::ArrMap(_) => "Array",
+            VarType::Dict(_) => "Dictionary",
+            VarType::SubResource(_) | VarType::ExtResource(_) => "Object",
+            VarType::None(text) if text == "null" => "Nil",
+            VarType::None(text) if text.starts_with("&\"") => "StringName",
+            VarType::None(text) if text.starts_with("^\"") => "NodePath",
+            VarType::None(text) => {
+                let constructor = text.split('(').next().unwrap_or("").trim();
+                let constructor = constructor.split('[').next().unwrap_or("");
+                if !constructor.starts_with(|c: char| c.is_ascii_uppercase())
+                    || !constructor.chars().all(|c| c.is_ascii_alphanumeric())
+                {
+                    return None;
+                }
+
+                variant_name(constructor)
+            }
+            VarType::Curve(_) | VarType::Map(_) => return None,
+        })
+    }
+
+    /// Text of a String or a Godot 4 StringName (`&"name"`) value
+    pub fn string_name(&self) -> Option<&str> {
+        match self {
+            VarType::Str(string) => Some(string),
+            VarType::None(text) if text.len() >= 3 && text.starts_with("&\"") => {
+                text[2..].strip_suffix('"')
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether the value fits the Variant type `type_name`, spelled the GDScript or ClassDB
+    /// way of Godot 3 or 4. Integers fit floats, any array fits any array type and `null` fits
+    /// no built-in type. `None` for object (class) or unknown types, or unrecognized values
+    pub fn fits_type(&self, type_name: &str) -> Option<bool> {
+        let expected = variant_name(type_name.trim());
+        if !VARIANT_TYPES.contains(&expected) && !is_array_type(expected) {
+            return None;
+        }
+
+        let actual = self.variant_type()?;
+        Some(match (expected, actual) {
+            _ if expected == actual => true,
+            ("float", "int") => true,
+            ("String", "StringName") | ("StringName", "String") => true,
+            _ => is_array_type(expected) && is_array_type(actual),
+        })
+    }
+}
+
 impl TryFrom<VarType> for isize {
     type Error = ();
 
@@ -118,3 +265,73 @@ impl TryFrom<&VarType> for bool {
         }
     }
 }
+
+impl TryFrom<&VarType> for String {
+    type Error = ();
+
+    fn try_from(value: &VarType) -> Result<Self, Self::Error> {
+        if let VarType::Str(string) = value {
+            Ok(string.clone())
+        } else {
+            Err(())
+        }
+    }
+}
+
+impl TryFrom<&VarType> for Vector2<f32> {
+    type Error = ();
+
+    fn try_from(value: &VarType) -> Result<Self, Self::Error> {
+        if let VarType::Vector(vector) = value {
+            Ok(*vector)
+        } else {
+            Err(())
+        }
+    }
+}
+
+impl TryFrom<&VarType> for [Vector2<f32>; 2] {
+    type Error = ();
+
+    fn try_from(value: &VarType) -> Result<Self, Self::Error> {
+        if let VarType::Rect2(rect) = value {
+            Ok(*rect)
+        } else {
+            Err(())
+        }
+    }
+}
+
+impl TryFrom<&VarType> for Transform3D {
+    type Error = ();
+
+    /// Also reads Godot 4 `Transform3D(...)` text
+    fn try_from(value: &VarType) -> Result<Self, Self::Error> {
+        match value {
+            VarType::Transform3D(transform) => Ok(*transform),
+            VarType::None(text) if text.starts_with("Transform3D(") => {
+                let reals = text.constructor_reals();
+                let mut array = [0.0; 12];
+                if reals.len() != array.len() {
+                    return Err(());
+                }
+                array.copy_from_slice(&reals);
+
+                Ok(Transform3D::from_reals(array))
+            }
+            _ => Err(()),
+        }
+    }
+}
+
+impl TryFrom<&VarType> for Transform2D {
+    type Error = ();
+
+    fn try_from(value: &VarType) -> Result<Self, Self::Error> {
+        if let VarType::Transform2D(transform) = value {
+            Ok(*transform)
+        } else {
+            Err(())
+        }
+    }
+}
@@ -0,0 +1,271 @@
+use crate::tscn_helper::TscnHelper;
+use crate::types::VarType;
+
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Godot 4 resource unique identifier (i.e. `uid://cecaux1sm7mo0`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Uid(i64);
+
+impl Uid {
+    pub const PREFIX: &'static str = "uid://";
+
+    const CHAR_COUNT: u64 = (b'z' - b'a') as u64;
+    const BASE: u64 = Self::CHAR_COUNT + (b'9' - b'0') as u64;
+
+    /// Creates uid from its numeric value, negative values are invalid
+    pub fn new(id: i64) -> Option<Self> {
+        if id < 0 {
+            return None;
+        }
+
+        Some(Uid(id))
+    }
+
+    pub fn id(&self) -> i64 {
+        self.0
+    }
+
+    /// Decodes base-34 text form, same as `ResourceUID::text_to_id`
+    pub fn from_text(text: &str) -> Option<Self> {
+        if !text.starts_with(Self::PREFIX) {
+            return None;
+        }
+
+        let mut uid: u64 = 0;
+        for ch in text[Self::PREFIX.len()..].bytes() {
+            let value = match ch {
+                b'a'..=b'z' => (ch - b'a') as u64,
+                b'0'..=b'9' => (ch - b'0') as u64 + Self::CHAR_COUNT,
+                _ => return None,
+            };
+
+            uid = uid.wrapping_mul(Self::BASE).wrapping_add(value);
+        }
+
+        Some(Uid((uid & 0x7FFF_FFFF_FFFF_FFFF) as i64))
+    }
+
+    /// Encodes into base-34 text form, same as `ResourceUID::id_to_text`
+    pub fn to_text(&self) -> String {
+        let mut id = self.0 as u64;
+        let mut chars: Vec<char> = Vec::new();
+
+        // At least one digit, zero is `uid://a`
+        loop {
+            let c = (id % Self::BASE) as u8;
+            if (c as u64) < Self::CHAR_COUNT {
+                chars.push((b'a' + c) as char);
+            } else {
+                chars.push((b'0' + c - Self::CHAR_COUNT as u8) as char);
+            }
+            id /= Self::BASE;
+
+            if id == 0 {
+                break;
+            }
+        }
+
+        chars.reverse();
+        format!("{}{}", Self::PREFIX, chars.into_iter().collect::<String>())
+    }
+}
+
+impl fmt::Display for Uid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_text())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Uid {
+    fn serialize<S: serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_str(&self.to_text())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Uid {
+    fn deserialize<D: serde::Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(d)?;
+
+        text.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+impl FromStr for Uid {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_text(s).ok_or(format!("Invalid uid `{}`", s))
+    }
+}
+
+/// Uid to resource path mapping, as kept by the editor in `.godot/uid_cache.bin`
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UidCache {
+    paths: HashMap<Uid, String>,
+}
+
+impl UidCache {
+    pub fn new() -> Self {
+        UidCache::default()
+    }
+
+    /// Loads `uid_cache.bin` file
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let bytes = fs::read(path).map_err(|e| e.to_string())?;
+
+        Self::from_bytes(&bytes)
+    }
+
+    /// Reads binary cache: entry count followed by `(id: u64, length: u32, path)` entries
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let mut cache = UidCache::new();
+        let mut offset = 0;
+
+        let count = Self::read_u32(bytes, &mut offset)?;
+        for _ in 0..count {
+            let id = Self::read_u64(bytes, &mut offset)? as i64;
+            let length = Self::read_u32(bytes, &mut offset)? as usize;
+
+            if offset + length > bytes.len() {
+                return Err("Unexpected end of uid cache".to_string());
+            }
+
+            let path = String::from_utf8_lossy(&bytes[offset..offset + length])
+                .trim_end_matches('\0')
+                .to_string();
+            offset += length;
+
+            if let Some(uid) = Uid::new(id) {
+                cache.insert(uid, path);
+            }
+        }
+
+        Ok(cache)
+    }
+
+    /// Writes cache in the same layout as `uid_cache.bin`
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+
+        bytes.extend_from_slice(&(self.paths.len() as u32).to_le_bytes());
+        for (uid, path) in &self.paths {
+            bytes.extend_from_slice(&(uid.id() as u64).to_le_bytes());
+            bytes.extend_from_slice(&(path.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(path.as_bytes());
+        }
+
+        bytes
+    }
+
+    pub fn insert(&mut self, uid: Uid, path: String) {
+        self.paths.insert(uid, path);
+    }
+
+    pub fn extend(&mut self, other: UidCache) {
+        self.paths.extend(other.paths);
+    }
+
+    pub fn get(&self, uid: &Uid) -> Option<&String> {
+        self.paths.get(uid)
+    }
+
+    /// Resolves text uid (i.e. `uid://cecaux1sm7mo0`) to resource path
+    pub fn resolve(&self, text: &str) -> Option<&String> {
+        self.get(&Uid::from_text(text)?)
+    }
+
+    pub fn len(&self) -> usize {
+        self.paths.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+
+    /// Registers uid of an imported resource from its `.import` file contents
+    pub fn add_import(&mut self, contents: &str) -> Option<Uid> {
+        let mut uid: Option<Uid> = None;
+        let mut source_file: Option<String> = None;
+
+        for line in contents.lines() {
+            if let Some(command) = TscnHelper::parse_command(line, "") {
+                match (command.lhs.as_str(), command.rhs) {
+                    ("uid", VarType::Str(text)) => uid = Uid::from_text(&text),
+                    ("source_file", VarType::Str(path)) => source_file = Some(path),
+                    _ => (),
+                }
+            }
+        }
+
+        let uid = uid?;
+        self.insert(uid, source_file?);
+
+        Some(uid)
+    }
+
+    fn read_u32(bytes: &[u8], offset: &mut usize) -> Result<u32, String> {
+        let slice = bytes
+            .get(*offset..*offset + 4)
+            .ok_or("Unexpected end of uid cache")?;
+        *offset += 4;
+
+        Ok(u32::from_le_bytes(slice.try_into().unwrap()))
+    }
+
+    fn read_u64(bytes: &[u8], offset: &mut usize) -> Result<u64, String> {
+        let slice = bytes
+            .get(*offset..*offset + 8)
+            .ok_or("Unexpected end of uid cache")?;
+        *offset += 8;
+
+        Ok(u64::from_le_bytes(slice.try_into().unwrap()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::uid::*;
+
+    #[test]
+    fn test_uid_text() {
+        let uid = Uid::from_text("uid://cecaux1sm7mo0").unwrap();
+        assert_eq!(uid.to_text(), "uid://cecaux1sm7mo0");
+        assert_eq!(Uid::new(34).unwrap().to_text(), "uid://ba");
+        assert_eq!(Uid::from_text("uid://ba"), Uid::new(34));
+        assert_eq!(Uid::new(0).unwrap().to_text(), "uid://a");
+        assert_eq!(Uid::from_text("uid://a"), Uid::new(0));
+        assert_eq!(Uid::from_text("uid://<invalid>"), None);
+        assert_eq!(Uid::from_text("res://icon.png"), None);
+        assert_eq!(Uid::new(-1), None);
+    }
+
+    #[test]
+    fn test_uid_cache() {
+        let mut cache = UidCache::new();
+        let uid = Uid::from_text("uid://cecaux1sm7mo0").unwrap();
+        cache.insert(uid, "res://icon.svg".to_string());
+
+        let cache = UidCache::from_bytes(&cache.to_bytes()).unwrap();
+        assert_eq!(
+            cache.resolve("uid://cecaux1sm7mo0"),
+            Some(&"res://icon.svg".to_string())
+        );
+
+        let mut cache = UidCache::new();
+        let import = "[remap]\n\nimporter=\"texture\"\nuid=\"uid://ba\"\n\n[deps]\n\nsource_file=\"res://player.png\"\n";
+        assert_eq!(cache.add_import(import), Uid::new(34));
+        assert_eq!(
+            cache.resolve("uid://ba"),
+            Some(&"res://player.png".to_string())
+        );
+    }
+}